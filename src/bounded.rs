@@ -0,0 +1,371 @@
+//! Module containing an image-aware wrapper around an `ImgXY2Celestial` transform,
+//! i.e. a transform knowing the size of the image it applies to (`NAXIS1`, `NAXIS2`).
+//! It provides the usual "where is my image?" operations: containment test, footprint,
+//! bounding cone, pixel scale and pixel solid angles.
+
+use crate::{CanonicalProjection, Cone, ImgXY, LonLat, XYZ};
+use crate::img2celestial::{Img2Celestial, ImgXY2Celestial};
+use crate::img2proj::ImgXY2ProjXY;
+use crate::sphere::{ang_dist, arc_middle, orientation, polygon_area, smallest_enclosing_cone};
+
+/// Convention used for the pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelConvention {
+  /// FITS convention: the center of the first pixel is at `(1, 1)`, so the image covers
+  /// `[0.5, NAXIS + 0.5]` (to be used with e.g. `WcsImgXY2ProjXY`).
+  Fits,
+  /// The center of the first pixel is at `(0, 0)`, so the image covers
+  /// `[-0.5, NAXIS - 0.5]` (to be used with e.g. `BasicImgXY2ProjXY`).
+  ZeroBased,
+}
+
+impl PixelConvention {
+  /// Returns the coordinate of the center of the first pixel.
+  pub fn first_pixel_center(&self) -> f64 {
+    match self {
+      PixelConvention::Fits => 1.0,
+      PixelConvention::ZeroBased => 0.0,
+    }
+  }
+}
+
+/// Default maximum distance, in pixels, between the true image edges and the great-circle arcs
+/// of the footprint polygon.
+pub const DEFAULT_FOOTPRINT_TOL: f64 = 0.1;
+
+/// Maximum number of recursive subdivisions of each image edge when building the footprint.
+const FOOTPRINT_MAX_DEPTH: u8 = 10;
+/// Minimum number of recursive subdivisions of each image edge when building the footprint
+/// (each edge is thus made of at least 4 arcs).
+const FOOTPRINT_MIN_DEPTH: u8 = 2;
+
+/// Image of `naxis1 x naxis2` pixels together with the transform from its pixel coordinates
+/// to celestial coordinates.
+/// # Remark
+/// * the `ImgXY2Celestial` implementation simply delegates to the inner transform, so it also
+///   accepts pixel coordinates outside of the image.
+pub struct BoundedImg2Celestial<T: ImgXY2Celestial> {
+  img2celestial: T,
+  naxis1: u32,
+  naxis2: u32,
+  convention: PixelConvention,
+}
+
+impl<T: ImgXY2Celestial> BoundedImg2Celestial<T> {
+
+  /// # Params
+  /// * `img2celestial`: the transform from image to celestial coordinates
+  /// * `naxis1`: number of pixels along the image x-axis (value of the `NAXIS1` keyword)
+  /// * `naxis2`: number of pixels along the image y-axis (value of the `NAXIS2` keyword)
+  /// * `convention`: pixel convention used by `img2celestial`
+  /// # Panics
+  /// * if `naxis1` or `naxis2` equals 0
+  pub fn new(img2celestial: T, naxis1: u32, naxis2: u32, convention: PixelConvention) -> Self {
+    assert!(naxis1 > 0 && naxis2 > 0);
+    Self { img2celestial, naxis1, naxis2, convention }
+  }
+
+  /// Returns the inner transform.
+  pub fn img2celestial(&self) -> &T {
+    &self.img2celestial
+  }
+
+  /// Returns the number of pixels along the image x-axis.
+  pub fn naxis1(&self) -> u32 {
+    self.naxis1
+  }
+
+  /// Returns the number of pixels along the image y-axis.
+  pub fn naxis2(&self) -> u32 {
+    self.naxis2
+  }
+
+  /// Returns the pixel convention.
+  pub fn convention(&self) -> PixelConvention {
+    self.convention
+  }
+
+  /// Returns the coordinates of the center of the pixel of given indices.
+  /// # Params
+  /// * `i`: index of the pixel along the x-axis, in `[0, naxis1[`
+  /// * `j`: index of the pixel along the y-axis, in `[0, naxis2[`
+  pub fn pixel_center(&self, i: u32, j: u32) -> ImgXY {
+    let first = self.convention.first_pixel_center();
+    ImgXY::new(first + i as f64, first + j as f64)
+  }
+
+  /// Returns the range covered by the image along the x-axis (from the edge of the first pixel
+  /// to the edge of the last pixel).
+  pub fn x_edges(&self) -> (f64, f64) {
+    let first = self.convention.first_pixel_center();
+    (first - 0.5, first - 0.5 + self.naxis1 as f64)
+  }
+
+  /// Returns the range covered by the image along the y-axis (from the edge of the first pixel
+  /// to the edge of the last pixel).
+  pub fn y_edges(&self) -> (f64, f64) {
+    let first = self.convention.first_pixel_center();
+    (first - 0.5, first - 0.5 + self.naxis2 as f64)
+  }
+
+  /// Returns the coordinates of the center of the image.
+  pub fn img_center(&self) -> ImgXY {
+    let (xmin, xmax) = self.x_edges();
+    let (ymin, ymax) = self.y_edges();
+    ImgXY::new(0.5 * (xmin + xmax), 0.5 * (ymin + ymax))
+  }
+
+  /// Returns the celestial position of the center of the image.
+  pub fn center(&self) -> Option<LonLat> {
+    self.img2celestial.img2lonlat(&self.img_center())
+  }
+
+  /// Returns `true` if the given image coordinates are inside the image (edges included).
+  pub fn contains_img(&self, img_pos: &ImgXY) -> bool {
+    let (xmin, xmax) = self.x_edges();
+    let (ymin, ymax) = self.y_edges();
+    (xmin..=xmax).contains(&img_pos.x()) && (ymin..=ymax).contains(&img_pos.y())
+  }
+
+  /// Returns `true` if the given celestial position falls inside the image.
+  pub fn contains_lonlat(&self, lonlat: &LonLat) -> bool {
+    self.contains_xyz(&lonlat.to_xyz())
+  }
+
+  /// Returns `true` if the given celestial position falls inside the image.
+  pub fn contains_xyz(&self, xyz: &XYZ) -> bool {
+    self.img2celestial.xyz2img(xyz)
+      .map(|img_pos| self.contains_img(&img_pos))
+      .unwrap_or(false)
+  }
+
+  /// Returns the footprint of the image on the sky, i.e. the polygon (made of great-circle arcs)
+  /// following the image edges.
+  /// The edges are recursively subdivided until the middle of each great-circle arc
+  /// falls at less than `tol` pixels from the true image edge, so that the footprint is exact
+  /// also for non-linear projections.
+  /// The vertices are given counter-clockwise when seen from the inside of the sphere (i.e. as
+  /// seen on a sky image with the North up and the East on the left), the polygon being implicitly
+  /// closed (the last vertex is not a copy of the first one).
+  /// # Params
+  /// * `tol`: tolerance, in pixels (see e.g. `DEFAULT_FOOTPRINT_TOL`)
+  /// # Return
+  /// * `None` if a part of the image edges is outside the valid projection area.
+  pub fn footprint(&self, tol: f64) -> Option<Vec<LonLat>> {
+    self.footprint_xyz(tol).map(|vertices| vertices.iter().map(|v| v.to_lonlat()).collect())
+  }
+
+  /// Same as `footprint`, but returning Euclidean coordinates.
+  pub fn footprint_xyz(&self, tol: f64) -> Option<Vec<XYZ>> {
+    let (xmin, xmax) = self.x_edges();
+    let (ymin, ymax) = self.y_edges();
+    let corners = [
+      ImgXY::new(xmin, ymin),
+      ImgXY::new(xmax, ymin),
+      ImgXY::new(xmax, ymax),
+      ImgXY::new(xmin, ymax),
+    ];
+    let corners_xyz = corners.iter()
+      .map(|c| self.img2celestial.img2xyz(c))
+      .collect::<Option<Vec<XYZ>>>()?;
+    let mut vertices = Vec::new();
+    for i in 0..4 {
+      let j = (i + 1) % 4;
+      vertices.push(corners_xyz[i].clone());
+      self.densify_edge(&corners[i], &corners_xyz[i], &corners[j], &corners_xyz[j], tol, 0, &mut vertices)?;
+    }
+    let center = self.img2celestial.img2xyz(&self.img_center())?;
+    if orientation(&vertices, &center) > 0.0 {
+      vertices.reverse();
+    }
+    Some(vertices)
+  }
+
+  /// Push in `vertices` the points (excluding `a` and `b`) needed to follow the image edge
+  /// from `a` to `b` with great-circle arcs.
+  #[allow(clippy::too_many_arguments)]
+  fn densify_edge(
+    &self,
+    a: &ImgXY, a_xyz: &XYZ,
+    b: &ImgXY, b_xyz: &XYZ,
+    tol: f64, depth: u8,
+    vertices: &mut Vec<XYZ>
+  ) -> Option<()> {
+    if depth >= FOOTPRINT_MAX_DEPTH {
+      return Some(());
+    }
+    let m = ImgXY::new(0.5 * (a.x() + b.x()), 0.5 * (a.y() + b.y()));
+    let m_xyz = self.img2celestial.img2xyz(&m)?;
+    let split = depth < FOOTPRINT_MIN_DEPTH || arc_middle(a_xyz, b_xyz)
+      .and_then(|arc_m| self.img2celestial.xyz2img(&arc_m))
+      .map(|arc_m| (arc_m.x() - m.x()).hypot(arc_m.y() - m.y()) > tol)
+      .unwrap_or(true);
+    if split {
+      self.densify_edge(a, a_xyz, &m, &m_xyz, tol, depth + 1, vertices)?;
+      vertices.push(m_xyz.clone());
+      self.densify_edge(&m, &m_xyz, b, b_xyz, tol, depth + 1, vertices)?;
+    }
+    Some(())
+  }
+
+  /// Returns the smallest cone containing the image footprint (computed with the default
+  /// tolerance).
+  /// # Remark
+  /// * if the radius of the cone is larger than `pi/2`, the returned cone is not
+  ///   necessarily the smallest one.
+  pub fn bounding_cone(&self) -> Option<Cone> {
+    let vertices = self.footprint_xyz(DEFAULT_FOOTPRINT_TOL)?;
+    smallest_enclosing_cone(&vertices).map(|(center, radius)| Cone::new(center.to_lonlat(), radius))
+  }
+
+  /// Returns the pixel scale at the center of the image, i.e. the angular distance, in radians,
+  /// between the two sides of the central pixel along the x-axis and along the y-axis.
+  /// # Return
+  /// * `(scale_x, scale_y)`, in radians per pixel
+  pub fn pixel_scale_at_center(&self) -> Option<(f64, f64)> {
+    let c = self.img_center();
+    let x_scale = ang_dist(
+      &self.img2celestial.img2xyz(&ImgXY::new(c.x() - 0.5, c.y()))?,
+      &self.img2celestial.img2xyz(&ImgXY::new(c.x() + 0.5, c.y()))?
+    );
+    let y_scale = ang_dist(
+      &self.img2celestial.img2xyz(&ImgXY::new(c.x(), c.y() - 0.5))?,
+      &self.img2celestial.img2xyz(&ImgXY::new(c.x(), c.y() + 0.5))?
+    );
+    Some((x_scale, y_scale))
+  }
+
+  /// Returns the exact solid angle, in steradians, of each pixel of the image (see
+  /// `Img2Celestial::pixel_solid_angle`), the pixel `(i, j)` being at index `j * naxis1 + i`.
+  /// Each pixel corner is deprojected only once.
+  /// # Remark
+  /// * the value is `NaN` for pixels having a corner out of the projection domain.
+  pub fn solid_angle_map(&self) -> Vec<f64> {
+    let (x0, y0) = (self.x_edges().0, self.y_edges().0);
+    let corners_row = |j: u32| -> Vec<Option<XYZ>> {
+      (0..=self.naxis1)
+        .map(|i| self.img2celestial.img2xyz(&ImgXY::new(x0 + i as f64, y0 + j as f64)))
+        .collect()
+    };
+    let mut map = Vec::with_capacity(self.naxis1 as usize * self.naxis2 as usize);
+    let mut bottom = corners_row(0);
+    for j in 0..self.naxis2 {
+      let top = corners_row(j + 1);
+      map.extend((0..self.naxis1 as usize).map(|i| {
+        match (&bottom[i], &bottom[i + 1], &top[i + 1], &top[i]) {
+          (Some(a), Some(b), Some(c), Some(d)) => polygon_area(&[a.clone(), b.clone(), c.clone(), d.clone()]),
+          _ => f64::NAN,
+        }
+      }));
+      bottom = top;
+    }
+    map
+  }
+}
+
+impl<P: CanonicalProjection, W: ImgXY2ProjXY> BoundedImg2Celestial<Img2Celestial<P, W>> {
+
+  /// Same as `solid_angle_map`, but using the Jacobian approximation of the solid angle of each
+  /// pixel (see `Img2Celestial::pixel_solid_angle_approx`).
+  /// # Remark
+  /// * the value is `NaN` for pixels having their center out of the projection domain.
+  pub fn solid_angle_map_approx(&self) -> Vec<f64> {
+    (0..self.naxis2)
+      .flat_map(|j| (0..self.naxis1).map(move |i| (i, j)))
+      .map(|(i, j)| self.img2celestial.pixel_solid_angle_approx(&self.pixel_center(i, j)).unwrap_or(f64::NAN))
+      .collect()
+  }
+}
+
+impl<T: ImgXY2Celestial> ImgXY2Celestial for BoundedImg2Celestial<T> {
+  fn img2xyz(&self, img_pos: &ImgXY) -> Option<XYZ> {
+    self.img2celestial.img2xyz(img_pos)
+  }
+  fn xyz2img(&self, xyz: &XYZ) -> Option<ImgXY> {
+    self.img2celestial.xyz2img(xyz)
+  }
+  fn img2lonlat(&self, img_pos: &ImgXY) -> Option<LonLat> {
+    self.img2celestial.img2lonlat(img_pos)
+  }
+  fn lonlat2img(&self, lonlat: &LonLat) -> Option<ImgXY> {
+    self.img2celestial.lonlat2img(lonlat)
+  }
+}
+
+/// Test fixture: a `TAN` image, with square pixels and the East on the left, following the
+/// `FITS` pixel convention.
+/// # Params
+/// * `center`: position of the reference pixel
+/// * `crpix`: `(CRPIX1, CRPIX2)`
+/// * `crota2`: `CROTA2`, in degrees
+/// * `cdelt`: pixel size, in degrees
+/// * `naxis`: `(NAXIS1, NAXIS2)`
+#[cfg(test)]
+pub(crate) fn tan_image(
+  center: &LonLat,
+  crpix: (f64, f64),
+  crota2: f64,
+  cdelt: f64,
+  naxis: (u32, u32)
+) -> BoundedImg2Celestial<Img2Celestial<crate::zenithal::tan::Tan, crate::img2proj::WcsImgXY2ProjXY>> {
+  use crate::{CenteredProjection, img2proj::WcsImgXY2ProjXY, zenithal::tan::Tan};
+  let img2proj = WcsImgXY2ProjXY::from_cr(crpix.0, crpix.1, crota2, -cdelt, cdelt);
+  let mut proj = CenteredProjection::new(Tan::new());
+  proj.set_proj_center_from_lonlat(center);
+  BoundedImg2Celestial::new(Img2Celestial::new(img2proj, proj), naxis.0, naxis.1, PixelConvention::Fits)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_bounded_tan() {
+    let img = tan_image(&LonLat::new(10_f64.to_radians(), 20_f64.to_radians()), (60.5, 30.5), 0.0, 1.0 / 60.0, (120, 60));
+    let center = img.center().unwrap();
+    assert!((center.lon().to_degrees() - 10.0).abs() < 1e-12);
+    assert!((center.lat().to_degrees() - 20.0).abs() < 1e-12);
+    assert!(img.contains_lonlat(&center));
+    assert!(!img.contains_lonlat(&LonLat::new(10_f64.to_radians(), 21_f64.to_radians())));
+    assert!(img.contains_lonlat(&LonLat::new(10_f64.to_radians(), 20.4_f64.to_radians())));
+
+    let (sx, sy) = img.pixel_scale_at_center().unwrap();
+    assert!((sx.to_degrees() * 60.0 - 1.0).abs() < 1e-6);
+    assert!((sy.to_degrees() * 60.0 - 1.0).abs() < 1e-6);
+
+    let footprint = img.footprint_xyz(DEFAULT_FOOTPRINT_TOL).unwrap();
+    assert!(footprint.len() >= 16);
+    assert!(orientation(&footprint, &center.to_xyz()) < 0.0);
+
+    // Half diagonal of a 2 x 1 deg field
+    let cone = img.bounding_cone().unwrap();
+    assert!((cone.radius().to_degrees() - 1.25_f64.sqrt()).abs() < 1e-3);
+    assert!(footprint.iter().all(|v| ang_dist(&cone.center().to_xyz(), v) <= cone.radius() + 1e-12));
+  }
+
+  #[test]
+  fn test_solid_angle() {
+    let img = tan_image(&LonLat::new(10_f64.to_radians(), 20_f64.to_radians()), (60.5, 30.5), 0.0, 1.0 / 60.0, (120, 60));
+    let pixel = (1_f64 / 60.0).to_radians();
+    let center = ImgXY::new(60.5, 30.5);
+    let (sx, sy, skew) = img.img2celestial().pixel_scale(&center).unwrap();
+    assert!((sx / pixel - 1.0).abs() < 1e-9 && (sy / pixel - 1.0).abs() < 1e-9 && skew.abs() < 1e-9);
+    let exact = img.img2celestial().pixel_solid_angle(&center).unwrap();
+    let approx = img.img2celestial().pixel_solid_angle_approx(&center).unwrap();
+    assert!((exact / (pixel * pixel) - 1.0).abs() < 1e-6 && (approx / (pixel * pixel) - 1.0).abs() < 1e-6);
+    // The pixels tile the footprint
+    let map = img.solid_angle_map();
+    assert_eq!(map.len(), 120 * 60);
+    let footprint = img.footprint_xyz(DEFAULT_FOOTPRINT_TOL).unwrap();
+    assert!((map.iter().sum::<f64>() / polygon_area(&footprint) - 1.0).abs() < 1e-6);
+    // Wide field (1 deg pixels): the solid angle decreases like cos^3 of the distance to the center
+    let img = tan_image(&LonLat::new(0.0, 0.0), (30.5, 30.5), 0.0, 1.0, (60, 60));
+    let (exact, approx) = (img.solid_angle_map(), img.solid_angle_map_approx());
+    for (e, a) in exact.iter().zip(approx.iter()) {
+      assert!((a / e - 1.0).abs() < 1e-3);
+    }
+    let theta = ang_dist(&img.img2xyz(&img.pixel_center(0, 0)).unwrap(), &img.img2xyz(&ImgXY::new(30.5, 30.5)).unwrap());
+    let ratio = exact[0] / img.img2celestial().pixel_solid_angle(&ImgXY::new(30.5, 30.5)).unwrap();
+    assert!((ratio / theta.cos().powi(3) - 1.0).abs() < 1e-3);
+  }
+}
@@ -2,7 +2,7 @@
  
 use std::f64::consts::PI;
 
-use crate::{CustomFloat, CanonicalProjection, ProjXY, XYZ, math::HALF_PI, conic::Conic, ProjBounds};
+use crate::{lonlat_unproj_jacobian, CustomFloat, CanonicalProjection, ProjXY, XYZ, math::HALF_PI, conic::{conic_derivatives, sector_boundary, Conic}, ProjBounds};
 
 /// Conic Equidistant projection.
 #[derive(Debug, Clone)]
@@ -76,6 +76,22 @@ impl CanonicalProjection for Cod {
     &self.proj_bounds
   }
 
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    // r = ThetaA + Y0 - lat
+    Some(sector_boundary(self.c, self.y0, self.ta_plus_y0 + HALF_PI, self.ta_plus_y0 - HALF_PI, n))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    const EPS: f64 = 1.0e-14;
+    let x2d = pos.x;
+    let y2d = self.y0 - pos.y;
+    let r2 = x2d.pow2() + y2d.pow2();
+    (self.r2_min..=self.r2_max).contains(&r2) && {
+      let r = if self.conic.negative_ta { -(r2.sqrt()) } else { r2.sqrt() };
+      (-PI - EPS..PI + EPS).contains(&((x2d / r).atan2(y2d / r) / self.c))
+    }
+  }
+
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     let lon = xyz.y.atan2(xyz.x);
     // more computations but ore accurate than r = self.ta_plus_y0 - z.asin()
@@ -105,4 +121,11 @@ impl CanonicalProjection for Cod {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // lat = ta + y0 - r
+    let xyz = self.unproj(pos)?;
+    let (_, dlon, dr) = conic_derivatives(pos, self.y0, self.c, self.conic.negative_ta)?;
+    lonlat_unproj_jacobian(&xyz, dlon, [-dr[0], -dr[1]])
+  }
 }
@@ -2,7 +2,7 @@
 
 use std::f64::consts::PI;
 
-use crate::{CustomFloat, CanonicalProjection, ProjXY, XYZ, conic::Conic, ProjBounds};
+use crate::{lonlat_unproj_jacobian, CustomFloat, CanonicalProjection, ProjXY, XYZ, conic::{conic_derivatives, sector_boundary, Conic}, ProjBounds};
 use crate::math::HALF_PI;
 
 /// Conic Equal Area projection.
@@ -81,6 +81,26 @@ impl CanonicalProjection for Coe {
     &self.proj_bounds
   }
 
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    // r = sqrt(1 + sin(t1)sin(t2) - gamma * sin(lat)) / c
+    let r_south = (self.one_plus_sint1_sint2 + self.gamma).sqrt() / self.c;
+    let r_north = (self.one_plus_sint1_sint2 - self.gamma).max(0.0).sqrt() / self.c;
+    Some(sector_boundary(self.c, self.y0, r_south, r_north, n))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    const EPS: f64 = 1.0e-14;
+    let x2d = pos.x;
+    let y2d = self.y0 - pos.y;
+    let r2 = x2d.pow2() + y2d.pow2();
+    (self.r2_min..=self.r2_max).contains(&r2)
+      && (-1.0..1.0).contains(&((self.one_plus_sint1_sint2 - self.c2 * r2) / self.gamma))
+      && {
+        let r = if self.conic.negative_ta { -(r2.sqrt()) } else { r2.sqrt() };
+        (-PI - EPS..PI + EPS).contains(&((x2d / r).atan2(y2d / r) / self.c))
+      }
+  }
+
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     let r = self.one_plus_sint1_sint2 - self.gamma * xyz.z;
     if r >= 0.0 {
@@ -117,4 +137,13 @@ impl CanonicalProjection for Coe {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // sin(lat) = (1 + sin(t1) sin(t2) - c^2 r^2) / gamma
+    let xyz = self.unproj(pos)?;
+    let (r, dlon, dr) = conic_derivatives(pos, self.y0, self.c, self.conic.negative_ta)?;
+    let cosb = (xyz.x.pow2() + xyz.y.pow2()).sqrt();
+    let dlat_dr = -(self.c2 * r).twice() / (self.gamma * cosb);
+    lonlat_unproj_jacobian(&xyz, dlon, [dlat_dr * dr[0], dlat_dr * dr[1]])
+  }
 }
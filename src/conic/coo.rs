@@ -2,7 +2,7 @@
 
 use std::f64::consts::PI;
 
-use crate::{CustomFloat, CanonicalProjection, ProjXY, XYZ, math::HALF_PI, conic::Conic, ProjBounds};
+use crate::{lonlat_unproj_jacobian, CustomFloat, CanonicalProjection, ProjXY, XYZ, math::HALF_PI, conic::{conic_derivatives, Conic}, ProjBounds};
 
 /// Conic Orthomorphic projection.
 pub struct Coo {
@@ -62,6 +62,19 @@ impl CanonicalProjection for Coo {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    None // Infinite radius at the pole opposite to the cone apex
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    const EPS: f64 = 1.0e-14;
+    let x2d = pos.x;
+    let y2d = self.y0 - pos.y;
+    let r2 = x2d.pow2() + y2d.pow2();
+    let r = if self.conic.negative_ta { -(r2.sqrt()) } else { r2.sqrt() };
+    (-PI - EPS..PI + EPS).contains(&((x2d / r).atan2(y2d / r) / self.c))
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     let lon = xyz.y.atan2(xyz.x);
@@ -87,4 +100,13 @@ impl CanonicalProjection for Coo {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // lat = pi/2 - 2 atan(t), with t = (r / psi)^(1/c)
+    let xyz = self.unproj(pos)?;
+    let (r, dlon, dr) = conic_derivatives(pos, self.y0, self.c, self.conic.negative_ta)?;
+    let t = (r / self.psi).powf(self.one_over_c);
+    let dlat_dr = -(t / ((1.0 + t.pow2()) * self.c * r)).twice();
+    lonlat_unproj_jacobian(&xyz, dlon, [dlat_dr * dr[0], dlat_dr * dr[1]])
+  }
 }
@@ -2,7 +2,7 @@
 
 use std::f64::consts::PI;
 
-use crate::{CustomFloat, CanonicalProjection, ProjXY, XYZ, math::HALF_PI, conic::Conic, ProjBounds};
+use crate::{lonlat_unproj_jacobian, CustomFloat, CanonicalProjection, ProjXY, XYZ, math::HALF_PI, conic::{conic_derivatives, Conic}, ProjBounds};
 
 /// Conic perspective projection.
 pub struct Cop {
@@ -69,6 +69,19 @@ impl CanonicalProjection for Cop {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    None // Infinite radius at ThetaA -+ PI/2
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    const EPS: f64 = 1.0e-14;
+    let x2d = pos.x;
+    let y2d = self.y0 - pos.y;
+    let r2 = x2d.pow2() + y2d.pow2();
+    let r = if self.conic.negative_ta { -(r2.sqrt()) } else { r2.sqrt() };
+    (-PI - EPS..PI + EPS).contains(&((x2d / r).atan2(y2d / r) / self.c))
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     if self.is_lat_in_domain_of_validity(xyz.z) {
@@ -113,6 +126,14 @@ impl CanonicalProjection for Cop {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // lat = ta + atan(cot(ta) - r / cos(nu))
+    let xyz = self.unproj(pos)?;
+    let (r, dlon, dr) = conic_derivatives(pos, self.y0, self.c, self.conic.negative_ta)?;
+    let dlat_dr = -1.0 / (self.cos_nu * (1.0 + (self.cotan_ta - r / self.cos_nu).pow2()));
+    lonlat_unproj_jacobian(&xyz, dlon, [dlat_dr * dr[0], dlat_dr * dr[1]])
+  }
 }
 
 fn is_close_to_one(x: f64) -> bool {
@@ -6,6 +6,10 @@ pub mod coe;
 pub mod coo;
 pub mod cop;
 
+use std::f64::consts::PI;
+
+use crate::{closed_ccw_polyline, CustomFloat, ProjXY};
+
 #[derive(Debug, Clone)]
 struct Conic {
   /// Parameters `ThetaA` (in radians)
@@ -37,4 +41,42 @@ impl Conic {
     }
   }
 
-}
\ No newline at end of file
+}
+/// Returns the boundary of the valid area of a conic projection: the closed counter-clockwise
+/// polyline bounding the annular sector made of the points `(r sin(c lon), y0 - r cos(c lon))`,
+/// with `lon` in `[-PI, PI]` and the (signed) radius `r` in `[r_south, r_north]`.
+/// # Params
+/// * `n`: number of segments approximating each of the two arcs
+fn sector_boundary(c: f64, y0: f64, r_south: f64, r_north: f64, n: usize) -> Vec<ProjXY> {
+  let n = n.max(1);
+  let arc = |r: f64, reverse: bool| -> Vec<ProjXY> {
+    if r == 0.0 { // Cone apex
+      return vec![ProjXY::new(0.0, y0)];
+    }
+    (0..=n)
+      .map(|i| {
+        let lon = PI * (2.0 * i as f64 / n as f64 - 1.0);
+        let (sinc, cosc) = (c * if reverse { -lon } else { lon }).sin_cos();
+        ProjXY::new(r * sinc, y0 - r * cosc)
+      })
+      .collect()
+  };
+  let mut vertices = arc(r_south, false);
+  vertices.extend(arc(r_north, true));
+  closed_ccw_polyline(vertices)
+}
+
+/// Returns, at the given position in the projection plane, the (signed) radius `r` of a conic
+/// projection, and the derivatives `[dlon/dX, dlon/dY]` and `[dr/dX, dr/dY]`
+/// (`None` at the cone apex).
+fn conic_derivatives(pos: &ProjXY, y0: f64, c: f64, negative_ta: bool) -> Option<(f64, [f64; 2], [f64; 2])> {
+  let (x2d, y2d) = (pos.x, y0 - pos.y);
+  let rho2 = x2d.pow2() + y2d.pow2();
+  if rho2 == 0.0 {
+    None
+  } else {
+    let rho = rho2.sqrt();
+    let s = if negative_ta { -1.0 } else { 1.0 };
+    Some((s * rho, [y2d / (c * rho2), x2d / (c * rho2)], [s * x2d / rho, -s * y2d / rho]))
+  }
+}
@@ -0,0 +1,416 @@
+//! Module containing the projection of curves sampled on the sphere, split at the borders of the
+//! projection valid area and at the projection discontinuities (e.g. the `lon = 180` cut of
+//! all-sky projections).
+//!
+//! A discontinuity between two consecutive samples is detected by bisection: the arc is
+//! recursively split in two, keeping the half having the largest projected length. For a
+//! continuous projection, the projected length tends toward zero, while it remains roughly
+//! constant at a jump.
+//!
+//! The pieces of a split polygon are closed along the boundary of the projection valid area
+//! (on which the discontinuities of the supported projections lie), walking counter-clockwise
+//! from the end of a piece to the start of the next one, the inside of the polygon being kept
+//! on the left.
+
+use std::f64::consts::PI;
+
+use crate::XYZ;
+use crate::sphere::{arc_middle, great_arc_points, is_in_polygon, outside_point};
+
+/// Number of bisections used to locate a discontinuity or the border of the valid area.
+const N_BISECTIONS: u8 = 30;
+/// A segment is suspected to contain a jump if one of its halves carries more than this fraction
+/// of its projected length.
+const SUSPECT_RATIO: f64 = 0.75;
+/// After `N_BISECTIONS`, a segment contains a jump if its projected length is still larger than
+/// this fraction of the initial projected length.
+const JUMP_RATIO: f64 = 1.0e-3;
+
+/// Maximum number of recursive subdivisions of each segment when densifying a curve.
+const MAX_DENSIFY_DEPTH: u8 = 8;
+
+/// Maximum angular distance, in radians, between two consecutive points sampling a great-circle
+/// arc before its densification.
+const ARC_SAMPLING_STEP: f64 = PI / 180.0;
+
+/// Number of segments approximating each curved part of the projection boundary used to close
+/// the projected polygons.
+pub(crate) const BOUNDARY_N_SEGMENTS: usize = 720;
+
+/// Densifies the given curve: each arc between two consecutive points is recursively subdivided
+/// until the projection of its middle falls at less than `tol` from the middle of the projected
+/// chord (or until `MAX_DENSIFY_DEPTH` is reached).
+/// Arcs whose projection fails are not subdivided.
+/// # Params
+/// * `points`: the points sampling the curve
+/// * `closed`: `true` if the last point is connected to the first one
+/// * `f`: the projection function
+/// * `tol`: the tolerance, in the unit of the projected coordinates
+pub(crate) fn densify<F>(points: &[XYZ], closed: bool, f: F, tol: f64) -> Vec<XYZ>
+  where F: Fn(&XYZ) -> Option<(f64, f64)>
+{
+  #[allow(clippy::too_many_arguments)]
+  fn rec<F>(a: &XYZ, pa: (f64, f64), b: &XYZ, pb: (f64, f64), f: &F, tol: f64, depth: u8, res: &mut Vec<XYZ>)
+    where F: Fn(&XYZ) -> Option<(f64, f64)>
+  {
+    if depth >= MAX_DENSIFY_DEPTH {
+      return;
+    }
+    if let Some(m) = arc_middle(a, b) {
+      if let Some(pm) = f(&m) {
+        let err = (pm.0 - 0.5 * (pa.0 + pb.0)).hypot(pm.1 - 0.5 * (pa.1 + pb.1));
+        if err > tol {
+          rec(a, pa, &m, pm, f, tol, depth + 1, res);
+          res.push(m.clone());
+          rec(&m, pm, b, pb, f, tol, depth + 1, res);
+        }
+      }
+    }
+  }
+  let n = points.len();
+  let n_segments = if closed { n } else { n.saturating_sub(1) };
+  let mut res = Vec::with_capacity(n);
+  for k in 0..n {
+    res.push(points[k].clone());
+    if k < n_segments {
+      let (a, b) = (&points[k], &points[(k + 1) % n]);
+      if let (Some(pa), Some(pb)) = (f(a), f(b)) {
+        rec(a, pa, b, pb, &f, tol, 0, &mut res);
+      }
+    }
+  }
+  res
+}
+
+/// Projects the given curve.
+/// # Params
+/// * `points`: the points sampling the curve (supposedly close enough to each other)
+/// * `closed`: `true` if the curve is closed (the last point being connected to the first one,
+///   without being repeated)
+/// * `f`: the projection function
+/// # Return
+/// * the projected pieces of the curve, each containing at least 2 points; an unsplit closed curve
+///   is returned as a single piece repeating its first point at the end
+pub(crate) fn project_curve<F>(points: &[XYZ], closed: bool, f: F) -> Vec<Vec<(f64, f64)>>
+  where F: Fn(&XYZ) -> Option<(f64, f64)>
+{
+  let n = points.len();
+  if n < 2 {
+    return vec![];
+  }
+  let projected: Vec<Option<(f64, f64)>> = points.iter().map(&f).collect();
+  let n_segments = if closed { n } else { n - 1 };
+  let mut pieces: Vec<Vec<(f64, f64)>> = Vec::new();
+  let mut current: Vec<(f64, f64)> = Vec::new();
+  let mut split = false;
+  let flush = |current: &mut Vec<(f64, f64)>, pieces: &mut Vec<Vec<(f64, f64)>>| {
+    if current.len() >= 2 {
+      pieces.push(std::mem::take(current));
+    } else {
+      current.clear();
+    }
+  };
+  if let Some(p) = projected[0] {
+    current.push(p);
+  }
+  // Tells whether the last segment (ending on the first point) is continuous
+  let mut last_continuous = false;
+  for k in 0..n_segments {
+    let (a, b) = (&points[k], &points[(k + 1) % n]);
+    last_continuous = false;
+    match (projected[k], projected[(k + 1) % n]) {
+      (Some(pa), Some(pb)) => match find_jump(a, b, pa, pb, &f) {
+        None => {
+          current.push(pb);
+          last_continuous = true;
+        },
+        Some((end, start)) => {
+          current.extend(end);
+          flush(&mut current, &mut pieces);
+          current.extend(start);
+          current.push(pb);
+          split = true;
+        },
+      },
+      (Some(_), None) => {
+        current.extend(last_valid(a, b, &f));
+        flush(&mut current, &mut pieces);
+        split = true;
+      },
+      (None, Some(pb)) => {
+        current.extend(last_valid(b, a, &f));
+        current.push(pb);
+        split = true;
+      },
+      (None, None) => split = true,
+    }
+  }
+  flush(&mut current, &mut pieces);
+  // Merge the last piece with the first one if the curve is closed and continuous at its first point
+  if closed && split && last_continuous && pieces.len() >= 2 && projected[0].is_some() {
+    let first = pieces.remove(0);
+    pieces.last_mut().unwrap().extend_from_slice(&first[1..]);
+  }
+  pieces
+}
+
+/// Projects the polyline made of the given vertices connected by great-circle arcs, splitting it
+/// at the discontinuities and densifying it (see `project_curve` and `densify`).
+/// # Params
+/// * `vertices`: the polyline vertices
+/// * `closed`: `true` if the last vertex is connected to the first one (without being repeated)
+/// * `f`: the projection function
+/// * `tol`: the densification tolerance, in the unit of the projected coordinates
+pub(crate) fn project_arcs<F>(vertices: &[XYZ], closed: bool, f: F, tol: f64) -> Vec<Vec<(f64, f64)>>
+  where F: Fn(&XYZ) -> Option<(f64, f64)>
+{
+  let n = vertices.len();
+  let n_arcs = if closed { n } else { n.saturating_sub(1) };
+  let mut points: Vec<XYZ> = (0..n_arcs)
+    .flat_map(|k| great_arc_points(&vertices[k], &vertices[(k + 1) % n], ARC_SAMPLING_STEP)
+      // Undefined arc between antipodal vertices
+      .unwrap_or_else(|| vec![vertices[k].clone()]))
+    .collect();
+  if !closed {
+    points.extend(vertices.last().cloned());
+  }
+  let points = densify(&points, closed, &f, tol);
+  project_curve(&points, closed, &f)
+}
+
+/// Projects the polygon made of the given vertices connected by great-circle arcs (the polygon
+/// being the part of the sphere, smaller than a hemisphere, delimited by the arcs).
+/// The pieces of a split polygon are closed along the given boundary of the projection valid area.
+/// # Params
+/// * `vertices`: the polygon vertices (the first one not being repeated)
+/// * `f`: the projection function
+/// * `f_inv`: the deprojection function
+/// * `boundary`: the closed counter-clockwise boundary of the valid projection area (`None` if unbounded)
+/// * `tol`: the densification tolerance, in the unit of the projected coordinates
+/// # Return
+/// * closed rings (the first point being repeated at the end), except if the polygon is split
+///   and `boundary` is `None` or the inside of the polygon can't be determined, in which case
+///   the open pieces are returned unchanged
+pub(crate) fn project_polygon<F, G>(
+  vertices: &[XYZ],
+  f: F,
+  f_inv: G,
+  boundary: Option<&[(f64, f64)]>,
+  tol: f64
+) -> Vec<Vec<(f64, f64)>>
+  where
+    F: Fn(&XYZ) -> Option<(f64, f64)>,
+    G: Fn(&(f64, f64)) -> Option<XYZ>
+{
+  let mut pieces = project_arcs(vertices, true, &f, tol);
+  let unsplit = pieces.len() == 1 && pieces[0].first() == pieces[0].last();
+  let (boundary, outside) = match (boundary, outside_point(vertices)) {
+    (Some(boundary), Some(outside)) if !unsplit && !pieces.is_empty() => (boundary, outside),
+    _ => return pieces,
+  };
+  // Look, at the points of the pieces, for the side of the polygon inside
+  let is_inside = |p: &(f64, f64)| f_inv(p).map(|xyz| is_in_polygon(vertices, &outside, &xyz));
+  let inside_on_left = pieces.iter()
+    .flat_map(|piece| piece.windows(3))
+    .find_map(|w| {
+      let (dx, dy) = (w[2].0 - w[0].0, w[2].1 - w[0].1);
+      let eps = 1.0e-3;
+      let (lx, ly) = (-dy * eps, dx * eps);
+      match (is_inside(&(w[1].0 + lx, w[1].1 + ly)), is_inside(&(w[1].0 - lx, w[1].1 - ly))) {
+        (Some(left), Some(right)) if left != right => Some(left),
+        _ => None,
+      }
+    });
+  match inside_on_left {
+    Some(true) => {},
+    Some(false) => pieces.iter_mut().for_each(|piece| piece.reverse()),
+    None => return pieces,
+  }
+  close_along_boundary(&pieces, boundary)
+}
+
+/// Closes the given pieces, having the inside of the polygon on their left, along the given closed
+/// counter-clockwise boundary on which the pieces start and end.
+fn close_along_boundary(pieces: &[Vec<(f64, f64)>], boundary: &[(f64, f64)]) -> Vec<Vec<(f64, f64)>> {
+  let dist = |p: &(f64, f64), q: &(f64, f64)| (p.0 - q.0).hypot(p.1 - q.1);
+  // Curvilinear abscissa of each boundary vertex
+  let mut abscissa = Vec::with_capacity(boundary.len());
+  let mut len = 0.0;
+  for (k, p) in boundary.iter().enumerate() {
+    if k > 0 {
+      len += dist(&boundary[k - 1], p);
+    }
+    abscissa.push(len);
+  }
+  // Curvilinear abscissa of the point of the boundary the closest to the given point
+  let locate = |p: &(f64, f64)| -> f64 {
+    boundary.windows(2).zip(abscissa.iter())
+      .map(|(w, s)| {
+        let (dx, dy) = (w[1].0 - w[0].0, w[1].1 - w[0].1);
+        let l2 = dx * dx + dy * dy;
+        let t = if l2 > 0.0 { (((p.0 - w[0].0) * dx + (p.1 - w[0].1) * dy) / l2).clamp(0.0, 1.0) } else { 0.0 };
+        (dist(p, &(w[0].0 + t * dx, w[0].1 + t * dy)), s + t * l2.sqrt())
+      })
+      .min_by(|a, b| a.0.total_cmp(&b.0))
+      .map(|(_, s)| s)
+      .unwrap_or(0.0)
+  };
+  // Distance, going counter-clockwise along the boundary, from `from` to `to`
+  let ccw_dist = |from: f64, to: f64| (to - from).rem_euclid(len);
+  let starts: Vec<f64> = pieces.iter().map(|piece| locate(&piece[0])).collect();
+  let ends: Vec<f64> = pieces.iter().map(|piece| locate(piece.last().unwrap())).collect();
+  let mut visited = vec![false; pieces.len()];
+  let mut rings = Vec::new();
+  for first in 0..pieces.len() {
+    if visited[first] {
+      continue;
+    }
+    visited[first] = true;
+    let mut ring = pieces[first].clone();
+    let mut current = first;
+    loop {
+      let end = ends[current];
+      // Next piece start met going counter-clockwise along the boundary
+      let next = (0..pieces.len())
+        .filter(|j| !visited[*j] || *j == first)
+        .min_by(|a, b| ccw_dist(end, starts[*a]).total_cmp(&ccw_dist(end, starts[*b])))
+        .unwrap();
+      let d_next = ccw_dist(end, starts[next]);
+      let mut path: Vec<(f64, &(f64, f64))> = abscissa.iter().zip(boundary.iter())
+        .take(boundary.len() - 1)
+        .map(|(s, p)| (ccw_dist(end, *s), p))
+        .filter(|(d, _)| *d > 0.0 && *d < d_next)
+        .collect();
+      path.sort_by(|a, b| a.0.total_cmp(&b.0));
+      ring.extend(path.into_iter().map(|(_, p)| *p));
+      if next == first {
+        ring.push(ring[0]);
+        break;
+      }
+      visited[next] = true;
+      ring.extend_from_slice(&pieces[next]);
+      current = next;
+    }
+    rings.push(ring);
+  }
+  rings
+}
+
+/// Looks for a discontinuity in the arc `[a, b]`, returning the projection of the two points
+/// (if valid) on each side of the discontinuity.
+#[allow(clippy::type_complexity)]
+fn find_jump<F>(
+  a: &XYZ, b: &XYZ, pa: (f64, f64), pb: (f64, f64), f: &F
+) -> Option<(Option<(f64, f64)>, Option<(f64, f64)>)>
+  where F: Fn(&XYZ) -> Option<(f64, f64)>
+{
+  let dist = |p: (f64, f64), q: (f64, f64)| (p.0 - q.0).hypot(p.1 - q.1);
+  let l0 = dist(pa, pb);
+  if l0 == 0.0 {
+    return None;
+  }
+  let (mut a, mut b, mut pa, mut pb) = (a.clone(), b.clone(), pa, pb);
+  for i in 0..N_BISECTIONS {
+    let m = arc_middle(&a, &b)?;
+    match f(&m) {
+      None => return Some((last_valid(&a, &m, f), last_valid(&b, &m, f))),
+      Some(pm) => {
+        let (l, l1, l2) = (dist(pa, pb), dist(pa, pm), dist(pm, pb));
+        if i == 0 && l1.max(l2) <= SUSPECT_RATIO * l {
+          return None;
+        }
+        if l1 >= l2 {
+          b = m;
+          pb = pm;
+        } else {
+          a = m;
+          pa = pm;
+        }
+      },
+    }
+  }
+  if dist(pa, pb) > JUMP_RATIO * l0 {
+    Some((Some(pa), Some(pb)))
+  } else {
+    None
+  }
+}
+
+/// Returns the projection of the point of the arc `[valid, invalid]` closest to the border of
+/// the valid area (`valid` having a valid projection and `invalid` not).
+fn last_valid<F>(valid: &XYZ, invalid: &XYZ, f: &F) -> Option<(f64, f64)>
+  where F: Fn(&XYZ) -> Option<(f64, f64)>
+{
+  let (mut a, mut b) = (valid.clone(), invalid.clone());
+  let mut pa = f(&a)?;
+  for _ in 0..N_BISECTIONS {
+    let m = arc_middle(&a, &b)?;
+    match f(&m) {
+      Some(pm) => {
+        a = m;
+        pa = pm;
+      },
+      None => b = m,
+    }
+  }
+  Some(pa)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{LonLat, ProjXY, Projection};
+  use crate::cylindrical::car::Car;
+  use crate::math::HALF_PI;
+  use crate::pseudocyl::{ait::Ait, mol::Mol};
+
+  #[test]
+  fn test_project_curve() {
+    // Parallel at lat = 30 deg, crossing the lon = 180 deg cut of the Mollweide projection
+    let proj = Mol::new();
+    let points: Vec<XYZ> = (0..360)
+      .map(|k| LonLat::new((k as f64).to_radians(), 30_f64.to_radians()).to_xyz())
+      .collect();
+    let pieces = project_curve(&points, true, |xyz| proj.proj_xyz(xyz).map(|p| (p.x(), p.y())));
+    assert_eq!(pieces.len(), 1);
+    let piece = &pieces[0];
+    // From lon = 180 (x < 0) to lon = 180 (x > 0), going through lon = 0
+    assert!(piece[0].0 < 0.0 && piece.last().unwrap().0 > 0.0);
+    assert!((piece[0].0 + piece.last().unwrap().0).abs() < 1e-6);
+    assert_eq!(piece.len(), 362);
+  }
+
+  #[test]
+  fn test_proj_polyline_and_polygon() {
+    let xyz = |lon: f64, lat: f64| LonLat::new(lon.to_radians(), lat.to_radians()).to_xyz();
+    let twice_area = |ring: &[ProjXY]| ring.windows(2).map(|w| w[0].x() * w[1].y() - w[1].x() * w[0].y()).sum::<f64>();
+    let ait = Ait::new();
+    // Polyline crossing the lon = 180 deg cut
+    let pieces = ait.proj_polyline(&[xyz(170.0, 10.0), xyz(190.0, 10.0)], 1e-4);
+    assert_eq!(pieces.len(), 2);
+    assert!(pieces[0].iter().all(|p| p.x() > 0.0) && pieces[1].iter().all(|p| p.x() < 0.0));
+    // Polygon crossing the cut: closed on each side along the ellipse, whatever its orientation
+    let square = [xyz(170.0, -10.0), xyz(190.0, -10.0), xyz(190.0, 10.0), xyz(170.0, 10.0)];
+    let reversed: Vec<XYZ> = square.iter().rev().cloned().collect();
+    for vertices in [&square[..], &reversed[..]] {
+      let rings = ait.proj_polygon(vertices, 1e-4);
+      assert_eq!(rings.len(), 2);
+      for ring in rings.iter() {
+        assert_eq!(ring.first(), ring.last());
+        assert!(twice_area(ring) > 0.0);
+        assert!(ring.iter().all(|p| p.x().signum() == ring[0].x().signum()));
+      }
+    }
+    // Polygon around the North pole in CAR: closed along the top edge
+    let car = Car::new();
+    let cap: Vec<XYZ> = (0..4).map(|i| xyz(45.0 + 90.0 * i as f64, 80.0)).collect();
+    let rings = car.proj_polygon(&cap, 1e-4);
+    assert_eq!(rings.len(), 1);
+    assert!(rings[0].iter().any(|p| p.y() == HALF_PI));
+    assert!(rings[0].iter().all(|p| p.y() > 1.3));
+    // Polygon fully inside
+    let rings = car.proj_polygon(&[xyz(10.0, 0.0), xyz(20.0, 0.0), xyz(20.0, 10.0)], 1e-4);
+    assert_eq!(rings.len(), 1);
+    assert_eq!(rings[0].first(), rings[0].last());
+  }
+}
@@ -1,6 +1,6 @@
 //! Plate Carre projection.
 use std::f64::consts::PI;
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{closed_ccw_polyline, lonlat_unproj_jacobian, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
 use crate::math::HALF_PI;
 
 /// Plate Carre projection.
@@ -31,6 +31,19 @@ impl CanonicalProjection for Car {
     &PROJ_BOUNDS
   }
 
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    Some(closed_ccw_polyline(vec![
+      ProjXY::new(-PI, -HALF_PI),
+      ProjXY::new(PI, -HALF_PI),
+      ProjXY::new(PI, HALF_PI),
+      ProjXY::new(-PI, HALF_PI),
+    ]))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    (-PI..=PI).contains(&pos.x) && (-HALF_PI..=HALF_PI).contains(&pos.y)
+  }
+
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     // We do not use lat = asin(z) for precision purpose
     let r2 = xyz.x.pow2() + xyz.y.pow2();
@@ -47,4 +60,9 @@ impl CanonicalProjection for Car {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // lon = X, lat = Y
+    self.unproj(pos).and_then(|xyz| lonlat_unproj_jacobian(&xyz, [1.0, 0.0], [0.0, 1.0]))
+  }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 //! Cylindrical equal area projection.
 
 use std::f64::consts::PI;
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{closed_ccw_polyline, lonlat_unproj_jacobian, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
 
 /// Cylindrical equal area projection.
 /// With default value `lambda = 1`, this projection is a 
@@ -53,6 +53,20 @@ impl CanonicalProjection for Cea {
   fn bounds(&self) -> &ProjBounds {
     &self.proj_bounds
   }
+
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    let y = self.one_over_lambda.abs();
+    Some(closed_ccw_polyline(vec![
+      ProjXY::new(-PI, -y),
+      ProjXY::new(PI, -y),
+      ProjXY::new(PI, y),
+      ProjXY::new(-PI, y),
+    ]))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    (-PI..=PI).contains(&pos.x) && (-self.one_over_lambda..=self.one_over_lambda).contains(&pos.y)
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     Some(ProjXY::new(xyz.y.atan2(xyz.x), xyz.z * self.one_over_lambda))
@@ -68,4 +82,12 @@ impl CanonicalProjection for Cea {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // lon = X, sin(lat) = lambda Y
+    self.unproj(pos).and_then(|xyz| {
+      let cosb = (xyz.x.pow2() + xyz.y.pow2()).sqrt();
+      lonlat_unproj_jacobian(&xyz, [1.0, 0.0], [0.0, self.lambda / cosb])
+    })
+  }
 }
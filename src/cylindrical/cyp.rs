@@ -1,7 +1,7 @@
 //! Cylindrical perspective projection.
 
 use std::f64::consts::PI;
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{closed_ccw_polyline, lonlat_unproj_jacobian, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
 
 /// Cylindrical perspective projection.
 pub struct Cyp {
@@ -60,6 +60,22 @@ impl CanonicalProjection for Cyp {
   fn bounds(&self) -> &ProjBounds {
     &self.proj_bounds
   }
+
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    let x = self.pi_x_lambda.abs();
+    let y = self.lpm_over_mu.abs();
+    Some(closed_ccw_polyline(vec![
+      ProjXY::new(-x, -y),
+      ProjXY::new(x, -y),
+      ProjXY::new(x, y),
+      ProjXY::new(-x, y),
+    ]))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    (-self.pi_x_lambda..=self.pi_x_lambda).contains(&pos.x)
+      && (-self.lpm_over_mu..=self.lpm_over_mu).contains(&pos.y)
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     let r = (xyz.x.pow2() + xyz.y.pow2()).sqrt(); // more accurate than sqrt(1 - z^2)
@@ -70,8 +86,7 @@ impl CanonicalProjection for Cyp {
   }
 
   fn unproj(&self, pos: &ProjXY) -> Option<XYZ> {
-    if (-self.pi_x_lambda..=self.pi_x_lambda).contains(&pos.x) 
-      && (-self.lpm_over_mu..=self.lpm_over_mu).contains(&pos.y) {
+    if self.is_in_valid_proj_area(pos) {
       let (sinl, cosl) = (pos.x / self.lambda).sin_cos();
       let nu = pos.y / self.lpm;
       let t = (self.mu * nu) / (1.0 + nu.pow2()).sqrt();
@@ -84,4 +99,14 @@ impl CanonicalProjection for Cyp {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // lon = X / lambda, Y = (mu + lambda) sin(lat) / (mu + cos(lat))
+    // => dY/dlat = (mu + lambda) (1 + mu cos(lat)) / (mu + cos(lat))^2
+    self.unproj(pos).and_then(|xyz| {
+      let cosb = (xyz.x.pow2() + xyz.y.pow2()).sqrt();
+      let dlat_dy = (self.mu + cosb).pow2() / (self.lpm * (1.0 + self.mu * cosb));
+      lonlat_unproj_jacobian(&xyz, [1.0 / self.lambda, 0.0], [0.0, dlat_dy])
+    })
+  }
 }
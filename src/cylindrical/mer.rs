@@ -1,7 +1,7 @@
 //! Mercator projection.
 
 use std::f64::consts::PI;
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{lonlat_unproj_jacobian, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
 
 /// Mercator projection.
 pub struct Mer;
@@ -29,6 +29,14 @@ impl CanonicalProjection for Mer {
     &PROJ_BOUNDS
   }
 
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    None
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    (-PI..PI).contains(&pos.x)
+  }
+
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     if -1.0 < xyz.z && xyz.z < 1.0 {
       Some(ProjXY::new(xyz.y.atan2(xyz.x), xyz.z.atanh()))
@@ -47,4 +55,12 @@ impl CanonicalProjection for Mer {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // lon = X, Y = ln(tan(pi/4 + lat/2)) => dY/dlat = 1 / cos(lat)
+    self.unproj(pos).and_then(|xyz| {
+      let cosb = (xyz.x.pow2() + xyz.y.pow2()).sqrt();
+      lonlat_unproj_jacobian(&xyz, [1.0, 0.0], [0.0, cosb])
+    })
+  }
 }
\ No newline at end of file
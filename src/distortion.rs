@@ -0,0 +1,235 @@
+//! Module containing the analysis of the distortions of projections over a region of the sky, to
+//! choose the best projection to represent that region.
+//!
+//! At each position, the local distortion is characterized by the semi-axes `a >= b` of the
+//! Tissot indicatrix (see the `tissot` module), i.e. the maximum and minimum scale factors (in
+//! projection plane units per radian). The areal scale is `a b` and the maximum angular
+//! deformation is `2 asin((a - b) / (a + b))`.
+//! Over a region, the area (resp. scale) distortion is the relative difference between the areal
+//! scale (resp. the scale factors) at a position and at the region center.
+
+use std::f64::consts::{PI, TAU};
+
+use crate::{LonLat, Projection, XYZ};
+use crate::region::{SkyRegion, DEFAULT_STEP};
+use crate::sphere::{destination, smallest_enclosing_cone, tangent_frame};
+use crate::tissot::TissotIndicatrix;
+
+/// Number of rings of positions sampling the inside of the region.
+const N_RINGS: usize = 20;
+
+/// Criterion used to rank the projections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistortionCriterion {
+  /// Maximum area distortion (to be used for e.g. photometry or density maps).
+  Area,
+  /// Maximum angular deformation (to be used for e.g. morphology).
+  Angle,
+  /// Maximum scale variation (to be used for e.g. astrometry or distance measurements).
+  Scale,
+}
+
+/// Distortion statistics of a projection over a region.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistortionStats {
+  max_area: f64,
+  rms_area: f64,
+  max_angle: f64,
+  rms_angle: f64,
+  max_scale: f64,
+  rms_scale: f64,
+}
+
+impl DistortionStats {
+
+  /// Get the maximum relative area distortion, `|ab / (ab)_center - 1|`
+  pub fn max_area(&self) -> f64 {
+    self.max_area
+  }
+
+  /// Get the RMS of the relative area distortion
+  pub fn rms_area(&self) -> f64 {
+    self.rms_area
+  }
+
+  /// Get the maximum angular deformation, in radians
+  pub fn max_angle(&self) -> f64 {
+    self.max_angle
+  }
+
+  /// Get the RMS of the angular deformation, in radians
+  pub fn rms_angle(&self) -> f64 {
+    self.rms_angle
+  }
+
+  /// Get the maximum relative scale variation, `max(|a / s_center - 1|, |b / s_center - 1|)` with
+  /// `s_center = sqrt(ab)_center`
+  pub fn max_scale(&self) -> f64 {
+    self.max_scale
+  }
+
+  /// Get the RMS of the relative scale variation
+  pub fn rms_scale(&self) -> f64 {
+    self.rms_scale
+  }
+
+  /// Get the value of the given criterion
+  pub fn get(&self, criterion: DistortionCriterion) -> f64 {
+    match criterion {
+      DistortionCriterion::Area => self.max_area,
+      DistortionCriterion::Angle => self.max_angle,
+      DistortionCriterion::Scale => self.max_scale,
+    }
+  }
+}
+
+/// Result of the analysis of a list of candidate projections over a region.
+pub struct ProjectionRecommendation {
+  center: LonLat,
+  ranking: Vec<(usize, DistortionStats)>,
+}
+
+impl ProjectionRecommendation {
+
+  /// Get the recommended projection center (the center of the smallest cone containing the region)
+  pub fn center(&self) -> &LonLat {
+    &self.center
+  }
+
+  /// Get the candidates indices with their statistics, from the best to the worst; candidates
+  /// failing to project a position of the region are not part of the ranking
+  pub fn ranking(&self) -> &[(usize, DistortionStats)] {
+    &self.ranking
+  }
+
+  /// Get the index of the recommended projection (`None` if no candidate can project the region)
+  pub fn best(&self) -> Option<usize> {
+    self.ranking.first().map(|(i, _)| *i)
+  }
+}
+
+/// Analyses the distortions of candidate projections over the given region.
+/// # Params
+/// * `region`: the region
+/// * `candidates`: the candidate projections, supposedly not centered (i.e. centered on
+///   `(lon, lat) = (0, 0)`), since they are evaluated with the region center moved to `(0, 0)`
+/// * `criterion`: the criterion used to rank the projections
+/// # Return
+/// * `None` if the region is empty
+/// # Remark
+/// * to use the recommended projection, build a `CenteredProjection` and set its center to the
+///   recommended center.
+pub fn recommend_projection(
+  region: &SkyRegion,
+  candidates: &[&dyn Projection],
+  criterion: DistortionCriterion
+) -> Option<ProjectionRecommendation> {
+  let boundary: Vec<XYZ> = region.boundaries(DEFAULT_STEP).into_iter().flatten().collect();
+  let (center, radius) = match smallest_enclosing_cone(&boundary)? {
+    // The boundary of a region larger than a hemisphere is also the boundary of its complement
+    (c, r) if !region.contains_xyz(&c) => {
+      let opposite = XYZ::new(-c.x(), -c.y(), -c.z());
+      if region.contains_xyz(&opposite) { (opposite, PI - r) } else { (c, r) }
+    },
+    cone => cone,
+  };
+  let center = center.to_lonlat();
+  let c = center.to_xyz();
+  let mut points = vec![c.clone()];
+  for i in 1..=N_RINGS {
+    let r = radius * i as f64 / N_RINGS as f64;
+    let n = 6 * i;
+    points.extend((0..n).map(|j| destination(&c, r, TAU * j as f64 / n as f64)));
+  }
+  points.retain(|p| region.contains_xyz(p));
+  points.extend(boundary);
+  let rotation = Rotation::new(&c);
+  let mut ranking: Vec<(usize, DistortionStats)> = candidates.iter()
+    .enumerate()
+    .filter_map(|(i, proj)| distortion_stats(*proj, &rotation, &points).map(|stats| (i, stats)))
+    .collect();
+  ranking.sort_by(|(_, s1), (_, s2)| s1.get(criterion).total_cmp(&s2.get(criterion)));
+  Some(ProjectionRecommendation { center, ranking })
+}
+
+/// Computes the statistics of the given projection at the given positions, the first position
+/// being the center.
+fn distortion_stats(proj: &dyn Projection, rotation: &Rotation, points: &[XYZ]) -> Option<DistortionStats> {
+  let indicatrix0 = TissotIndicatrix::from_xyz(proj, &rotation.apply(&points[0]))?;
+  let (area0, scale0) = (indicatrix0.areal_scale(), indicatrix0.areal_scale().sqrt());
+  let (mut max_area, mut max_angle, mut max_scale) = (0.0_f64, 0.0_f64, 0.0_f64);
+  let (mut sum_area, mut sum_angle, mut sum_scale) = (0.0, 0.0, 0.0);
+  for p in points {
+    let indicatrix = TissotIndicatrix::from_xyz(proj, &rotation.apply(p))?;
+    let (a, b) = (indicatrix.a(), indicatrix.b());
+    let area = (indicatrix.areal_scale() / area0 - 1.0).abs();
+    let angle = indicatrix.max_angular_deformation();
+    let scale = (a / scale0 - 1.0).abs().max((b / scale0 - 1.0).abs());
+    max_area = max_area.max(area);
+    max_angle = max_angle.max(angle);
+    max_scale = max_scale.max(scale);
+    sum_area += area * area;
+    sum_angle += angle * angle;
+    sum_scale += scale * scale;
+  }
+  let n = points.len() as f64;
+  Some(DistortionStats {
+    max_area, rms_area: (sum_area / n).sqrt(),
+    max_angle, rms_angle: (sum_angle / n).sqrt(),
+    max_scale, rms_scale: (sum_scale / n).sqrt(),
+  })
+}
+
+/// Rotation moving a given center to `(lon, lat) = (0, 0)`, its (East, North) tangent frame
+/// (see `sphere::tangent_frame`) becoming the `(y, z)` frame.
+struct Rotation {
+  c: XYZ,
+  e: XYZ,
+  n: XYZ,
+}
+
+impl Rotation {
+
+  fn new(center: &XYZ) -> Self {
+    let (e, n) = tangent_frame(center);
+    Self { c: center.clone(), e, n }
+  }
+
+  fn apply(&self, p: &XYZ) -> XYZ {
+    XYZ::new_renorming_if_necessary(self.c.scalar(p), self.e.scalar(p), self.n.scalar(p))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Cone;
+  use crate::cylindrical::car::Car;
+  use crate::zenithal::{arc::Arc, sin::Sin, stg::Stg, tan::Tan, zea::Zea};
+
+  #[test]
+  fn test_recommend_projection() {
+    let (tan, zea, stg, sin, arc, car) = (Tan::new(), Zea::new(), Stg::new(), Sin::new(), Arc::new(), Car::new());
+    let candidates: [&dyn Projection; 6] = [&tan, &zea, &stg, &sin, &arc, &car];
+    // Wide field around the North pole
+    let region = SkyRegion::Cone(Cone::new(LonLat::new(1.0, 1.5), 30_f64.to_radians()));
+    let area = recommend_projection(&region, &candidates, DistortionCriterion::Area).unwrap();
+    assert!((area.center().lat() - 1.5).abs() < 1e-6);
+    // ZEA is equal-area
+    assert_eq!(area.best(), Some(1));
+    assert!(area.ranking()[0].1.max_area() < 1e-6);
+    // STG is conformal
+    let angle = recommend_projection(&region, &candidates, DistortionCriterion::Angle).unwrap();
+    assert_eq!(angle.best(), Some(2));
+    assert!(angle.ranking()[0].1.max_angle() < 1e-6);
+    // Analytic values at 30 deg from the center: ARC a = 1 / sinc(30 deg), b = 1
+    let stats = &angle.ranking().iter().find(|(i, _)| *i == 4).unwrap().1;
+    let a = 30_f64.to_radians() / 30_f64.to_radians().sin();
+    assert!((stats.max_scale() - (a - 1.0)).abs() < 1e-6);
+    assert!((stats.max_angle() - 2.0 * ((a - 1.0) / (a + 1.0)).asin()).abs() < 1e-6);
+    // Half of the sky: TAN and SIN fail
+    let region = SkyRegion::Cone(Cone::new(LonLat::new(0.0, 0.0), 100_f64.to_radians()));
+    let rec = recommend_projection(&region, &candidates, DistortionCriterion::Scale).unwrap();
+    assert!(rec.ranking().iter().all(|(i, _)| *i != 0 && *i != 3));
+  }
+}
@@ -0,0 +1,658 @@
+//! Module containing a reader and a writer of [DS9](https://ds9.si.edu/doc/ref/region.html)
+//! region files (`.reg`), supporting the `circle`, `ellipse`, `box`, `polygon`, `point`,
+//! `line` and `text` shapes in the `image`, `fk5`, `icrs` and `galactic` coordinate systems.
+//!
+//! Regions can be converted from one system to another through an `ImgXY2Celestial` transform.
+//! Sizes and angles are converted using the local linear approximation of the transform
+//! (local pixel scale and North direction) at the region center.
+//!
+//! In this module:
+//! * image coordinates follow the FITS convention (the center of the first pixel is at `(1, 1)`);
+//! * sky positions `(lon, lat)`, sky sizes and all angles are in radians;
+//! * like in DS9, angles are counted counter-clockwise from the image x-axis in the `image` system
+//!   and from the West toward the North in sky systems (so that both are equal for a
+//!   North-up East-left image).
+//! * `fk5` (J2000) and `icrs` are considered as identical (they differ by less than 0.03 arcsec).
+
+use std::f64::consts::TAU;
+
+use crate::{LonLat, ImgXY, XYZ};
+use crate::img2celestial::ImgXY2Celestial;
+use crate::sphere::east_north;
+
+/// Rotation matrix from equatorial (ICRS) to galactic coordinates (Hipparcos, ESA 1997).
+#[allow(clippy::excessive_precision)]
+const EQ_TO_GAL: [[f64; 3]; 3] = [
+  [-0.0548755604162154, -0.8734370902348850, -0.4838350155487132],
+  [ 0.4941094278755837, -0.4448296299600112,  0.7469822444972189],
+  [-0.8676661490190047, -0.1980763734312015,  0.4559837761750669],
+];
+
+/// Coordinate systems supported in DS9 region files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ds9System {
+  Image,
+  Fk5,
+  Icrs,
+  Galactic,
+}
+
+impl Ds9System {
+
+  /// Returns the system corresponding to the given (case insensitive) DS9 keyword.
+  pub fn from_keyword(keyword: &str) -> Option<Self> {
+    match keyword.to_lowercase().as_str() {
+      "image" => Some(Ds9System::Image),
+      "fk5" | "j2000" => Some(Ds9System::Fk5),
+      "icrs" => Some(Ds9System::Icrs),
+      "galactic" => Some(Ds9System::Galactic),
+      _ => None,
+    }
+  }
+
+  /// Returns the DS9 keyword of the system.
+  pub fn keyword(&self) -> &'static str {
+    match self {
+      Ds9System::Image => "image",
+      Ds9System::Fk5 => "fk5",
+      Ds9System::Icrs => "icrs",
+      Ds9System::Galactic => "galactic",
+    }
+  }
+
+  /// Returns `true` for sky coordinate systems (i.e. all systems except `image`).
+  pub fn is_sky(&self) -> bool {
+    !matches!(self, Ds9System::Image)
+  }
+
+  /// Transforms a position in this (sky) system into an equatorial position.
+  fn transform_to_equatorial(self, xyz: &XYZ) -> XYZ {
+    match self {
+      Ds9System::Galactic => {
+        let m = &EQ_TO_GAL;
+        XYZ::new_renorming_if_necessary(
+          m[0][0] * xyz.x() + m[1][0] * xyz.y() + m[2][0] * xyz.z(),
+          m[0][1] * xyz.x() + m[1][1] * xyz.y() + m[2][1] * xyz.z(),
+          m[0][2] * xyz.x() + m[1][2] * xyz.y() + m[2][2] * xyz.z(),
+        )
+      },
+      _ => xyz.clone(),
+    }
+  }
+
+  /// Transforms an equatorial position into a position in this (sky) system.
+  fn transform_from_equatorial(self, xyz: &XYZ) -> XYZ {
+    match self {
+      Ds9System::Galactic => {
+        let m = &EQ_TO_GAL;
+        XYZ::new_renorming_if_necessary(
+          m[0][0] * xyz.x() + m[0][1] * xyz.y() + m[0][2] * xyz.z(),
+          m[1][0] * xyz.x() + m[1][1] * xyz.y() + m[1][2] * xyz.z(),
+          m[2][0] * xyz.x() + m[2][1] * xyz.y() + m[2][2] * xyz.z(),
+        )
+      },
+      _ => xyz.clone(),
+    }
+  }
+}
+
+/// Shapes supported in DS9 region files.
+/// Positions are either image coordinates `(x, y)` or sky coordinates `(lon, lat)` in radians,
+/// and sizes are either in pixels or in radians, depending on the region system.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ds9Shape {
+  Circle { center: (f64, f64), radius: f64 },
+  Ellipse { center: (f64, f64), radii: (f64, f64), angle: f64 },
+  Box { center: (f64, f64), size: (f64, f64), angle: f64 },
+  Polygon { vertices: Vec<(f64, f64)> },
+  Point { pos: (f64, f64) },
+  Line { start: (f64, f64), end: (f64, f64) },
+  Text { pos: (f64, f64), text: String },
+}
+
+impl Ds9Shape {
+
+  /// Returns the DS9 name of the shape.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Ds9Shape::Circle { .. } => "circle",
+      Ds9Shape::Ellipse { .. } => "ellipse",
+      Ds9Shape::Box { .. } => "box",
+      Ds9Shape::Polygon { .. } => "polygon",
+      Ds9Shape::Point { .. } => "point",
+      Ds9Shape::Line { .. } => "line",
+      Ds9Shape::Text { .. } => "text",
+    }
+  }
+}
+
+/// A DS9 region: a shape in a given coordinate system, together with its properties
+/// (the part of the line after the `#` character, e.g. `color=red width=2`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ds9Region {
+  system: Ds9System,
+  shape: Ds9Shape,
+  exclude: bool,
+  properties: String,
+}
+
+impl Ds9Region {
+
+  pub fn new(system: Ds9System, shape: Ds9Shape) -> Self {
+    Self { system, shape, exclude: false, properties: String::new() }
+  }
+
+  /// Get the coordinate system of the region
+  pub fn system(&self) -> Ds9System {
+    self.system
+  }
+
+  /// Get the shape of the region
+  pub fn shape(&self) -> &Ds9Shape {
+    &self.shape
+  }
+
+  /// Returns `true` for exclude regions (starting with `-` in DS9 files)
+  pub fn is_exclude(&self) -> bool {
+    self.exclude
+  }
+
+  /// Get the region properties (e.g. `color=red width=2`)
+  pub fn properties(&self) -> &str {
+    &self.properties
+  }
+
+  /// Set the exclude flag of the region.
+  pub fn set_exclude(&mut self, exclude: bool) {
+    self.exclude = exclude;
+  }
+
+  /// Set the region properties (e.g. `color=red width=2`).
+  pub fn set_properties(&mut self, properties: &str) {
+    self.properties = properties.trim().to_string();
+  }
+
+  /// Converts the region into the given coordinate system.
+  /// # Params
+  /// * `target`: the system of the returned region
+  /// * `img2celestial`: image to celestial coordinates transform, using the FITS pixel convention
+  /// * `img_system`: sky coordinate system of the `img2celestial` celestial coordinates
+  ///   (e.g. `Icrs` for `RA---TAN`/`DEC--TAN`, `Galactic` for `GLON-TAN`/`GLAT-TAN`)
+  /// # Return
+  /// * `None` if a position can't be transformed (e.g. outside of the valid projection area)
+  /// # Panics
+  /// * if `img_system` is `Image`
+  pub fn convert<C: ImgXY2Celestial + ?Sized>(
+    &self,
+    target: Ds9System,
+    img2celestial: &C,
+    img_system: Ds9System
+  ) -> Option<Ds9Region> {
+    assert!(img_system.is_sky());
+    let shape = if target == self.system {
+      self.shape.clone()
+    } else {
+      let wcs = LocalWcs { img2celestial, img_system };
+      let img_shape = if self.system.is_sky() {
+        wcs.shape_to_image(&self.shape, self.system)?
+      } else {
+        self.shape.clone()
+      };
+      if target.is_sky() {
+        wcs.shape_from_image(&img_shape, target)?
+      } else {
+        img_shape
+      }
+    };
+    Some(Ds9Region { system: target, shape, exclude: self.exclude, properties: self.properties.clone() })
+  }
+}
+
+/// Wraps an image to celestial coordinates transform to convert shapes from/to the image system.
+struct LocalWcs<'a, C: ImgXY2Celestial + ?Sized> {
+  img2celestial: &'a C,
+  img_system: Ds9System,
+}
+
+impl<C: ImgXY2Celestial + ?Sized> LocalWcs<'_, C> {
+
+  fn img2sky(&self, pos: (f64, f64), system: Ds9System) -> Option<XYZ> {
+    self.img2celestial.img2xyz(&ImgXY::new(pos.0, pos.1))
+      .map(|xyz| system.transform_from_equatorial(&self.img_system.transform_to_equatorial(&xyz)))
+  }
+
+  fn img2lonlat(&self, pos: (f64, f64), system: Ds9System) -> Option<(f64, f64)> {
+    self.img2sky(pos, system).map(|xyz| {
+      let lonlat = xyz.to_lonlat();
+      (lonlat.lon(), lonlat.lat())
+    })
+  }
+
+  fn lonlat2img(&self, pos: (f64, f64), system: Ds9System) -> Option<(f64, f64)> {
+    let xyz = LonLat::new(pos.0, pos.1).to_xyz();
+    self.img2celestial.xyz2img(&self.img_system.transform_from_equatorial(&system.transform_to_equatorial(&xyz)))
+      .map(|img_pos| (img_pos.x(), img_pos.y()))
+  }
+
+  /// Returns the local Jacobian `[[dE/dx, dE/dy], [dN/dx, dN/dy]]`, in radians per pixel,
+  /// of the transformation from image coordinates to the local East, North sky directions
+  /// in the given system.
+  fn jacobian(&self, pos: (f64, f64), system: Ds9System) -> Option<[[f64; 2]; 2]> {
+    let (e, n) = east_north(&self.img2sky(pos, system)?)?;
+    let diff = |a: XYZ, b: XYZ| [a.x() - b.x(), a.y() - b.y(), a.z() - b.z()];
+    let dx = diff(self.img2sky((pos.0 + 0.5, pos.1), system)?, self.img2sky((pos.0 - 0.5, pos.1), system)?);
+    let dy = diff(self.img2sky((pos.0, pos.1 + 0.5), system)?, self.img2sky((pos.0, pos.1 - 0.5), system)?);
+    let proj = |v: &[f64; 3], u: &XYZ| v[0] * u.x() + v[1] * u.y() + v[2] * u.z();
+    Some([[proj(&dx, &e), proj(&dy, &e)], [proj(&dx, &n), proj(&dy, &n)]])
+  }
+
+  fn shape_to_image(&self, shape: &Ds9Shape, system: Ds9System) -> Option<Ds9Shape> {
+    let to_img = |pos: &(f64, f64)| self.lonlat2img(*pos, system);
+    // Transforms a sky axis (size and angle) into a pixel axis
+    let axes = |center: (f64, f64), sizes: (f64, f64), angle: f64| -> Option<((f64, f64), f64)> {
+      let j = self.jacobian(center, system)?;
+      let (sina, cosa) = angle.sin_cos();
+      let v1 = solve2(&j, (-cosa, sina))?;
+      let v2 = solve2(&j, (sina, cosa))?;
+      Some(((sizes.0 * v1.0.hypot(v1.1), sizes.1 * v2.0.hypot(v2.1)), v1.1.atan2(v1.0).rem_euclid(TAU)))
+    };
+    Some(match shape {
+      Ds9Shape::Circle { center, radius } => {
+        let center = to_img(center)?;
+        let j = self.jacobian(center, system)?;
+        Ds9Shape::Circle { center, radius: radius / det2(&j).abs().sqrt() }
+      },
+      Ds9Shape::Ellipse { center, radii, angle } => {
+        let center = to_img(center)?;
+        let (radii, angle) = axes(center, *radii, *angle)?;
+        Ds9Shape::Ellipse { center, radii, angle }
+      },
+      Ds9Shape::Box { center, size, angle } => {
+        let center = to_img(center)?;
+        let (size, angle) = axes(center, *size, *angle)?;
+        Ds9Shape::Box { center, size, angle }
+      },
+      Ds9Shape::Polygon { vertices } => Ds9Shape::Polygon {
+        vertices: vertices.iter().map(to_img).collect::<Option<Vec<_>>>()?
+      },
+      Ds9Shape::Point { pos } => Ds9Shape::Point { pos: to_img(pos)? },
+      Ds9Shape::Line { start, end } => Ds9Shape::Line { start: to_img(start)?, end: to_img(end)? },
+      Ds9Shape::Text { pos, text } => Ds9Shape::Text { pos: to_img(pos)?, text: text.clone() },
+    })
+  }
+
+  fn shape_from_image(&self, shape: &Ds9Shape, system: Ds9System) -> Option<Ds9Shape> {
+    let to_sky = |pos: &(f64, f64)| self.img2lonlat(*pos, system);
+    // Transforms a pixel axis (size and angle) into a sky axis
+    let axes = |center: (f64, f64), sizes: (f64, f64), angle: f64| -> Option<((f64, f64), f64)> {
+      let j = self.jacobian(center, system)?;
+      let (sina, cosa) = angle.sin_cos();
+      let u1 = mul2(&j, (cosa, sina));
+      let u2 = mul2(&j, (-sina, cosa));
+      Some(((sizes.0 * u1.0.hypot(u1.1), sizes.1 * u2.0.hypot(u2.1)), u1.1.atan2(-u1.0).rem_euclid(TAU)))
+    };
+    Some(match shape {
+      Ds9Shape::Circle { center, radius } => {
+        let j = self.jacobian(*center, system)?;
+        Ds9Shape::Circle { center: to_sky(center)?, radius: radius * det2(&j).abs().sqrt() }
+      },
+      Ds9Shape::Ellipse { center, radii, angle } => {
+        let (radii, angle) = axes(*center, *radii, *angle)?;
+        Ds9Shape::Ellipse { center: to_sky(center)?, radii, angle }
+      },
+      Ds9Shape::Box { center, size, angle } => {
+        let (size, angle) = axes(*center, *size, *angle)?;
+        Ds9Shape::Box { center: to_sky(center)?, size, angle }
+      },
+      Ds9Shape::Polygon { vertices } => Ds9Shape::Polygon {
+        vertices: vertices.iter().map(to_sky).collect::<Option<Vec<_>>>()?
+      },
+      Ds9Shape::Point { pos } => Ds9Shape::Point { pos: to_sky(pos)? },
+      Ds9Shape::Line { start, end } => Ds9Shape::Line { start: to_sky(start)?, end: to_sky(end)? },
+      Ds9Shape::Text { pos, text } => Ds9Shape::Text { pos: to_sky(pos)?, text: text.clone() },
+    })
+  }
+}
+
+fn det2(m: &[[f64; 2]; 2]) -> f64 {
+  m[0][0] * m[1][1] - m[0][1] * m[1][0]
+}
+
+fn mul2(m: &[[f64; 2]; 2], v: (f64, f64)) -> (f64, f64) {
+  (m[0][0] * v.0 + m[0][1] * v.1, m[1][0] * v.0 + m[1][1] * v.1)
+}
+
+/// Solves `m . x = v`.
+fn solve2(m: &[[f64; 2]; 2], v: (f64, f64)) -> Option<(f64, f64)> {
+  let det = det2(m);
+  if det != 0.0 {
+    Some(((m[1][1] * v.0 - m[0][1] * v.1) / det, (m[0][0] * v.1 - m[1][0] * v.0) / det))
+  } else {
+    None
+  }
+}
+
+/// Parses the content of a DS9 region file.
+/// Global properties and comments are ignored.
+/// # Return
+/// * an error message (containing the line number) if a line can't be parsed, e.g. if it
+///   contains an unsupported shape or coordinate system
+pub fn parse_ds9(content: &str) -> Result<Vec<Ds9Region>, String> {
+  let mut regions = Vec::new();
+  // DS9 default system
+  let mut system = Ds9System::Fk5;
+  for (i, line) in content.lines().enumerate() {
+    parse_line(line, &mut system, &mut regions)
+      .map_err(|e| format!("Error line {}: {}", i + 1, e))?;
+  }
+  Ok(regions)
+}
+
+fn parse_line(line: &str, system: &mut Ds9System, regions: &mut Vec<Ds9Region>) -> Result<(), String> {
+  let line = line.trim();
+  let (commands, properties) = match line.strip_prefix('#') {
+    // DS9 writes some shapes (e.g. text) commented out: "# text(x,y) text={...}"
+    Some(comment) => {
+      let comment = comment.trim_start();
+      let is_shape = ["text(", "point(", "line("].iter().any(|s| comment.starts_with(s));
+      match comment.find(')') {
+        Some(i) if is_shape => (&comment[..=i], &comment[i + 1..]),
+        _ => return Ok(()),
+      }
+    },
+    None => split_comment(line),
+  };
+  for command in commands.split(';').map(str::trim).filter(|c| !c.is_empty()) {
+    if let Some(s) = Ds9System::from_keyword(command) {
+      *system = s;
+    } else if command.starts_with("global") {
+      continue;
+    } else if command.contains('(') {
+      let mut region = parse_shape(command, *system)?;
+      region.set_properties(properties);
+      if let Ds9Shape::Text { text, .. } = &mut region.shape {
+        if let Some((t, props)) = extract_text_property(properties) {
+          *text = t;
+          region.set_properties(&props);
+        }
+      }
+      regions.push(region);
+    } else {
+      return Err(format!("Unsupported command or coordinate system '{}'", command));
+    }
+  }
+  Ok(())
+}
+
+/// Splits the line into its command part and its comment part (the first `#` outside braces).
+fn split_comment(line: &str) -> (&str, &str) {
+  let mut depth = 0;
+  for (i, c) in line.char_indices() {
+    match c {
+      '{' => depth += 1,
+      '}' => depth -= 1,
+      '#' if depth == 0 => return (&line[..i], &line[i + 1..]),
+      _ => {},
+    }
+  }
+  (line, "")
+}
+
+/// Extracts `text={...}` from the given properties, returning the text and the remaining properties.
+fn extract_text_property(properties: &str) -> Option<(String, String)> {
+  let start = properties.find("text={")?;
+  let end = start + properties[start..].find('}')?;
+  let text = properties[start + 6..end].to_string();
+  let remaining = format!("{} {}", &properties[..start], &properties[end + 1..]);
+  Some((text, remaining.trim().to_string()))
+}
+
+fn parse_shape(command: &str, system: Ds9System) -> Result<Ds9Region, String> {
+  let (exclude, command) = match command.strip_prefix('-') {
+    Some(c) => (true, c),
+    None => (false, command.strip_prefix('+').unwrap_or(command)),
+  };
+  let open = command.find('(').unwrap();
+  let close = command.rfind(')').ok_or_else(|| format!("Missing ')' in '{}'", command))?;
+  if close < open {
+    return Err(format!("Malformed shape '{}'", command));
+  }
+  let name = command[..open].trim().to_lowercase();
+  let mut args_str = command[open + 1..close].to_string();
+  // Text given as last argument, e.g. text(10,20,{Hello})
+  let mut text = String::new();
+  if let Some(start) = args_str.find('{') {
+    let end = start + args_str[start..].rfind('}').ok_or_else(|| format!("Missing '}}' in '{}'", command))?;
+    text = args_str[start + 1..end].to_string();
+    args_str.truncate(start);
+  }
+  let args: Vec<&str> = args_str.split(',').map(str::trim).filter(|a| !a.is_empty()).collect();
+  let pos = |i: usize| parse_pos(args[i], args[i + 1], system);
+  let size = |i: usize| parse_size(args[i], system);
+  let angle = |i: usize| -> Result<f64, String> {
+    args.get(i)
+      .map(|a| a.parse::<f64>().map(f64::to_radians).map_err(|e| format!("Wrong angle '{}': {}", a, e)))
+      .unwrap_or(Ok(0.0))
+  };
+  let shape = match (name.as_str(), args.len()) {
+    ("circle", 3) => Ds9Shape::Circle { center: pos(0)?, radius: size(2)? },
+    ("ellipse", 4 | 5) => Ds9Shape::Ellipse { center: pos(0)?, radii: (size(2)?, size(3)?), angle: angle(4)? },
+    ("box", 4 | 5) => Ds9Shape::Box { center: pos(0)?, size: (size(2)?, size(3)?), angle: angle(4)? },
+    ("polygon", n) if n >= 6 && n % 2 == 0 => Ds9Shape::Polygon {
+      vertices: (0..n).step_by(2).map(pos).collect::<Result<Vec<_>, String>>()?
+    },
+    ("point", 2) => Ds9Shape::Point { pos: pos(0)? },
+    ("line", 4) => Ds9Shape::Line { start: pos(0)?, end: pos(2)? },
+    ("text", 2) => Ds9Shape::Text { pos: pos(0)?, text },
+    _ => return Err(format!("Unsupported shape or wrong number of parameters: '{}'", command)),
+  };
+  let mut region = Ds9Region::new(system, shape);
+  region.set_exclude(exclude);
+  Ok(region)
+}
+
+/// Parses a position, returning image coordinates or sky coordinates in radians.
+fn parse_pos(x: &str, y: &str, system: Ds9System) -> Result<(f64, f64), String> {
+  let parse = |s: &str| s.trim_end_matches('d').parse::<f64>().map_err(|e| format!("Wrong coordinate '{}': {}", s, e));
+  match system {
+    Ds9System::Image => Ok((parse(x)?, parse(y)?)),
+    _ => {
+      let lon = if x.contains(':') {
+        let lon = parse_sexagesimal(x)?;
+        if system == Ds9System::Galactic { lon } else { 15.0 * lon }
+      } else {
+        parse(x)?
+      };
+      let lat = if y.contains(':') { parse_sexagesimal(y)? } else { parse(y)? };
+      if !(-90.0..=90.0).contains(&lat) {
+        return Err(format!("Latitude out of [-90, 90]: {}", lat));
+      }
+      Ok((lon.rem_euclid(360.0).to_radians(), lat.to_radians()))
+    },
+  }
+}
+
+/// Parses `[+-]a:b:c`, returning `a + b/60 + c/3600` with the given sign.
+fn parse_sexagesimal(s: &str) -> Result<f64, String> {
+  let (sign, unsigned) = match s.strip_prefix('-') {
+    Some(u) => (-1.0, u),
+    None => (1.0, s.strip_prefix('+').unwrap_or(s)),
+  };
+  let mut value = 0.0;
+  let mut factor = 1.0;
+  for field in unsigned.split(':') {
+    value += factor * field.parse::<f64>().map_err(|e| format!("Wrong sexagesimal value '{}': {}", s, e))?;
+    factor /= 60.0;
+  }
+  Ok(sign * value)
+}
+
+/// Parses a size, returning a value in pixels for the image system or in radians for sky systems.
+fn parse_size(s: &str, system: Ds9System) -> Result<f64, String> {
+  let (value, unit) = match s.char_indices().last() {
+    Some((i, c)) if !c.is_ascii_digit() && c != '.' => (&s[..i], Some(c)),
+    _ => (s, None),
+  };
+  let value = value.parse::<f64>().map_err(|e| format!("Wrong size '{}': {}", s, e))?;
+  match (system.is_sky(), unit) {
+    (false, None | Some('i') | Some('p')) => Ok(value),
+    (true, Some('"')) => Ok((value / 3600.0).to_radians()),
+    (true, Some('\'')) => Ok((value / 60.0).to_radians()),
+    (true, None | Some('d')) => Ok(value.to_radians()),
+    (true, Some('r')) => Ok(value),
+    _ => Err(format!("Unsupported size unit in the {} system: '{}'", system.keyword(), s)),
+  }
+}
+
+/// Writes the given regions in the DS9 region file format.
+/// Sky positions are written in decimal degrees and sky sizes in arcseconds.
+pub fn write_ds9(regions: &[Ds9Region]) -> String {
+  let mut s = String::from("# Region file format: DS9 version 4.1\n");
+  let mut current_system = None;
+  for region in regions {
+    if current_system != Some(region.system) {
+      s.push_str(region.system.keyword());
+      s.push('\n');
+      current_system = Some(region.system);
+    }
+    let sky = region.system.is_sky();
+    let pos = |p: &(f64, f64)| if sky {
+      format!("{:.7},{:.7}", p.0.to_degrees().rem_euclid(360.0), p.1.to_degrees())
+    } else {
+      format!("{:.4},{:.4}", p.0, p.1)
+    };
+    let size = |v: f64| if sky {
+      format!("{:.4}\"", v.to_degrees() * 3600.0)
+    } else {
+      format!("{:.4}", v)
+    };
+    let angle = |a: f64| format!("{:.4}", a.to_degrees());
+    if region.exclude {
+      s.push('-');
+    }
+    s.push_str(region.shape.name());
+    s.push('(');
+    match &region.shape {
+      Ds9Shape::Circle { center, radius } => s.push_str(&format!("{},{}", pos(center), size(*radius))),
+      Ds9Shape::Ellipse { center, radii, angle: a } =>
+        s.push_str(&format!("{},{},{},{}", pos(center), size(radii.0), size(radii.1), angle(*a))),
+      Ds9Shape::Box { center, size: wh, angle: a } =>
+        s.push_str(&format!("{},{},{},{}", pos(center), size(wh.0), size(wh.1), angle(*a))),
+      Ds9Shape::Polygon { vertices } =>
+        s.push_str(&vertices.iter().map(pos).collect::<Vec<String>>().join(",")),
+      Ds9Shape::Point { pos: p } | Ds9Shape::Text { pos: p, .. } => s.push_str(&pos(p)),
+      Ds9Shape::Line { start, end } => s.push_str(&format!("{},{}", pos(start), pos(end))),
+    }
+    s.push(')');
+    let text = match &region.shape {
+      Ds9Shape::Text { text, .. } => format!("text={{{}}}", text),
+      _ => String::new(),
+    };
+    let properties = format!("{} {}", text, region.properties);
+    let properties = properties.trim();
+    if !properties.is_empty() {
+      s.push_str(" # ");
+      s.push_str(properties);
+    }
+    s.push('\n');
+  }
+  s
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::CenteredProjection;
+  use crate::img2celestial::Img2Celestial;
+  use crate::img2proj::WcsImgXY2ProjXY;
+  use crate::zenithal::tan::Tan;
+
+  fn img2celestial(crota2: f64) -> Img2Celestial<Tan, WcsImgXY2ProjXY> {
+    // 1 arcsec pixels, centered on (150, 2) deg
+    let img2proj = WcsImgXY2ProjXY::from_cr(100.0, 100.0, crota2, -1.0 / 3600.0, 1.0 / 3600.0);
+    let mut proj = CenteredProjection::new(Tan::new());
+    proj.set_proj_center_from_lonlat(&LonLat::new(150_f64.to_radians(), 2_f64.to_radians()));
+    Img2Celestial::new(img2proj, proj)
+  }
+
+  #[test]
+  fn test_ds9_read_write() {
+    let content = "# Region file format: DS9 version 4.1
+global color=green dashlist=8 3 width=1
+fk5
+circle(10:00:00.000,+02:00:00.00,30\") # color=red
+image; -box(100,100,20,10,45)
+galactic
+point(0,0) # point=cross
+# text(150.0,2.0) text={Hello, world}
+";
+    let regions = parse_ds9(content).unwrap();
+    assert_eq!(regions.len(), 4);
+    assert_eq!(regions[0].properties(), "color=red");
+    match regions[0].shape() {
+      Ds9Shape::Circle { center, radius } => {
+        assert!((center.0.to_degrees() - 150.0).abs() < 1e-12);
+        assert!((radius.to_degrees() * 3600.0 - 30.0).abs() < 1e-9);
+      },
+      _ => panic!(),
+    }
+    assert!(regions[1].is_exclude());
+    assert_eq!(regions[2].system(), Ds9System::Galactic);
+    assert_eq!(regions[3].shape(), &Ds9Shape::Text { pos: (150_f64.to_radians(), 2_f64.to_radians()), text: String::from("Hello, world") });
+    // Round trip
+    let written = write_ds9(&regions);
+    let regions2 = parse_ds9(&written).unwrap();
+    assert_eq!(written, write_ds9(&regions2));
+    // Malformed inputs
+    assert!(parse_ds9("image; text(1,2,}x{)").is_err());
+    assert!(parse_ds9("image; circle(1,2,3").is_err());
+    assert!(parse_ds9("image; circle(1,2)").is_err());
+  }
+
+  #[test]
+  fn test_ds9_convert() {
+    // Galactic center
+    let gc = Ds9Region::new(Ds9System::Galactic, Ds9Shape::Point { pos: (0.0, 0.0) });
+    let img = img2celestial(0.0);
+    let gc_icrs = gc.convert(Ds9System::Icrs, &img, Ds9System::Icrs);
+    // Far from the image: the TAN projection fails
+    assert!(gc_icrs.is_none());
+
+    // North-up East-left image: the angles are the same in the image and in the sky
+    let center = (150_f64.to_radians(), 2_f64.to_radians());
+    let ell = Ds9Region::new(Ds9System::Fk5, Ds9Shape::Ellipse {
+      center, radii: ((20.0 / 3600_f64).to_radians(), (10.0 / 3600_f64).to_radians()), angle: 30_f64.to_radians()
+    });
+    match ell.convert(Ds9System::Image, &img, Ds9System::Icrs).unwrap().shape() {
+      Ds9Shape::Ellipse { center, radii, angle } => {
+        assert!((center.0 - 100.0).abs() < 1e-6 && (center.1 - 100.0).abs() < 1e-6);
+        assert!((radii.0 - 20.0).abs() < 1e-6 && (radii.1 - 10.0).abs() < 1e-6);
+        assert!((angle.to_degrees() - 30.0).abs() < 1e-6);
+      },
+      _ => panic!(),
+    }
+    // Image rotated by 20 deg
+    let img = img2celestial(20.0);
+    let ell_img = ell.convert(Ds9System::Image, &img, Ds9System::Icrs).unwrap();
+    match ell_img.shape() {
+      Ds9Shape::Ellipse { radii, angle, .. } => {
+        assert!((radii.0 - 20.0).abs() < 1e-6 && (radii.1 - 10.0).abs() < 1e-6);
+        // North rotated by CROTA2 counter-clockwise in the image
+        assert!((angle.to_degrees() - 50.0).abs() < 1e-6);
+      },
+      _ => panic!(),
+    }
+    let back = ell_img.convert(Ds9System::Fk5, &img, Ds9System::Icrs).unwrap();
+    match back.shape() {
+      Ds9Shape::Ellipse { radii, angle, .. } => {
+        assert!((radii.0.to_degrees() * 3600.0 - 20.0).abs() < 1e-6);
+        assert!((angle.to_degrees() - 30.0).abs() < 1e-6);
+      },
+      _ => panic!(),
+    }
+    // Galactic <-> equatorial
+    let gc_eq = Ds9System::Galactic.transform_to_equatorial(&LonLat::new(0.0, 0.0).to_xyz()).to_lonlat();
+    assert!((gc_eq.lon().to_degrees() - 266.40499).abs() < 1e-4);
+    assert!((gc_eq.lat().to_degrees() + 28.93617).abs() < 1e-4);
+  }
+}
@@ -0,0 +1,180 @@
+//! Module containing the automatic framing of a set of positions (e.g. a catalogue subset or a
+//! region): computation of the projection center, of the rotation of the projection around its
+//! center and of the extent of the positions in the projection plane, to build an image
+//! containing all the positions.
+//!
+//! The projection center is the center of the smallest cone containing all the positions, so that
+//! sets wrapping around `lon = 0` or containing a pole are handled like any other set.
+//! The rotation is the one minimizing the area of the bounding rectangle of the positions in the
+//! (rotationally invariant) `ARC` plane, the longest side of the rectangle being along the x-axis.
+
+use std::f64::consts::PI;
+use std::ops::RangeInclusive;
+
+use crate::{CanonicalProjection, CenteredProjection, LonLat, Projection, XYZ};
+use crate::img2celestial::Img2Celestial;
+use crate::img2proj::BasicImgXY2ProjXY;
+use crate::math::{min_area_angle, rotated_bounds};
+use crate::region::{SkyRegion, DEFAULT_STEP};
+use crate::sphere::smallest_enclosing_cone;
+use crate::zenithal::arc::Arc;
+
+/// Framing of a set of positions in a given projection.
+pub struct Framing<P: CanonicalProjection> {
+  proj: CenteredProjection<P>,
+  center: LonLat,
+  angle: f64,
+  x_bounds: RangeInclusive<f64>,
+  y_bounds: RangeInclusive<f64>,
+}
+
+impl<P: CanonicalProjection> Framing<P> {
+
+  /// # Params
+  /// * `proj`: the projection
+  /// * `points`: the positions to be framed
+  /// * `rotate`: if `false`, the projection is not rotated around its center (i.e. the North is up
+  ///   at the projection center)
+  /// # Return
+  /// * `None` if the input is empty, if all positions are identical, or if one of the positions
+  ///   can't be projected
+  pub fn new(proj: P, points: &[XYZ], rotate: bool) -> Option<Self> {
+    let (center, _) = smallest_enclosing_cone(points)?;
+    let center = center.to_lonlat();
+    let angle = if rotate { optimal_angle(&center, points) } else { 0.0 };
+    let mut proj = CenteredProjection::new(proj);
+    proj.set_proj_center_from_lonlat_and_positional_angle(&center, angle);
+    let (mut xmin, mut xmax, mut ymin, mut ymax) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+    for p in points {
+      let pos = proj.proj_xyz(p)?;
+      xmin = xmin.min(pos.x());
+      xmax = xmax.max(pos.x());
+      ymin = ymin.min(pos.y());
+      ymax = ymax.max(pos.y());
+    }
+    if xmax - xmin <= 0.0 && ymax - ymin <= 0.0 {
+      None
+    } else {
+      Some(Self { proj, center, angle, x_bounds: xmin..=xmax, y_bounds: ymin..=ymax })
+    }
+  }
+
+  /// Same as `new` with positions given in longitude and latitude.
+  pub fn from_lonlat(proj: P, points: &[LonLat], rotate: bool) -> Option<Self> {
+    let points: Vec<XYZ> = points.iter().map(|p| p.to_xyz()).collect();
+    Self::new(proj, &points, rotate)
+  }
+
+  /// Same as `new` for the positions of the boundaries of the given region
+  /// (sampled with a step of `DEFAULT_STEP`).
+  pub fn from_region(proj: P, region: &SkyRegion, rotate: bool) -> Option<Self> {
+    let points: Vec<XYZ> = region.boundaries(DEFAULT_STEP).into_iter().flatten().collect();
+    Self::new(proj, &points, rotate)
+  }
+
+  /// Get the projection, centered and rotated
+  pub fn proj(&self) -> &CenteredProjection<P> {
+    &self.proj
+  }
+
+  /// Get the projection center
+  pub fn center(&self) -> &LonLat {
+    &self.center
+  }
+
+  /// Get the rotation angle of the projection around its center, in radians, in `[-pi/2, pi/2[`
+  /// (see `CenteredProjection::set_proj_center_from_lonlat_and_positional_angle`)
+  pub fn angle(&self) -> f64 {
+    self.angle
+  }
+
+  /// Get the range of the positions along the x-axis of the projection plane
+  pub fn x_bounds(&self) -> &RangeInclusive<f64> {
+    &self.x_bounds
+  }
+
+  /// Get the range of the positions along the y-axis of the projection plane
+  pub fn y_bounds(&self) -> &RangeInclusive<f64> {
+    &self.y_bounds
+  }
+
+  /// Returns the transformation from an image of the given size to the projection plane, so that
+  /// all positions are in the image, with the same scale along both axes.
+  /// # Params
+  /// * `img_size`: `(size_x, size_y)` number of pixels in each dimension
+  /// * `margin`: minimum number of pixels between the positions and each side of the image
+  /// # Remark
+  /// * like in `BasicImgXY2ProjXY::from`, the projection `x` axis is not reversed, i.e. the East is
+  ///   on the right of the image.
+  /// # Panics
+  /// * if `margin` is negative or if `2 * margin` is larger than or equal to an image size
+  pub fn img2proj(&self, img_size: (u16, u16), margin: f64) -> BasicImgXY2ProjXY {
+    let (size_x, size_y) = (img_size.0 as f64, img_size.1 as f64);
+    assert!(0.0 <= margin && 2.0 * margin < size_x.min(size_y));
+    let (dx, dy) = (self.x_bounds.end() - self.x_bounds.start(), self.y_bounds.end() - self.y_bounds.start());
+    let scale = (dx / (size_x - 2.0 * margin)).max(dy / (size_y - 2.0 * margin));
+    let cx = 0.5 * (self.x_bounds.start() + self.x_bounds.end());
+    let cy = 0.5 * (self.y_bounds.start() + self.y_bounds.end());
+    let (hx, hy) = (0.5 * scale * size_x, 0.5 * scale * size_y);
+    BasicImgXY2ProjXY::from(img_size, (&(cx - hx..=cx + hx), &(cy - hy..=cy + hy)))
+  }
+
+  /// Same as `img2proj`, returning the full transformation from the image to the sphere.
+  pub fn into_img2celestial(self, img_size: (u16, u16), margin: f64) -> Img2Celestial<P, BasicImgXY2ProjXY> {
+    let img2proj = self.img2proj(img_size, margin);
+    Img2Celestial::new(img2proj, self.proj)
+  }
+}
+
+/// Returns the rotation angle, in `[-pi/2, pi/2[`, minimizing the area of the bounding rectangle
+/// of the positions in the `ARC` plane centered on the given center, with the longest side along
+/// the x-axis.
+fn optimal_angle(center: &LonLat, points: &[XYZ]) -> f64 {
+  let mut arc = CenteredProjection::new(Arc::new());
+  arc.set_proj_center_from_lonlat(center);
+  let arc_points: Vec<(f64, f64)> = points.iter()
+    .filter_map(|p| arc.proj_xyz(p))
+    .map(|pos| (pos.x(), pos.y()))
+    .collect();
+  let phi = min_area_angle(&arc_points);
+  let (umin, umax, vmin, vmax) = rotated_bounds(&arc_points, phi);
+  // A rotation of the projection of `gamma` rotates the (East, North) coordinates of `gamma`
+  let gamma = if vmax - vmin > umax - umin { -phi - 0.5 * PI } else { -phi };
+  (gamma + 0.5 * PI).rem_euclid(PI) - 0.5 * PI
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::zenithal::{sin::Sin, tan::Tan};
+
+  #[test]
+  fn test_framing() {
+    let deg = |lon: f64, lat: f64| LonLat::new(lon.to_radians(), lat.to_radians());
+    // Set wrapping around lon = 0, elongated along the North
+    let points = [deg(359.0, -5.0), deg(1.0, -5.0), deg(1.0, 5.0), deg(359.0, 5.0), deg(359.5, 4.0), deg(0.0, 0.0)];
+    let framing = Framing::from_lonlat(Tan::new(), &points, false).unwrap();
+    assert!(framing.center().lon().sin().abs() < 1e-9 && framing.center().lat().abs() < 1e-9);
+    assert!(framing.y_bounds().end() - framing.y_bounds().start() > 4.0 * (framing.x_bounds().end() - framing.x_bounds().start()));
+    // Rotated: the longest side along the x-axis
+    let framing = Framing::from_lonlat(Tan::new(), &points, true).unwrap();
+    assert!((framing.angle().abs() - 0.5 * PI).abs() < 1e-6);
+    assert!(framing.x_bounds().end() - framing.x_bounds().start() > 4.0 * (framing.y_bounds().end() - framing.y_bounds().start()));
+    // All points in the image, with a margin of at least 10 pixels
+    let img = framing.into_img2celestial((300, 100), 10.0);
+    for p in points.iter() {
+      let xy = img.lonlat2img(p).unwrap();
+      assert!((9.5..=289.5).contains(&xy.x()) && (9.5..=89.5).contains(&xy.y()));
+    }
+    // Set containing the North pole
+    let points: Vec<LonLat> = (0..12).map(|i| deg(30.0 * i as f64, 80.0)).collect();
+    let framing = Framing::from_lonlat(Sin::new(), &points, true).unwrap();
+    assert!((framing.center().lat() - 0.5 * PI).abs() < 1e-9);
+    let r = 10_f64.to_radians().sin();
+    assert!(*framing.x_bounds().start() >= -r - 1e-9 && *framing.x_bounds().end() <= r + 1e-9);
+    // Can't be framed
+    assert!(Framing::from_lonlat(Tan::new(), &[deg(0.0, 0.0)], true).is_none());
+    let tetrahedron = [deg(0.0, 90.0), deg(0.0, -19.47), deg(120.0, -19.47), deg(240.0, -19.47)];
+    assert!(Framing::from_lonlat(Tan::new(), &tetrahedron, true).is_none());
+  }
+}
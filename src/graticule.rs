@@ -0,0 +1,293 @@
+//! Module containing the generation of graticules, i.e. of the meridians and parallels to be
+//! drawn on a projection plot or on an image.
+//!
+//! Each line is sampled with a coarse step, adaptively densified according to the projection
+//! curvature, and split where it crosses a projection discontinuity or leaves the projection
+//! valid area.
+
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use crate::{Cone, ImgXY, LonLat, ProjXY, Projection, XYZ};
+use crate::curve::{densify, project_curve};
+use crate::img2celestial::ImgXY2Celestial;
+
+/// Maximum angular distance between the points of the initial (coarse) sampling of a line.
+const COARSE_STEP: f64 = PI / 36.0;
+/// Number of lines (of each kind) aimed at by `Graticule::from_fov`.
+const N_LINES_FOV: f64 = 5.0;
+/// Nice steps larger than 1 arcsec, in arcsec.
+const NICE_STEPS: [f64; 23] = [
+  324000.0, 162000.0, 108000.0, 72000.0, 54000.0, 36000.0, 18000.0, 7200.0, 3600.0,
+  1800.0, 1200.0, 900.0, 600.0, 300.0, 120.0, 60.0,
+  30.0, 20.0, 15.0, 10.0, 5.0, 2.0, 1.0,
+];
+
+/// Kind of graticule line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraticuleLineKind {
+  /// Line of constant longitude.
+  Meridian,
+  /// Line of constant latitude.
+  Parallel,
+}
+
+/// A graticule line, possibly split in several polylines.
+pub struct GraticuleLine<T> {
+  kind: GraticuleLineKind,
+  value: f64,
+  label: String,
+  polylines: Vec<Vec<T>>,
+}
+
+impl<T> GraticuleLine<T> {
+
+  /// Get the kind of line
+  pub fn kind(&self) -> GraticuleLineKind {
+    self.kind
+  }
+
+  /// Get the longitude (for a meridian, in `[0, 2pi[`) or the latitude (for a parallel) of the line, in radians
+  pub fn value(&self) -> f64 {
+    self.value
+  }
+
+  /// Get the label of the line, i.e. its value in sexagesimal degrees with the precision of the graticule step
+  pub fn label(&self) -> &str {
+    &self.label
+  }
+
+  /// Get the polylines of the line (a closed polyline repeating its first point at the end)
+  pub fn polylines(&self) -> &[Vec<T>] {
+    &self.polylines
+  }
+}
+
+/// Graticule generator.
+pub struct Graticule {
+  lon_step: f64,
+  lat_step: f64,
+}
+
+impl Graticule {
+
+  /// # Params
+  /// * `lon_step`: the longitude difference between two meridians, in radians
+  /// * `lat_step`: the latitude difference between two parallels, in radians
+  /// # Panics
+  /// * if a step is not strictly positive
+  pub fn new(lon_step: f64, lat_step: f64) -> Self {
+    assert!(lon_step > 0.0 && lat_step > 0.0);
+    Self { lon_step, lat_step }
+  }
+
+  /// Graticule with a "nice" step (see `nice_step`) adapted to the given field of view.
+  /// # Params
+  /// * `fov`: the size of the field of view, in radians
+  pub fn from_fov(fov: f64) -> Self {
+    let step = nice_step(fov / N_LINES_FOV);
+    Self::new(step, step)
+  }
+
+  /// Get the longitude difference between two meridians, in radians
+  pub fn lon_step(&self) -> f64 {
+    self.lon_step
+  }
+
+  /// Get the latitude difference between two parallels, in radians
+  pub fn lat_step(&self) -> f64 {
+    self.lat_step
+  }
+
+  /// Computes the graticule lines in the projection plane.
+  /// # Params
+  /// * `proj`: the projection (possibly a rotated, i.e. centered, projection)
+  /// * `area`: if set, the lines are limited to the longitude/latitude range of the given cone
+  /// * `tol`: the maximum distance, in the projection plane, between the polylines and the true lines
+  pub fn proj_lines<P: Projection + ?Sized>(&self, proj: &P, area: Option<&Cone>, tol: f64) -> Vec<GraticuleLine<ProjXY>> {
+    self.lines(|xyz| proj.proj_xyz(xyz).map(|p| (p.x(), p.y())), area, tol, |(x, y)| ProjXY::new(x, y))
+  }
+
+  /// Same as `proj_lines`, but in image coordinates (`tol` being in pixels).
+  pub fn img_lines<C: ImgXY2Celestial + ?Sized>(&self, img2celestial: &C, area: Option<&Cone>, tol: f64) -> Vec<GraticuleLine<ImgXY>> {
+    self.lines(|xyz| img2celestial.xyz2img(xyz).map(|p| (p.x(), p.y())), area, tol, |(x, y)| ImgXY::new(x, y))
+  }
+
+  fn lines<T, F, G>(&self, f: F, area: Option<&Cone>, tol: f64, to_t: G) -> Vec<GraticuleLine<T>>
+    where
+      F: Fn(&XYZ) -> Option<(f64, f64)>,
+      G: Fn((f64, f64)) -> T
+  {
+    assert!(tol > 0.0);
+    let ((lon_min, lon_max), (lat_min, lat_max)) = area.map(lonlat_ranges)
+      .unwrap_or(((0.0, 2.0 * PI), (-FRAC_PI_2, FRAC_PI_2)));
+    let full_lon = lon_max - lon_min >= 2.0 * PI;
+    let mut lines = Vec::new();
+    let mut push = |kind, value: f64, points: Vec<XYZ>, closed: bool, step: f64| {
+      let points = densify(&points, closed, &f, tol);
+      let polylines: Vec<Vec<T>> = project_curve(&points, closed, &f).into_iter()
+        .map(|polyline| polyline.into_iter().map(&to_t).collect())
+        .collect();
+      if !polylines.is_empty() {
+        let label = match kind {
+          GraticuleLineKind::Meridian => format_angle(value, step, false),
+          GraticuleLineKind::Parallel => format_angle(value, step, true),
+        };
+        lines.push(GraticuleLine { kind, value, label, polylines });
+      }
+    };
+    // Meridians
+    let k_max = if full_lon {
+      (2.0 * PI / self.lon_step).ceil() as i64 - 1
+    } else {
+      (lon_max / self.lon_step).floor() as i64
+    };
+    for k in (lon_min / self.lon_step).ceil() as i64..=k_max {
+      let lon = k as f64 * self.lon_step;
+      let n = ((lat_max - lat_min) / COARSE_STEP).ceil().max(1.0) as usize;
+      let points = (0..=n)
+        .map(|i| LonLat::new(lon, lat_min + (lat_max - lat_min) * i as f64 / n as f64).to_xyz())
+        .collect();
+      push(GraticuleLineKind::Meridian, lon.rem_euclid(2.0 * PI), points, false, self.lon_step);
+    }
+    // Parallels (poles excluded)
+    for k in (lat_min / self.lat_step).ceil() as i64..=(lat_max / self.lat_step).floor() as i64 {
+      let lat = k as f64 * self.lat_step;
+      if lat.abs() >= FRAC_PI_2 - 1e-12 {
+        continue;
+      }
+      let n = ((lon_max - lon_min) / COARSE_STEP).ceil().max(1.0) as usize;
+      let (points, closed) = if full_lon {
+        ((0..n).map(|i| LonLat::new(2.0 * PI * i as f64 / n as f64, lat).to_xyz()).collect(), true)
+      } else {
+        ((0..=n).map(|i| LonLat::new(lon_min + (lon_max - lon_min) * i as f64 / n as f64, lat).to_xyz()).collect(), false)
+      };
+      push(GraticuleLineKind::Parallel, lat, points, closed, self.lat_step);
+    }
+    lines
+  }
+}
+
+/// Returns the largest "nice" step (e.g. 10 deg, 30 arcmin, 15 arcsec, 0.2 arcsec) lower than or equal
+/// to the given value (the largest nice step being 90 deg).
+/// # Params
+/// * `approx`: the approximate step, in radians
+/// # Panics
+/// * if `approx` is not strictly positive
+pub fn nice_step(approx: f64) -> f64 {
+  assert!(approx > 0.0);
+  let arcsec = approx.to_degrees() * 3600.0;
+  let nice = if arcsec >= 1.0 {
+    NICE_STEPS.iter().find(|s| **s <= arcsec).cloned().unwrap_or(NICE_STEPS[0])
+  } else {
+    // 0.5, 0.2, 0.1, 0.05, ...
+    let p = 10_f64.powf(arcsec.log10().floor());
+    [5.0, 2.0, 1.0].iter().map(|m| m * p).find(|s| *s <= arcsec).unwrap_or(p)
+  };
+  (nice / 3600.0).to_radians()
+}
+
+/// Returns the smallest "nice" step (see `nice_step`) larger than or equal to the given value
+/// (the largest nice step being 90 deg).
+pub(crate) fn nice_step_ceil(approx: f64) -> f64 {
+  let step = nice_step(approx);
+  if step >= approx * (1.0 - 1e-12) {
+    return step;
+  }
+  let arcsec = step.to_degrees() * 3600.0;
+  let next = if arcsec < 1.0 - 1e-9 {
+    let p = 10_f64.powf((arcsec.log10() + 1e-9).floor());
+    let m = (arcsec / p).round();
+    (if m < 1.5 { 2.0 } else if m < 3.5 { 5.0 } else { 10.0 }) * p
+  } else {
+    NICE_STEPS.iter().rev().find(|s| **s > arcsec * (1.0 + 1e-9)).cloned().unwrap_or(NICE_STEPS[0])
+  };
+  (next / 3600.0).to_radians()
+}
+
+/// Returns the longitude and latitude ranges (in radians) covering the given cone.
+fn lonlat_ranges(cone: &Cone) -> ((f64, f64), (f64, f64)) {
+  let (lon, lat, r) = (cone.center().lon(), cone.center().lat(), cone.radius());
+  let (lat_min, lat_max) = ((lat - r).max(-FRAC_PI_2), (lat + r).min(FRAC_PI_2));
+  if lat_min <= -FRAC_PI_2 || lat_max >= FRAC_PI_2 {
+    ((0.0, 2.0 * PI), (lat_min, lat_max))
+  } else {
+    let dlon = (r.sin() / lat.cos()).min(1.0).asin();
+    ((lon - dlon, lon + dlon), (lat_min, lat_max))
+  }
+}
+
+/// Formats the given angle in sexagesimal degrees, with the precision of the given step.
+pub(crate) fn format_angle(angle: f64, step: f64, signed: bool) -> String {
+  let is_multiple = |unit: f64| {
+    let r = step.to_degrees() / unit;
+    (r - r.round()).abs() < 1e-6 * r.max(1.0)
+  };
+  let sign = if signed && angle < -1e-15 { "-" } else if signed { "+" } else { "" };
+  let deg = angle.abs().to_degrees();
+  if is_multiple(1.0) {
+    format!("{}{}°", sign, deg.round())
+  } else if is_multiple(1.0 / 60.0) {
+    let m = (deg * 60.0).round() as u64;
+    format!("{}{}°{:02}'", sign, m / 60, m % 60)
+  } else {
+    // Number of decimals of the arcsec
+    let n_dec = if is_multiple(1.0 / 3600.0) {
+      0
+    } else {
+      (-(step.to_degrees() * 3600.0).log10()).ceil().max(1.0) as u32
+    };
+    let p = 10_u64.pow(n_dec);
+    let units = (deg * 3600.0 * p as f64).round() as u64;
+    let (sec, frac) = (units / p, units % p);
+    let dms = format!("{}{}°{:02}'{:02}", sign, sec / 3600, (sec / 60) % 60, sec % 60);
+    if n_dec == 0 {
+      format!("{}\"", dms)
+    } else {
+      format!("{}.{:0n$}\"", dms, frac, n = n_dec as usize)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::CenteredProjection;
+  use crate::pseudocyl::mol::Mol;
+  use crate::zenithal::tan::Tan;
+
+  #[test]
+  fn test_nice_step() {
+    assert_eq!(nice_step(12_f64.to_radians()).to_degrees(), 10.0);
+    assert!((nice_step(0.7_f64.to_radians()).to_degrees() - 0.5).abs() < 1e-12);
+    assert!((nice_step((0.3_f64 / 3600.0).to_radians()).to_degrees() * 3600.0 - 0.2).abs() < 1e-9);
+    assert_eq!(format_angle(30_f64.to_radians(), 10_f64.to_radians(), false), "30°");
+    assert_eq!(format_angle(-30.5_f64.to_radians(), 0.5_f64.to_radians(), true), "-30°30'");
+    assert_eq!(format_angle((10.0 + 15.0 / 3600.0_f64).to_radians(), (15.0 / 3600.0_f64).to_radians(), true), "+10°00'15\"");
+    assert_eq!(format_angle((1.0 + 0.2 / 3600.0_f64).to_radians(), (0.2 / 3600.0_f64).to_radians(), false), "1°00'00.2\"");
+  }
+
+  #[test]
+  fn test_graticule() {
+    // All-sky: 12 meridians and 5 parallels (-60 to 60), the meridian at lon = 180 being on the cut
+    let lines = Graticule::new(30_f64.to_radians(), 30_f64.to_radians()).proj_lines(&Mol::new(), None, 1e-4);
+    let meridians: Vec<_> = lines.iter().filter(|l| l.kind() == GraticuleLineKind::Meridian).collect();
+    let parallels: Vec<_> = lines.iter().filter(|l| l.kind() == GraticuleLineKind::Parallel).collect();
+    assert_eq!(meridians.len(), 12);
+    assert_eq!(parallels.len(), 5);
+    // Parallels are split at lon = 180, not at lon = 0
+    assert!(parallels.iter().all(|l| l.polylines().len() == 1));
+    assert_eq!(parallels[0].label(), "-60°");
+
+    // Small field of view in TAN
+    let center = LonLat::new(10_f64.to_radians(), 40_f64.to_radians());
+    let mut proj = CenteredProjection::new(Tan::new());
+    proj.set_proj_center_from_lonlat(&center);
+    let fov = 2_f64.to_radians();
+    let graticule = Graticule::from_fov(fov);
+    assert!((graticule.lon_step().to_degrees() - 20.0 / 60.0).abs() < 1e-12);
+    let lines = graticule.proj_lines(&proj, Some(&Cone::new(center, fov / 2.0)), 1e-6);
+    assert!(lines.iter().any(|l| l.label() == "10°00'"));
+    assert!(lines.iter().any(|l| l.label() == "+40°20'"));
+    assert!(lines.iter().all(|l| l.polylines().len() == 1));
+  }
+}
@@ -0,0 +1,231 @@
+//! Module containing an approximate, fast, pixel mapper.
+//!
+//! Like the `tranGrid` function of AST (also used in Montage), the exact mapping is evaluated on
+//! the nodes of a coarse grid and bilinearly interpolated between the nodes.
+//! Each grid cell is recursively subdivided (quadtree) as long as the interpolation error, tested
+//! at the middle of the cell edges and at the cell center, is larger than a given tolerance.
+//! Cells in which the exact mapping fails (e.g. near the border of the valid projection area) or
+//! which can't reach the tolerance are evaluated exactly.
+
+use crate::ImgXY;
+use crate::reproj::PixMapper;
+
+/// Size, in pixels, of the cells of the initial (coarsest) grid.
+const ROOT_CELL_SIZE: f64 = 64.0;
+/// Size, in pixels, under which cells are not subdivided anymore.
+const MIN_CELL_SIZE: f64 = 1.0;
+
+/// A cell of the adaptive grid.
+enum Node {
+  /// Bilinear interpolation from the mapped corners (in the order `(x0, y0)`, `(x1, y0)`, `(x0, y1)`, `(x1, y1)`).
+  Interpolated([(f64, f64); 4]),
+  /// Cell in which the exact mapping is used.
+  Exact,
+  /// Cell subdivided into 4 sub-cells (same order as the corners).
+  Split(Box<[Node; 4]>),
+}
+
+/// Approximate pixel mapper interpolating an exact mapper on an adaptive grid.
+pub struct AdaptiveGridMapper<'a, M: PixMapper + ?Sized> {
+  exact: &'a M,
+  x_min: f64,
+  y_min: f64,
+  n_x: usize,
+  n_y: usize,
+  cell_size: f64,
+  roots: Vec<Node>,
+}
+
+impl<'a, M: PixMapper + ?Sized> AdaptiveGridMapper<'a, M> {
+
+  /// Builds the adaptive grid.
+  /// # Params
+  /// * `exact`: the exact mapper to be approximated
+  /// * `x_range`: range of the input x coordinates covered by the grid (e.g. `target.x_edges()`)
+  /// * `y_range`: range of the input y coordinates covered by the grid (e.g. `target.y_edges()`)
+  /// * `tol`: maximum interpolation error, in output pixels
+  /// # Remark
+  /// * the error being tested on a few points per cell, it is not strictly guaranteed everywhere.
+  /// * inputs outside of the grid ranges are mapped exactly.
+  /// # Panics
+  /// * if `tol` is not strictly positive or if a range is empty
+  pub fn new(exact: &'a M, x_range: (f64, f64), y_range: (f64, f64), tol: f64) -> Self {
+    assert!(tol > 0.0);
+    assert!(x_range.0 < x_range.1 && y_range.0 < y_range.1);
+    let n_x = ((x_range.1 - x_range.0) / ROOT_CELL_SIZE).ceil().max(1.0) as usize;
+    let n_y = ((y_range.1 - y_range.0) / ROOT_CELL_SIZE).ceil().max(1.0) as usize;
+    let cell_size = ((x_range.1 - x_range.0) / n_x as f64).max((y_range.1 - y_range.0) / n_y as f64);
+    let map = |x: f64, y: f64| exact.map(&ImgXY::new(x, y)).map(|p| (p.x(), p.y()));
+    // Exact mapping of the nodes of the initial grid
+    let nodes: Vec<Option<(f64, f64)>> = (0..=n_y)
+      .flat_map(|j| (0..=n_x).map(move |i| (i, j)))
+      .map(|(i, j)| map(x_range.0 + i as f64 * cell_size, y_range.0 + j as f64 * cell_size))
+      .collect();
+    let mut roots = Vec::with_capacity(n_x * n_y);
+    for j in 0..n_y {
+      for i in 0..n_x {
+        let k = j * (n_x + 1) + i;
+        let corners = [nodes[k], nodes[k + 1], nodes[k + n_x + 1], nodes[k + n_x + 2]];
+        let x0 = x_range.0 + i as f64 * cell_size;
+        let y0 = y_range.0 + j as f64 * cell_size;
+        roots.push(build(&map, x0, y0, cell_size, corners, tol));
+      }
+    }
+    Self { exact, x_min: x_range.0, y_min: y_range.0, n_x, n_y, cell_size, roots }
+  }
+
+  /// Returns the number of (leaf) cells of the grid, and the number of them in which the
+  /// exact mapping is used.
+  pub fn n_cells(&self) -> (usize, usize) {
+    fn count(node: &Node) -> (usize, usize) {
+      match node {
+        Node::Interpolated(_) => (1, 0),
+        Node::Exact => (1, 1),
+        Node::Split(children) => children.iter()
+          .map(count)
+          .fold((0, 0), |(a, b), (c, d)| (a + c, b + d)),
+      }
+    }
+    self.roots.iter().map(count).fold((0, 0), |(a, b), (c, d)| (a + c, b + d))
+  }
+}
+
+/// Recursively builds the node of the cell `[x0, x0 + size] x [y0, y0 + size]`.
+fn build<F>(map: &F, x0: f64, y0: f64, size: f64, corners: [Option<(f64, f64)>; 4], tol: f64) -> Node
+  where F: Fn(f64, f64) -> Option<(f64, f64)>
+{
+  let half = 0.5 * size;
+  let (xm, ym, x1, y1) = (x0 + half, y0 + half, x0 + size, y0 + size);
+  // Exact values at the middle of the edges and at the center of the cell
+  let bottom = map(xm, y0);
+  let left = map(x0, ym);
+  let center = map(xm, ym);
+  let right = map(x1, ym);
+  let top = map(xm, y1);
+  let interpolable = match corners {
+    [Some(c00), Some(c10), Some(c01), Some(c11)] => {
+      let c = [c00, c10, c01, c11];
+      [(0.5, 0.0, bottom), (0.0, 0.5, left), (0.5, 0.5, center), (1.0, 0.5, right), (0.5, 1.0, top)]
+        .iter()
+        .all(|(u, v, exact)| match exact {
+          Some((x, y)) => {
+            let (xi, yi) = bilinear(&c, *u, *v);
+            (xi - x).hypot(yi - y) <= tol
+          },
+          None => false,
+        })
+    },
+    _ => false,
+  };
+  if interpolable {
+    Node::Interpolated(corners.map(|c| c.unwrap()))
+  } else if half < MIN_CELL_SIZE {
+    Node::Exact
+  } else {
+    let [c00, c10, c01, c11] = corners;
+    Node::Split(Box::new([
+      build(map, x0, y0, half, [c00, bottom, left, center], tol),
+      build(map, xm, y0, half, [bottom, c10, center, right], tol),
+      build(map, x0, ym, half, [left, center, c01, top], tol),
+      build(map, xm, ym, half, [center, right, top, c11], tol),
+    ]))
+  }
+}
+
+/// Bilinear interpolation, `(u, v)` being the normalized coordinates in the cell.
+fn bilinear(c: &[(f64, f64); 4], u: f64, v: f64) -> (f64, f64) {
+  let (w00, w10, w01, w11) = ((1.0 - u) * (1.0 - v), u * (1.0 - v), (1.0 - u) * v, u * v);
+  (
+    w00 * c[0].0 + w10 * c[1].0 + w01 * c[2].0 + w11 * c[3].0,
+    w00 * c[0].1 + w10 * c[1].1 + w01 * c[2].1 + w11 * c[3].1,
+  )
+}
+
+impl<M: PixMapper + ?Sized> PixMapper for AdaptiveGridMapper<'_, M> {
+  fn map(&self, target_pos: &ImgXY) -> Option<ImgXY> {
+    let u = (target_pos.x() - self.x_min) / self.cell_size;
+    let v = (target_pos.y() - self.y_min) / self.cell_size;
+    if !(0.0..=self.n_x as f64).contains(&u) || !(0.0..=self.n_y as f64).contains(&v) {
+      return self.exact.map(target_pos);
+    }
+    let i = (u as usize).min(self.n_x - 1);
+    let j = (v as usize).min(self.n_y - 1);
+    // Normalized coordinates in the current cell
+    let (mut u, mut v) = (u - i as f64, v - j as f64);
+    let mut node = &self.roots[j * self.n_x + i];
+    loop {
+      match node {
+        Node::Interpolated(c) => {
+          let (x, y) = bilinear(c, u, v);
+          return Some(ImgXY::new(x, y));
+        },
+        Node::Exact => return self.exact.map(target_pos),
+        Node::Split(children) => {
+          let (iu, iv) = ((u >= 0.5) as usize, (v >= 0.5) as usize);
+          u = 2.0 * u - iu as f64;
+          v = 2.0 * v - iv as f64;
+          node = &children[2 * iv + iu];
+        },
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cell::Cell;
+  use crate::{CenteredProjection, LonLat};
+  use crate::bounded::{BoundedImg2Celestial, PixelConvention};
+  use crate::img2celestial::Img2Celestial;
+  use crate::img2proj::WcsImgXY2ProjXY;
+  use crate::pseudocyl::ait::Ait;
+  use crate::reproj::{ExactPixMapper, Interpolation, Resampler};
+  use crate::zenithal::tan::Tan;
+
+  /// Counts the number of calls to the exact mapper.
+  struct Counting<'a, M: PixMapper>(&'a M, Cell<usize>);
+  impl<M: PixMapper> PixMapper for Counting<'_, M> {
+    fn map(&self, target_pos: &ImgXY) -> Option<ImgXY> {
+      self.1.set(self.1.get() + 1);
+      self.0.map(target_pos)
+    }
+  }
+
+  #[test]
+  fn test_adaptive_grid() {
+    // 30 arcsec pixels TAN source image
+    let mut tan = CenteredProjection::new(Tan::new());
+    tan.set_proj_center_from_lonlat(&LonLat::new(1.0, 0.3));
+    let src = BoundedImg2Celestial::new(
+      Img2Celestial::new(WcsImgXY2ProjXY::from_cr(256.5, 256.5, 10.0, -30.0 / 3600.0, 30.0 / 3600.0), tan),
+      512, 512, PixelConvention::Fits
+    );
+    // 40 arcsec pixels AIT target image, slightly offset
+    let mut ait = CenteredProjection::new(Ait::new());
+    ait.set_proj_center_from_lonlat(&LonLat::new(1.02, 0.31));
+    let target = BoundedImg2Celestial::new(
+      Img2Celestial::new(WcsImgXY2ProjXY::from_cr(200.5, 200.5, 0.0, -40.0 / 3600.0, 40.0 / 3600.0), ait),
+      400, 400, PixelConvention::Fits
+    );
+    let exact = ExactPixMapper::new(&src, &target);
+    let counting = Counting(&exact, Cell::new(0));
+    let tol = 0.01;
+    let approx = AdaptiveGridMapper::new(&counting, target.x_edges(), target.y_edges(), tol);
+    assert!(counting.1.get() < 400 * 400 / 10);
+    assert_eq!(approx.n_cells().1, 0);
+    for j in (0..400).step_by(7) {
+      for i in (0..400).step_by(3) {
+        let pos = target.pixel_center(i, j);
+        let a = approx.map(&pos).unwrap();
+        let e = exact.map(&pos).unwrap();
+        assert!((a.x() - e.x()).hypot(a.y() - e.y()) < tol);
+      }
+    }
+    // Usable by the reprojection
+    let data = vec![1.0; 512 * 512];
+    let resampler = Resampler::new(Interpolation::Bilinear);
+    let (values, _) = resampler.reproject_with_mapper(&data, &src, &target, &approx);
+    assert_eq!(values[200 * 400 + 200], 1.0);
+  }
+}
@@ -0,0 +1,395 @@
+//! Module containing a minimal HEALPix (NESTED scheme) implementation, based on the `HPX`
+//! projection in which each HEALPix cell is a square rotated by 45 degrees (a diamond).
+//!
+//! In the (scaled) `HPX` plane `x = 4 X / pi` in `[0, 8[`, `y = 4 Y / pi` in `[-2, 2]`,
+//! the 12 base cells are diamonds of L1-radius 1 centered on:
+//! * `(1 + 2i, 1)` for the North polar cells `i` in `[0, 3]`;
+//! * `(2i, 0)` for the equatorial cells `4 + i`;
+//! * `(1 + 2i, -1)` for the South polar cells `8 + i`.
+//!
+//! Inside a base cell, a cell of depth `d` is identified by its coordinates `(i, j)` in `[0, 2^d[`,
+//! `i` increasing from the South vertex toward the East vertex and `j` from the South vertex toward
+//! the West vertex, the NESTED index being `base << 2d | interleave(i, j)` (`i` on the even bits).
+//! The RING scheme indices are obtained by conversion from the NESTED ones.
+//!
+//! For more information, see [Gorski et al. 2005](https://doi.org/10.1086/427976) and
+//! [Calabretta & Roukema 2007](https://doi.org/10.1111/j.1365-2966.2007.12297.x).
+
+use std::f64::consts::PI;
+
+use crate::{CanonicalProjection, Cone, LonLat, ProjXY, XYZ};
+use crate::hybrid::hpx::Hpx;
+use crate::sphere::ang_dist;
+
+/// Ring number (times `nside`) of the South vertex of each base cell, starting from the North pole.
+const JRLL: [i64; 12] = [2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4];
+/// Longitude (in units of `pi/4`) of the center of each base cell.
+const JPLL: [i64; 12] = [1, 3, 5, 7, 0, 2, 4, 6, 1, 3, 5, 7];
+
+/// HEALPix numbering scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+  /// Hierarchical numbering.
+  Nested,
+  /// Numbering along the iso-latitude rings, from North to South, and from West to East in each ring.
+  Ring,
+}
+
+/// Maximum supported depth.
+pub const MAX_DEPTH: u8 = 29;
+
+/// Returns the number of cells along the side of a base cell at the given depth, i.e. `2^depth`.
+pub fn nside(depth: u8) -> u32 {
+  assert!(depth <= MAX_DEPTH);
+  1_u32 << depth
+}
+
+/// Returns the number of cells covering the sphere at the given depth, i.e. `12 * 4^depth`.
+pub fn n_hash(depth: u8) -> u64 {
+  assert!(depth <= MAX_DEPTH);
+  12_u64 << (depth << 1)
+}
+
+/// Returns the depth of the given `nside` (`None` if `nside` is not a power of 2).
+pub fn depth_from_nside(nside: u32) -> Option<u8> {
+  if nside.is_power_of_two() && nside.trailing_zeros() <= MAX_DEPTH as u32 {
+    Some(nside.trailing_zeros() as u8)
+  } else {
+    None
+  }
+}
+
+/// Returns the NESTED index of the cell of given depth containing the given position.
+/// # Panics
+/// * if `depth` is larger than `MAX_DEPTH`
+pub fn hash(depth: u8, lonlat: &LonLat) -> u64 {
+  hash_xyz(depth, &lonlat.to_xyz())
+}
+
+/// Same as `hash`, taking Euclidean coordinates.
+pub fn hash_xyz(depth: u8, xyz: &XYZ) -> u64 {
+  let pos = Hpx::new().proj(xyz).expect("HPX is defined on the whole sphere");
+  let (x, y) = to_plane(&pos);
+  hash_plane(depth, x, y).expect("The projection of a position is inside a base cell")
+}
+
+/// Returns the index, in the given scheme, of the cell of given depth containing the given position.
+pub fn hash_with_scheme(depth: u8, scheme: Scheme, xyz: &XYZ) -> u64 {
+  let h = hash_xyz(depth, xyz);
+  match scheme {
+    Scheme::Nested => h,
+    Scheme::Ring => to_ring(depth, h),
+  }
+}
+
+/// Converts a NESTED index into a RING index.
+/// # Panics
+/// * if `hash` is not a valid index at the given depth
+pub fn to_ring(depth: u8, hash: u64) -> u64 {
+  let (base, i, j) = decompose(depth, hash);
+  let nside = nside(depth) as i64;
+  let (i, j, base) = (i as i64, j as i64, base as usize);
+  let nl4 = 4 * nside;
+  // Ring number, starting at 1 at the North pole
+  let jr = JRLL[base] * nside - i - j - 1;
+  let (nr, n_before, kshift) = if jr < nside {
+    (jr, 2 * jr * (jr - 1), 0)
+  } else if jr > 3 * nside {
+    let nr = nl4 - jr;
+    (nr, n_hash(depth) as i64 - 2 * (nr + 1) * nr, 0)
+  } else {
+    (nside, 2 * nside * (nside - 1) + (jr - nside) * nl4, (jr - nside) & 1)
+  };
+  // Index in the ring, starting at 1
+  let mut jp = (JPLL[base] * nr + i - j + 1 + kshift) / 2;
+  if jp > nl4 {
+    jp -= nl4;
+  } else if jp < 1 {
+    jp += nl4;
+  }
+  (n_before + jp - 1) as u64
+}
+
+/// Converts a RING index into a NESTED index.
+/// # Panics
+/// * if `hash` is not a valid index at the given depth
+pub fn from_ring(depth: u8, hash: u64) -> u64 {
+  let n_hash = n_hash(depth) as i64;
+  assert!((hash as i64) < n_hash);
+  let nside = nside(depth) as i64;
+  let (nl2, ncap, pix) = (2 * nside, 2 * nside * (nside - 1), hash as i64);
+  // Ring number (starting at 1 at the North pole), index in the ring (starting at 1), ...
+  let (iring, iphi, kshift, nr, base) = if pix < ncap {
+    let iring = (1 + isqrt(1 + 2 * pix)) >> 1;
+    let iphi = pix + 1 - 2 * iring * (iring - 1);
+    (iring, iphi, 0, iring, (iphi - 1) / iring)
+  } else if pix < n_hash - ncap {
+    let ip = pix - ncap;
+    let tmp = ip / (4 * nside);
+    let iring = tmp + nside;
+    let iphi = ip - tmp * 4 * nside + 1;
+    let ire = tmp + 1;
+    let irm = nl2 + 1 - tmp;
+    let ifm = (iphi - ire / 2 + nside - 1) / nside;
+    let ifp = (iphi - irm / 2 + nside - 1) / nside;
+    let base = match ifp.cmp(&ifm) {
+      std::cmp::Ordering::Equal => ifp | 4,
+      std::cmp::Ordering::Less => ifp,
+      std::cmp::Ordering::Greater => ifm + 8,
+    };
+    (iring, iphi, (iring + nside) & 1, nside, base)
+  } else {
+    let ip = n_hash - pix;
+    let nr = (1 + isqrt(2 * ip - 1)) >> 1;
+    let iphi = 4 * nr + 1 - (ip - 2 * nr * (nr - 1));
+    (2 * nl2 - nr, iphi, 0, nr, 8 + (iphi - 1) / nr)
+  };
+  let irt = iring - JRLL[base as usize] * nside + 1;
+  let mut ipt = 2 * iphi - JPLL[base as usize] * nr - kshift - 1;
+  if ipt >= nl2 {
+    ipt -= 8 * nside;
+  }
+  let (i, j) = ((ipt - irt) >> 1, (-ipt - irt) >> 1);
+  ((base as u64) << (depth << 1)) | interleave(i as u64, j as u64)
+}
+
+/// Integer square root.
+fn isqrt(v: i64) -> i64 {
+  let mut r = (v as f64).sqrt() as i64;
+  while r * r > v {
+    r -= 1;
+  }
+  while (r + 1) * (r + 1) <= v {
+    r += 1;
+  }
+  r
+}
+
+/// Returns the NESTED index of the cell containing the given point of the scaled `HPX`
+/// plane (`x` in `[0, 8[`, `y` in `[-2, 2]`), `None` if the point is not in a base cell.
+pub(crate) fn hash_plane(depth: u8, x: f64, y: f64) -> Option<u64> {
+  let (base, u, v) = base_cell_and_local(x, y)?;
+  let nside = nside(depth) as f64;
+  let n_max = (1_u64 << depth) - 1;
+  let i = ((u * nside) as u64).min(n_max);
+  let j = ((v * nside) as u64).min(n_max);
+  Some(((base as u64) << (depth << 1)) | interleave(i, j))
+}
+
+/// Returns the base cell containing the given point of the scaled `HPX` plane and the
+/// local coordinates `(u, v)` in `[0, 1]` of the point in the base cell
+/// (`u` from the South vertex toward the East vertex, `v` from the South vertex toward the West vertex).
+/// Returns `None` if `y` is not in `[-2, 2]`.
+/// # Remark
+/// * in the polar caps (`|y| > 1`), the base cell is given by `x` since the diamonds of the
+///   lattice are there cut into triangles: the poles and the cut lines between the triangles
+///   (`x` even) are then assigned to one of the adjacent base cells.
+pub(crate) fn base_cell_and_local(x: f64, y: f64) -> Option<(u8, f64, f64)> {
+  let (base, cx, cy) = if y.abs() > 1.0 {
+    if y.abs() > 2.0 {
+      return None;
+    }
+    let i = ((x * 0.5).floor() as i32).clamp(0, 3);
+    if y > 0.0 {
+      (i as u8, (1 + 2 * i) as f64, 1.0)
+    } else {
+      (8 + i as u8, (1 + 2 * i) as f64, -1.0)
+    }
+  } else {
+    let p = ((x + y + 1.0) * 0.5).floor();
+    let q = ((x - y + 1.0) * 0.5).floor();
+    let (cx, cy) = (p + q, p - q);
+    let base = match cy as i32 {
+      1 => ((cx as i32 - 1) / 2) as u8,
+      0 => 4 + ((cx as i32 / 2) % 4) as u8,
+      -1 => 8 + ((cx as i32 - 1) / 2) as u8,
+      _ => return None,
+    };
+    (base, cx, cy)
+  };
+  let (dx, dy) = (x - cx, y - cy);
+  Some((base, ((dx + dy + 1.0) * 0.5).clamp(0.0, 1.0), ((dy - dx + 1.0) * 0.5).clamp(0.0, 1.0)))
+}
+
+/// Returns the center, in the scaled `HPX` plane, of the given base cell.
+pub(crate) fn base_cell_center(base: u8) -> (f64, f64) {
+  assert!(base < 12);
+  match base / 4 {
+    0 => (1.0 + 2.0 * base as f64, 1.0),
+    1 => (2.0 * (base - 4) as f64, 0.0),
+    _ => (1.0 + 2.0 * (base - 8) as f64, -1.0),
+  }
+}
+
+/// Returns the point of the scaled `HPX` plane of given local coordinates `(u, v)` in the
+/// given base cell.
+pub(crate) fn local_to_plane(base: u8, u: f64, v: f64) -> (f64, f64) {
+  let (cx, cy) = base_cell_center(base);
+  (cx + u - v, cy + u + v - 1.0)
+}
+
+/// Returns the base cell and the coordinates `(i, j)` of the given cell in its base cell.
+/// # Panics
+/// * if `hash` is not a valid index at the given depth
+pub fn decompose(depth: u8, hash: u64) -> (u8, u32, u32) {
+  assert!(hash < n_hash(depth));
+  let base = (hash >> (depth << 1)) as u8;
+  let (i, j) = deinterleave(hash & ((1_u64 << (depth << 1)) - 1));
+  (base, i as u32, j as u32)
+}
+
+/// Returns the position on the sphere of the point of local coordinates `(du, dv)`
+/// (in `[0, 1]`, `(0.5, 0.5)` being the center) in the given cell.
+pub fn sph_coo(depth: u8, hash: u64, du: f64, dv: f64) -> XYZ {
+  let (base, i, j) = decompose(depth, hash);
+  let nside = nside(depth) as f64;
+  let (x, y) = local_to_plane(base, (i as f64 + du) / nside, (j as f64 + dv) / nside);
+  unproj_plane(x, y)
+}
+
+/// Returns the center of the given cell.
+pub fn center(depth: u8, hash: u64) -> LonLat {
+  sph_coo(depth, hash, 0.5, 0.5).to_lonlat()
+}
+
+/// Returns the 4 vertices of the given cell, in the order South, East, North, West.
+pub fn vertices(depth: u8, hash: u64) -> [LonLat; 4] {
+  [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]
+    .map(|(du, dv)| sph_coo(depth, hash, du, dv).to_lonlat())
+}
+
+/// Returns a cone containing the given cell (a bit larger than the smallest one).
+pub fn bounding_cone(depth: u8, hash: u64) -> Cone {
+  let center = sph_coo(depth, hash, 0.5, 0.5);
+  // Vertices and middle of the edges (the edges are not great-circle arcs)
+  let radius = [(0.0, 0.0), (0.5, 0.0), (1.0, 0.0), (1.0, 0.5), (1.0, 1.0), (0.5, 1.0), (0.0, 1.0), (0.0, 0.5)]
+    .iter()
+    .map(|(du, dv)| ang_dist(&center, &sph_coo(depth, hash, *du, *dv)))
+    .fold(0.0, f64::max);
+  Cone::new(center.to_lonlat(), (1.1 * radius).min(PI))
+}
+
+/// Transforms a point of the `HPX` projection plane into a point of the scaled plane.
+pub(crate) fn to_plane(pos: &ProjXY) -> (f64, f64) {
+  let x = pos.x() * 4.0 / PI;
+  (if x < 0.0 { x + 8.0 } else { x }, pos.y() * 4.0 / PI)
+}
+
+/// Transforms a point of the scaled plane into a point of the `HPX` projection plane.
+pub(crate) fn from_plane(x: f64, y: f64) -> ProjXY {
+  let x = if x >= 4.0 { x - 8.0 } else { x };
+  ProjXY::new(x * PI / 4.0, y * PI / 4.0)
+}
+
+/// Deprojects a point of the scaled plane (supposedly inside a base cell).
+pub(crate) fn unproj_plane(x: f64, y: f64) -> XYZ {
+  let y = y.clamp(-2.0, 2.0);
+  Hpx::new().unproj(&from_plane(x, y))
+    // Numerical issues at the poles, where x is not relevant
+    .unwrap_or_else(|| XYZ::new(0.0, 0.0, y.signum()))
+}
+
+/// Interleaves the bits of `i` (even bits) and `j` (odd bits).
+pub(crate) fn interleave(i: u64, j: u64) -> u64 {
+  spread(i) | (spread(j) << 1)
+}
+
+/// Inverse of `interleave`.
+pub(crate) fn deinterleave(h: u64) -> (u64, u64) {
+  (compact(h), compact(h >> 1))
+}
+
+/// Puts the 32 lowest bits of `v` on the even bits of the result.
+fn spread(v: u64) -> u64 {
+  let mut v = v & 0x00000000FFFFFFFF;
+  v = (v | (v << 16)) & 0x0000FFFF0000FFFF;
+  v = (v | (v << 8)) & 0x00FF00FF00FF00FF;
+  v = (v | (v << 4)) & 0x0F0F0F0F0F0F0F0F;
+  v = (v | (v << 2)) & 0x3333333333333333;
+  (v | (v << 1)) & 0x5555555555555555
+}
+
+/// Inverse of `spread`.
+fn compact(v: u64) -> u64 {
+  let mut v = v & 0x5555555555555555;
+  v = (v | (v >> 1)) & 0x3333333333333333;
+  v = (v | (v >> 2)) & 0x0F0F0F0F0F0F0F0F;
+  v = (v | (v >> 4)) & 0x00FF00FF00FF00FF;
+  v = (v | (v >> 8)) & 0x0000FFFF0000FFFF;
+  (v | (v >> 16)) & 0x00000000FFFFFFFF
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_nested() {
+    // Values computed with the `ang2pix_nest` algorithm of the HEALPix C++ library
+    assert_eq!(hash(0, &LonLat::new(0.0, 0.0)), 4);
+    assert_eq!(hash(0, &LonLat::new(45_f64.to_radians(), 60_f64.to_radians())), 0);
+    assert_eq!(hash(0, &LonLat::new(315_f64.to_radians(), -60_f64.to_radians())), 11);
+    assert_eq!(hash(1, &LonLat::new(10_f64.to_radians(), 20_f64.to_radians())), 19);
+    assert_eq!(hash(4, &LonLat::new(123.4_f64.to_radians(), -5.6_f64.to_radians())), 1360);
+    assert_eq!(hash(3, &LonLat::new(200_f64.to_radians(), 75_f64.to_radians())), 190);
+    assert_eq!(hash(5, &LonLat::new(17_f64.to_radians(), -80_f64.to_radians())), 8227);
+    // Center of each cell is in the cell
+    for depth in 0..4 {
+      for h in 0..n_hash(depth) {
+        assert_eq!(hash(depth, &center(depth, h)), h);
+      }
+    }
+  }
+
+  #[test]
+  fn test_ring() {
+    // Values computed with the `nest2ring` algorithm of the HEALPix C++ library
+    for (depth, nested, ring) in [(0, 5, 5), (1, 19, 12), (2, 100, 113), (3, 190, 8), (4, 1360, 1718), (5, 8227, 12177)] {
+      assert_eq!(to_ring(depth, nested), ring);
+      assert_eq!(from_ring(depth, ring), nested);
+    }
+    for depth in 0..5 {
+      let mut rings: Vec<u64> = (0..n_hash(depth)).map(|h| to_ring(depth, h)).collect();
+      assert!(rings.iter().enumerate().all(|(h, r)| from_ring(depth, *r) == h as u64));
+      rings.sort_unstable();
+      assert!(rings.iter().enumerate().all(|(k, r)| *r == k as u64));
+    }
+  }
+
+  #[test]
+  fn test_poles_and_polar_cuts() {
+    // Values computed with the `ang2pix_nest` algorithm of the HEALPix C++ library
+    assert_eq!(hash(0, &LonLat::new(0.0, 0.5 * PI)), 0);
+    assert_eq!(hash(0, &LonLat::new(100_f64.to_radians(), 0.5 * PI)), 1);
+    assert_eq!(hash(0, &LonLat::new(0.0, -0.5 * PI)), 8);
+    assert_eq!(hash(0, &LonLat::new(280_f64.to_radians(), -0.5 * PI)), 11);
+    // The returned cell contains the position, at the poles, on the polar cap cuts and on the
+    // limits between the polar caps and the equatorial region
+    let lat_t = (2_f64 / 3.0).asin();
+    let mut positions = vec![LonLat::new(0.0, 0.5 * PI), LonLat::new(0.0, -0.5 * PI)];
+    for k in 0..4 {
+      let lon = k as f64 * 0.5 * PI;
+      for lat in [1.2, 1.5, -1.2, -1.5, lat_t, -lat_t] {
+        positions.push(LonLat::new(lon, lat));
+      }
+    }
+    for lon in (0..360).map(|d| (d as f64 + 0.5).to_radians()) {
+      positions.push(LonLat::new(lon, lat_t));
+      positions.push(LonLat::new(lon, -lat_t));
+    }
+    for depth in [0, 3, 7] {
+      for lonlat in positions.iter() {
+        let h = hash(depth, lonlat);
+        let cone = bounding_cone(depth, h);
+        assert!(ang_dist(&cone.center().to_xyz(), &lonlat.to_xyz()) <= cone.radius(), "{} {:?}", depth, lonlat);
+      }
+    }
+    // Regular grid including the poles and the polar cap cuts
+    for i in 0..=360 {
+      for j in 0..=180 {
+        let lonlat = LonLat::new((i as f64).to_radians(), (j as f64 - 90.0).to_radians());
+        hash(7, &lonlat);
+      }
+    }
+  }
+}
@@ -0,0 +1,220 @@
+//! Module containing the generation of [HiPS](https://www.ivoa.net/documents/HiPS/) tiles from an image.
+//!
+//! A HiPS tile of order `k` and index `ipix` is made of the `512 x 512` HEALPix cells of depth `k + 9`
+//! it contains. The pixel `(i, j)` of a tile, stored at index `j * 512 + i` in the tile array,
+//! is the cell of NESTED index `ipix << 18 | interleave(i, j)`, i.e. `i` increases from the South
+//! vertex toward the East vertex of the tile and `j` from the South vertex toward the West vertex
+//! (see the `healpix` module).
+
+use crate::{CanonicalProjection, ImgXY, XYZ};
+use crate::bounded::{BoundedImg2Celestial, PixelConvention, DEFAULT_FOOTPRINT_TOL};
+use crate::healpix::{self, base_cell_and_local, decompose, local_to_plane, to_plane, unproj_plane};
+use crate::hybrid::hpx::Hpx;
+use crate::img2celestial::ImgXY2Celestial;
+use crate::reproj::Resampler;
+use crate::sphere::ang_dist;
+
+/// Number of pixels along the side of a tile.
+pub const TILE_WIDTH: u32 = 1 << TILE_DEPTH;
+/// Depth difference between a tile and its pixels.
+const TILE_DEPTH: u8 = 9;
+/// Maximum order of a tile (so that the depth of its pixels is not larger than `healpix::MAX_DEPTH`).
+pub const MAX_ORDER: u8 = healpix::MAX_DEPTH - TILE_DEPTH;
+/// Number of points along each side of a tile used to test if the tile overlaps an image.
+const N_SAMPLES: u32 = 16;
+
+/// Transformation from the (zero-based) pixel coordinates of a tile to celestial coordinates.
+pub struct HipsTileImg2Celestial {
+  base: u8,
+  nside: f64,
+  i: f64,
+  j: f64,
+}
+
+impl HipsTileImg2Celestial {
+
+  /// # Params
+  /// * `order`: the order of the tile
+  /// * `ipix`: the NESTED index of the tile
+  /// # Panics
+  /// * if `order` is larger than `MAX_ORDER` or if `ipix` is not a valid index
+  pub fn new(order: u8, ipix: u64) -> Self {
+    assert!(order <= MAX_ORDER);
+    let (base, i, j) = decompose(order, ipix);
+    Self { base, nside: healpix::nside(order) as f64, i: i as f64, j: j as f64 }
+  }
+
+  /// Returns the tile geometry, i.e. the `TILE_WIDTH x TILE_WIDTH` pixels
+  /// (`PixelConvention::ZeroBased`) of the tile.
+  pub fn into_bounded(self) -> BoundedImg2Celestial<Self> {
+    BoundedImg2Celestial::new(self, TILE_WIDTH, TILE_WIDTH, PixelConvention::ZeroBased)
+  }
+}
+
+impl ImgXY2Celestial for HipsTileImg2Celestial {
+  fn img2xyz(&self, img_pos: &ImgXY) -> Option<XYZ> {
+    let w = TILE_WIDTH as f64;
+    let u = (self.i + (img_pos.x() + 0.5) / w) / self.nside;
+    let v = (self.j + (img_pos.y() + 0.5) / w) / self.nside;
+    if (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v) {
+      let (x, y) = local_to_plane(self.base, u, v);
+      Some(unproj_plane(x, y))
+    } else {
+      None
+    }
+  }
+
+  /// Returns `None` for positions outside of the base cell containing the tile.
+  fn xyz2img(&self, xyz: &XYZ) -> Option<ImgXY> {
+    let (x, y) = to_plane(&Hpx::new().proj(xyz)?);
+    let (base, u, v) = base_cell_and_local(x, y)?;
+    if base != self.base {
+      return None;
+    }
+    let w = TILE_WIDTH as f64;
+    Some(ImgXY::new((u * self.nside - self.i) * w - 0.5, (v * self.nside - self.j) * w - 0.5))
+  }
+}
+
+/// A HiPS tile.
+pub struct HipsTile {
+  order: u8,
+  ipix: u64,
+  data: Vec<f64>,
+}
+
+impl HipsTile {
+
+  /// Get the order of the tile
+  pub fn order(&self) -> u8 {
+    self.order
+  }
+
+  /// Get the NESTED index of the tile
+  pub fn ipix(&self) -> u64 {
+    self.ipix
+  }
+
+  /// Get the `TILE_WIDTH x TILE_WIDTH` pixel values, row by row
+  pub fn data(&self) -> &[f64] {
+    &self.data
+  }
+
+  /// Returns the pixel values, row by row.
+  pub fn into_data(self) -> Vec<f64> {
+    self.data
+  }
+}
+
+/// Returns the NESTED indices, sorted, of the tiles of given order overlapping the image footprint.
+/// # Return
+/// * `None` if the image footprint can't be computed
+/// # Remark
+/// * the overlap is tested on a grid of points in each tile and on the footprint vertices, so a tile
+///   overlapping the image on an area much smaller than the tile may be missed.
+/// # Panics
+/// * if `order` is larger than `MAX_ORDER`
+pub fn overlapping_tiles<T: ImgXY2Celestial>(img: &BoundedImg2Celestial<T>, order: u8) -> Option<Vec<u64>> {
+  assert!(order <= MAX_ORDER);
+  let footprint = img.footprint_xyz(DEFAULT_FOOTPRINT_TOL)?;
+  let cone = img.bounding_cone()?;
+  let cone_center = cone.center().to_xyz();
+  let mut candidates: Vec<u64> = footprint.iter().map(|v| healpix::hash_xyz(order, v)).collect();
+  // Depth-first exploration of the cells overlapping the bounding cone of the image
+  let mut stack: Vec<(u8, u64)> = (0..12).map(|h| (0, h)).collect();
+  while let Some((depth, h)) = stack.pop() {
+    let cell_cone = healpix::bounding_cone(depth, h);
+    if ang_dist(&cone_center, &cell_cone.center().to_xyz()) > cone.radius() + cell_cone.radius() {
+      continue;
+    }
+    if depth < order {
+      stack.extend((0..4).map(|k| (depth + 1, (h << 2) | k)));
+    } else {
+      let step = 1.0 / N_SAMPLES as f64;
+      let overlap = (0..=N_SAMPLES)
+        .flat_map(|a| (0..=N_SAMPLES).map(move |b| (a as f64 * step, b as f64 * step)))
+        .any(|(du, dv)| img.contains_xyz(&healpix::sph_coo(depth, h, du, dv)));
+      if overlap {
+        candidates.push(h);
+      }
+    }
+  }
+  candidates.sort_unstable();
+  candidates.dedup();
+  Some(candidates)
+}
+
+/// Resamples an image into the HiPS tiles of given order overlapping it.
+/// # Params
+/// * `data`: values of the image pixels, row by row
+/// * `img`: geometry of the image
+/// * `order`: the order of the tiles
+/// * `resampler`: the resampler (interpolation kernel and blank value)
+/// # Return
+/// * `None` if the image footprint can't be computed
+/// * else, the tiles (sorted by NESTED index) containing at least one pixel with a valid value
+/// # Panics
+/// * if `order` is larger than `MAX_ORDER`
+/// * if the length of `data` is not `img.naxis1() * img.naxis2()`
+pub fn hips_tiles<T: ImgXY2Celestial>(
+  data: &[f64],
+  img: &BoundedImg2Celestial<T>,
+  order: u8,
+  resampler: &Resampler
+) -> Option<Vec<HipsTile>> {
+  let tiles = overlapping_tiles(img, order)?
+    .into_iter()
+    .filter_map(|ipix| {
+      let tile = HipsTileImg2Celestial::new(order, ipix).into_bounded();
+      let (values, footprint) = resampler.reproject_with_footprint(data, img, &tile);
+      if footprint.iter().any(|w| *w > 0.0) {
+        Some(HipsTile { order, ipix, data: values })
+      } else {
+        None
+      }
+    })
+    .collect();
+  Some(tiles)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::LonLat;
+  use crate::bounded::tan_image;
+  use crate::healpix::interleave;
+  use crate::reproj::Interpolation;
+
+  #[test]
+  fn test_hips_tiles() {
+    // 200 x 200 pixels of 1 arcmin centered on (lon=30, lat=50)
+    let center = LonLat::new(30_f64.to_radians(), 50_f64.to_radians());
+    let img = tan_image(&center, (100.5, 100.5), 0.0, 1.0 / 60.0, (200, 200));
+    // Order 3 tiles are ~7.3 deg wide
+    let order = 3;
+    let tiles = overlapping_tiles(&img, order).unwrap();
+    assert!(!tiles.is_empty() && tiles.len() <= 4);
+    assert!(tiles.contains(&healpix::hash(order, &center)));
+
+    // Pixel to cell consistency
+    let ipix = healpix::hash(order, &center);
+    let tile = HipsTileImg2Celestial::new(order, ipix);
+    let (i, j) = (123, 456);
+    let xyz = tile.img2xyz(&ImgXY::new(i as f64, j as f64)).unwrap();
+    let cell = healpix::hash_xyz(order + TILE_DEPTH, &xyz);
+    assert_eq!(cell, (ipix << (2 * TILE_DEPTH)) | interleave(i, j));
+    let pos = tile.xyz2img(&xyz).unwrap();
+    assert!((pos.x() - i as f64).abs() < 1e-6 && (pos.y() - j as f64).abs() < 1e-6);
+
+    // Resampling of a constant image
+    let data = vec![2.0; 200 * 200];
+    let tiles = hips_tiles(&data, &img, order, &Resampler::new(Interpolation::Bilinear)).unwrap();
+    assert!(!tiles.is_empty());
+    let tile = tiles.iter().find(|t| t.ipix() == ipix).unwrap();
+    assert_eq!(tile.data().len(), (TILE_WIDTH * TILE_WIDTH) as usize);
+    let pos = HipsTileImg2Celestial::new(order, ipix).xyz2img(&center.to_xyz()).unwrap();
+    let k = pos.y().round() as usize * TILE_WIDTH as usize + pos.x().round() as usize;
+    assert_eq!(tile.data()[k], 2.0);
+    assert!(tile.data().iter().any(|v| v.is_nan()));
+  }
+}
@@ -2,7 +2,7 @@
 
 use std::f64::consts::PI;
 
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{closed_ccw_polyline, lonlat_unproj_jacobian, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
 use crate::math::HALF_PI;
 
 /// Mask to keep only the f64 sign
@@ -54,6 +54,29 @@ impl CanonicalProjection for Hpx {
     &PROJ_BOUNDS
   }
 
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    // Sawtooth made of the 4 North and 4 South polar triangles on top of the equatorial band
+    let south = (-4..=4).map(|i| (i as f64, if i & 1 == 0 { -1.0 } else { -2.0 }));
+    let north = (-4..=4).rev().map(|i| (i as f64, if i & 1 == 0 { 1.0 } else { 2.0 }));
+    Some(closed_ccw_polyline(
+      south.chain(north)
+        .map(|(x, y)| ProjXY::new(x * PI_OVER_FOUR, y * PI_OVER_FOUR))
+        .collect()
+    ))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    let z = pos.y * FOUR_OVER_PI;
+    let x = pos.x * FOUR_OVER_PI;
+    if !(-2f64..=2f64).contains(&z) || !(-4f64..4f64).contains(&x) {
+      false
+    } else if (-1.0..=1.0).contains(&z) {
+      true
+    } else { // Polar triangles
+      (2.0 - z.abs()) >= pm1_offset_decompose(x.abs()).pm1.abs()
+    }
+  }
+
   /// Returns the projection, in the 2D Euclidean plane, of the given position on the unit sphere.
   /// # Output
   /// * `X`: coordinate along the X-axis in the projection plane, in `[-PI, PI]`
@@ -90,37 +113,29 @@ impl CanonicalProjection for Hpx {
   fn unproj(&self, pos: &ProjXY) -> Option<XYZ> {
     let mut z = pos.y * FOUR_OVER_PI;
     let x = pos.x * FOUR_OVER_PI;
-    if !(-2f64..=2f64).contains(&z) || !(-4f64..4f64).contains(&x) {
+    if !self.is_in_valid_proj_area(pos) {
       None
     } else if z > 1.0 {
       // North polar cap
       let x = abs_sign_decompose(x);
       let OffsetAndPM1 { offset, mut pm1 } = pm1_offset_decompose(x.abs);
-      if (2.0 - z) >= pm1.abs() {
-        deproj_collignon(&mut pm1, &mut z);
-        apply_offset_and_signs(&mut pm1, offset, x.sign);
-        pm1 *= PI_OVER_FOUR;
-        let (sinb, cosb) = z.sin_cos();
-        let (sinl, cosl) = pm1.sin_cos();
-        Some(XYZ::new(cosl * cosb, sinl * cosb, sinb))
-      } else {
-        None
-      }
+      deproj_collignon(&mut pm1, &mut z);
+      apply_offset_and_signs(&mut pm1, offset, x.sign);
+      pm1 *= PI_OVER_FOUR;
+      let (sinb, cosb) = z.sin_cos();
+      let (sinl, cosl) = pm1.sin_cos();
+      Some(XYZ::new(cosl * cosb, sinl * cosb, sinb))
     } else if z < -1.0 {
       // South polar cap
       let x = abs_sign_decompose(x);
       let OffsetAndPM1 { offset, mut pm1 } = pm1_offset_decompose(x.abs);
       z = -z;
-      if (2.0 - z) >= pm1.abs() {
-        deproj_collignon(&mut pm1, &mut z);
-        apply_offset_and_signs(&mut pm1, offset, x.sign);
-        pm1 *= PI_OVER_FOUR;
-        let (sinb, cosb) = (-z).sin_cos();
-        let (sinl, cosl) = pm1.sin_cos();
-        Some(XYZ::new(cosl * cosb, sinl * cosb, sinb))
-      } else {
-        None
-      }
+      deproj_collignon(&mut pm1, &mut z);
+      apply_offset_and_signs(&mut pm1, offset, x.sign);
+      pm1 *= PI_OVER_FOUR;
+      let (sinb, cosb) = (-z).sin_cos();
+      let (sinl, cosl) = pm1.sin_cos();
+      Some(XYZ::new(cosl * cosb, sinl * cosb, sinb))
     }  else {
       // Equatorial region
       let z = z * TRANSITION_Z; // z = sin(lat) = sinb
@@ -129,6 +144,30 @@ impl CanonicalProjection for Hpx {
       Some(XYZ::new(cosl * cosb, sinl * cosb, z))
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    let xyz = self.unproj(pos)?;
+    let y = pos.y * FOUR_OVER_PI;
+    if y.abs() <= 1.0 {
+      // Equatorial region: lon = X, sin(lat) = 2/3 (4/pi) Y
+      let cosb = (xyz.x.pow2() + xyz.y.pow2()).sqrt();
+      lonlat_unproj_jacobian(&xyz, [1.0, 0.0], [0.0, TRANSITION_Z * FOUR_OVER_PI / cosb])
+    } else {
+      // Polar caps, in units of pi/4: lon = offset + (x - offset) / sigma,
+      // with sigma = 2 - |y| and |lat| = 2 acos(sigma / sqrt(6)) - pi/2
+      let sigma = 2.0 - y.abs();
+      if sigma == 0.0 {
+        None
+      } else {
+        let x = abs_sign_decompose(pos.x * FOUR_OVER_PI);
+        let pm1 = pm1_offset_decompose(x.abs).pm1;
+        let sign_x = if x.sign == 0 { 1.0 } else { -1.0 };
+        let dlon = [1.0 / sigma, sign_x * y.signum() * pm1 / sigma.pow2()];
+        let dlat = [0.0, FOUR_OVER_PI.twice() / (6.0 - sigma.pow2()).sqrt()];
+        lonlat_unproj_jacobian(&xyz, dlon, dlat)
+      }
+    }
+  }
 }
 
 fn xpm1_and_offset(x: f64, y: f64) -> (f64, i8) {
@@ -215,11 +254,7 @@ fn is_not_near_from_pole(sqrt_of_three_time_one_minus_sin_of: f64) -> bool {
 }
 
 fn deal_with_numerical_approx_in_edges(lon: &mut f64) {
-  if *lon > 1.0 {
-    *lon = 1.0;
-  } else if *lon < -1.0 {
-    *lon = -1.0;
-  }
+  *lon = lon.clamp(-1.0, 1.0);
 }
 
 // Shift x by the given offset and apply lon sign to x
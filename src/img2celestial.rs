@@ -2,7 +2,58 @@
 //! from Image coordinates to Celestial coordinates.
 
 use crate::img2proj::{ImgXY2ProjXY, ProjXY2ImgXY};
-use crate::{CanonicalProjection, CenteredProjection, ImgXY, LonLat, Projection, XYZ};
+use crate::{CanonicalProjection, CenteredProjection, ImgXY, LonLat, Projection, XYZ, xyz2lonlat_jacobian};
+use crate::sphere::polygon_area;
+
+/// Transform the pixel coordinates in an image to celestial coordinates, and back.
+pub trait ImgXY2Celestial {
+
+  /// Transforms pixel coordinates into a position on the unit sphere.
+  fn img2xyz(&self, img_pos: &ImgXY) -> Option<XYZ>;
+
+  /// Transforms a position on the unit sphere into pixel coordinates.
+  fn xyz2img(&self, xyz: &XYZ) -> Option<ImgXY>;
+
+  /// Transforms pixel coordinates into equatorial coordinates.
+  fn img2lonlat(&self, img_pos: &ImgXY) -> Option<LonLat> {
+    self.img2xyz(img_pos).map(|xyz| xyz.to_lonlat())
+  }
+
+  /// Transforms equatorial coordinates into pixel coordinates.
+  fn lonlat2img(&self, lonlat: &LonLat) -> Option<ImgXY> {
+    self.xyz2img(&lonlat.to_xyz())
+  }
+}
+
+impl<T: ImgXY2Celestial + ?Sized> ImgXY2Celestial for &T {
+  fn img2xyz(&self, img_pos: &ImgXY) -> Option<XYZ> {
+    (**self).img2xyz(img_pos)
+  }
+  fn xyz2img(&self, xyz: &XYZ) -> Option<ImgXY> {
+    (**self).xyz2img(xyz)
+  }
+  fn img2lonlat(&self, img_pos: &ImgXY) -> Option<LonLat> {
+    (**self).img2lonlat(img_pos)
+  }
+  fn lonlat2img(&self, lonlat: &LonLat) -> Option<ImgXY> {
+    (**self).lonlat2img(lonlat)
+  }
+}
+
+impl<T: ImgXY2Celestial + ?Sized> ImgXY2Celestial for Box<T> {
+  fn img2xyz(&self, img_pos: &ImgXY) -> Option<XYZ> {
+    (**self).img2xyz(img_pos)
+  }
+  fn xyz2img(&self, xyz: &XYZ) -> Option<ImgXY> {
+    (**self).xyz2img(xyz)
+  }
+  fn img2lonlat(&self, img_pos: &ImgXY) -> Option<LonLat> {
+    (**self).img2lonlat(img_pos)
+  }
+  fn lonlat2img(&self, lonlat: &LonLat) -> Option<ImgXY> {
+    (**self).lonlat2img(lonlat)
+  }
+}
 
 /// Structure to convert back on forth from Image coordinates to Celestial coordinates.
 pub struct Img2Celestial<P: CanonicalProjection, W: ImgXY2ProjXY> {
@@ -47,5 +98,88 @@ impl<P: CanonicalProjection, W: ImgXY2ProjXY> Img2Celestial<P, W> {
   pub fn img2xyz(&self, img_pos: &ImgXY) -> Option<XYZ> {
     self.proj.unproj_xyz(&self.img2proj.img2proj(img_pos))
   }
+
+  /// Returns the Jacobian `d(x, y, z)/d(img_x, img_y)` of `img2xyz` at the given pixel coordinates
+  /// (`None` if the pixel is out of the projection domain).
+  pub fn img2xyz_jacobian(&self, img_pos: &ImgXY) -> Option<[[f64; 2]; 3]> {
+    let ji = self.img2proj.img2proj_jacobian(img_pos);
+    let jp = self.proj.unproj_jacobian(&self.img2proj.img2proj(img_pos))?;
+    Some(jp.map(|row| [row[0] * ji[0][0] + row[1] * ji[1][0], row[0] * ji[0][1] + row[1] * ji[1][1]]))
+  }
+
+  /// Returns the Jacobian `[[dlon/dimg_x, dlon/dimg_y], [dlat/dimg_x, dlat/dimg_y]]` of
+  /// `img2lonlat` at the given pixel coordinates (`None` if the pixel is out of the projection
+  /// domain or at a pole).
+  pub fn img2lonlat_jacobian(&self, img_pos: &ImgXY) -> Option<[[f64; 2]; 2]> {
+    let xyz = self.img2xyz(img_pos)?;
+    xyz2lonlat_jacobian(&xyz, &self.img2xyz_jacobian(img_pos)?)
+  }
+
+  /// Returns the local pixel scale at the given pixel coordinates, computed from the Jacobian of
+  /// `img2xyz`.
+  /// # Return
+  /// * `(scale_x, scale_y, skew)`: the angular size, in radians, of a pixel step along the image
+  ///   x-axis and along the image y-axis, and the skew angle, in radians, i.e. `pi/2` minus the
+  ///   angle between the images of the two axes on the sky (`0` for orthogonal axes)
+  /// * `None` if the pixel is out of the projection domain
+  pub fn pixel_scale(&self, img_pos: &ImgXY) -> Option<(f64, f64, f64)> {
+    let j = self.img2xyz_jacobian(img_pos)?;
+    let (cx, cy) = (j.map(|row| row[0]), j.map(|row| row[1]));
+    let norm = |v: &[f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    let (scale_x, scale_y) = (norm(&cx), norm(&cy));
+    let cross = [cx[1] * cy[2] - cx[2] * cy[1], cx[2] * cy[0] - cx[0] * cy[2], cx[0] * cy[1] - cx[1] * cy[0]];
+    let dot = cx[0] * cy[0] + cx[1] * cy[1] + cx[2] * cy[2];
+    Some((scale_x, scale_y, dot.atan2(norm(&cross))))
+  }
+
+  /// Returns the exact solid angle, in steradians, of the pixel centered on the given pixel
+  /// coordinates, i.e. the area of the spherical polygon made of the great-circle arcs
+  /// connecting the pixel corners.
+  /// # Return
+  /// * `None` if a corner of the pixel is out of the projection domain
+  pub fn pixel_solid_angle(&self, img_pos: &ImgXY) -> Option<f64> {
+    let (x, y) = (img_pos.x(), img_pos.y());
+    let corners = [
+      self.img2xyz(&ImgXY::new(x - 0.5, y - 0.5))?,
+      self.img2xyz(&ImgXY::new(x + 0.5, y - 0.5))?,
+      self.img2xyz(&ImgXY::new(x + 0.5, y + 0.5))?,
+      self.img2xyz(&ImgXY::new(x - 0.5, y + 0.5))?,
+    ];
+    Some(polygon_area(&corners))
+  }
+
+  /// Returns the approximated solid angle, in steradians, of the pixel centered on the given pixel
+  /// coordinates, i.e. the absolute value of the determinant of the Jacobian of `img2xyz` (in a
+  /// local orthonormal frame) at the pixel center.
+  /// # Return
+  /// * `None` if the pixel center is out of the projection domain
+  /// # Remark
+  /// * faster than `pixel_solid_angle`, the relative error being of the order of the square of
+  ///   the relative variation of the pixel scale over a pixel.
+  pub fn pixel_solid_angle_approx(&self, img_pos: &ImgXY) -> Option<f64> {
+    let j = self.img2xyz_jacobian(img_pos)?;
+    let (cx, cy) = (j.map(|row| row[0]), j.map(|row| row[1]));
+    let cross = [cx[1] * cy[2] - cx[2] * cy[1], cx[2] * cy[0] - cx[0] * cy[2], cx[0] * cy[1] - cx[1] * cy[0]];
+    Some((cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt())
+  }
   
 }
+
+impl<P: CanonicalProjection, W: ImgXY2ProjXY> ImgXY2Celestial for Img2Celestial<P, W> {
+
+  fn img2xyz(&self, img_pos: &ImgXY) -> Option<XYZ> {
+    Img2Celestial::img2xyz(self, img_pos)
+  }
+
+  fn xyz2img(&self, xyz: &XYZ) -> Option<ImgXY> {
+    Img2Celestial::xyz2img(self, xyz)
+  }
+
+  fn img2lonlat(&self, img_pos: &ImgXY) -> Option<LonLat> {
+    Img2Celestial::img2lonlat(self, img_pos)
+  }
+
+  fn lonlat2img(&self, lonlat: &LonLat) -> Option<ImgXY> {
+    Img2Celestial::lonlat2img(self, lonlat)
+  }
+}
@@ -28,6 +28,22 @@ pub trait ImgXY2ProjXY {
   /// # Params
   /// * `xy`: pixel coordinates (no units)
   fn img2proj(&self, xy: &ImgXY) -> ProjXY;
+
+  /// Returns the Jacobian `[[dX/dx, dX/dy], [dY/dx, dY/dy]]` of `img2proj` at the given pixel
+  /// coordinates, with `(X, Y)` the coordinates in the projection plane.
+  /// Computed by central finite differences by default.
+  /// # Params
+  /// * `xy`: pixel coordinates (no units)
+  fn img2proj_jacobian(&self, xy: &ImgXY) -> [[f64; 2]; 2] {
+    const H: f64 = 1e-3;
+    let d = |dx: f64, dy: f64| {
+      let plus = self.img2proj(&ImgXY::new(xy.x + dx, xy.y + dy));
+      let minus = self.img2proj(&ImgXY::new(xy.x - dx, xy.y - dy));
+      [(plus.x - minus.x) / (2.0 * H), (plus.y - minus.y) / (2.0 * H)]
+    };
+    let (dx, dy) = (d(H, 0.0), d(0.0, H));
+    [[dx[0], dy[0]], [dx[1], dy[1]]]
+  }
   
   /// Provide the inverse transformation.
   fn inverse(&self) -> Self::T;
@@ -68,14 +84,14 @@ impl BasicImgXY2ProjXY {
     let center_x = 0.5 * (img_size_x - 1.0);
     let xp_min = proj_bounds.0.start();
     let xp_max = proj_bounds.0.end();
-    let scale_x = (xp_max - xp_min) / img_size_x as f64;
+    let scale_x = (xp_max - xp_min) / img_size_x;
     let center_px = 0.5 * (xp_max + xp_min);
     // y-axis
     let img_size_y = img_size.1 as f64;
     let center_y = 0.5 * (img_size_y - 1.0);
     let yp_min = proj_bounds.1.start();
     let yp_max = proj_bounds.1.end();
-    let scale_y = (yp_max - yp_min) / img_size_y as f64;
+    let scale_y = (yp_max - yp_min) / img_size_y;
     let center_py = 0.5 * (yp_max + yp_min);
     // Create
     Self {
@@ -93,11 +109,15 @@ impl ImgXY2ProjXY for BasicImgXY2ProjXY {
   type T = Self;
 
   fn img2proj(&self, xy: &ImgXY) -> ProjXY {
-    let proj_x = self.center_px + (xy.x as f64 - self.center_x) * self.scale_x;
-    let proj_y = self.center_py + (xy.y as f64 - self.center_y) * self.scale_y;
+    let proj_x = self.center_px + (xy.x - self.center_x) * self.scale_x;
+    let proj_y = self.center_py + (xy.y - self.center_y) * self.scale_y;
     ProjXY::new(proj_x, proj_y)
   }
 
+  fn img2proj_jacobian(&self, _xy: &ImgXY) -> [[f64; 2]; 2] {
+    [[self.scale_x, 0.0], [0.0, self.scale_y]]
+  }
+
   fn inverse(&self) ->  Self::T {
     self.clone()
   }
@@ -142,6 +162,10 @@ impl ImgXY2ProjXY for ReversedEastPngImgXY2ProjXY {
     ProjXY::new(-proj_x, proj_y)
   }
 
+  fn img2proj_jacobian(&self, _xy: &ImgXY) -> [[f64; 2]; 2] {
+    [[-self.b.scale_x, 0.0], [0.0, -self.b.scale_y]]
+  }
+
   fn inverse(&self) ->  Self::T {
     self.clone()
   }
@@ -204,6 +228,7 @@ impl WcsImgXY2ProjXY {
   /// * `pc22`: value of the `PC22` keyword (element of a rotation matrix, no units), use 1 as default value
   /// * `cdelt1`: value of the `CDELT1` keyword, in degrees
   /// * `cdelt2`: value of the `CDELT2` keyword, in degrees
+  #[allow(clippy::too_many_arguments)]
   pub fn from_pc(
     crpix1: f64, crpix2: f64,
     pc11: f64,  pc12: f64,
@@ -258,6 +283,10 @@ impl ImgXY2ProjXY for WcsImgXY2ProjXY {
       self.cd21 * x + self.cd22 * y
     )
   }
+
+  fn img2proj_jacobian(&self, _xy: &ImgXY) -> [[f64; 2]; 2] {
+    [[self.cd11, self.cd12], [self.cd21, self.cd22]]
+  }
   
   
   fn inverse(&self) -> Self::T {
@@ -318,6 +347,18 @@ impl ImgXY2ProjXY for WcsWithSipImgXY2ProjXY {
       self.wcs.cd21 * x + self.wcs.cd22 * y
     )
   }
+
+  fn img2proj_jacobian(&self, xy: &ImgXY) -> [[f64; 2]; 2] {
+    let u = xy.x - self.wcs.crpix1;
+    let v = xy.y - self.wcs.crpix2;
+    // CD . (I + d(f, g)/d(u, v))
+    let (m11, m12) = (1.0 + self.sip.dfdu(u, v), self.sip.dfdv(u, v));
+    let (m21, m22) = (self.sip.dgdu(u, v), 1.0 + self.sip.dgdv(u, v));
+    [
+      [self.wcs.cd11 * m11 + self.wcs.cd12 * m21, self.wcs.cd11 * m12 + self.wcs.cd12 * m22],
+      [self.wcs.cd21 * m11 + self.wcs.cd22 * m21, self.wcs.cd21 * m12 + self.wcs.cd22 * m22],
+    ]
+  }
   
   fn inverse(&self) -> Self::T {
     WcsWithSipProjXY2ImgXY {
@@ -8,6 +8,28 @@ pub mod math;
 pub mod sip;
 pub mod img2proj;
 pub mod img2celestial;
+pub mod bounded;
+pub mod distortion;
+pub mod ds9;
+pub mod framing;
+pub mod graticule;
+pub mod gridmap;
+pub mod healpix;
+pub mod hips;
+pub mod moc;
+pub mod mosaic;
+pub mod ndim;
+pub mod region;
+pub mod reproj;
+pub mod skymap;
+pub mod sphere;
+pub mod stcs;
+pub mod tab;
+pub mod ticks;
+pub mod time;
+pub mod tissot;
+
+mod curve;
 
 pub mod zenithal;
 pub mod cylindrical;
@@ -54,7 +76,19 @@ impl LonLat {
     XYZ::new(cosl * cosb, sinl * cosb, sinb)
   }
 
+  /// Compute the angular distance using the Vincenty formula (`atan2` based), numerically stable
+  /// for all distances in `[0, pi]`.
+  pub fn ang_dist(&self, rhs: &LonLat) -> f64 {
+    let (sin_dlon, cos_dlon) = (rhs.lon - self.lon).sin_cos();
+    let (sinb1, cosb1) = self.lat.sin_cos();
+    let (sinb2, cosb2) = rhs.lat.sin_cos();
+    let y = (cosb2 * sin_dlon).hypot(cosb1 * sinb2 - sinb1 * cosb2 * cos_dlon);
+    let x = sinb1 * sinb2 + cosb1 * cosb2 * cos_dlon;
+    y.atan2(x)
+  }
+
   /// Compute the `Haversine` angular distance (adapterd for small distances but not for very large distance).
+  /// See `ang_dist` for large distances.
   pub fn haversine_dist(&self, rhs: &LonLat) -> f64 {
     let shs = squared_half_segment(
       rhs.lon - self.lon, rhs.lat - self.lat,
@@ -143,8 +177,48 @@ impl XYZ {
   }
 }
 
+/// Cone (i.e. spherical cap) defined by its center and its radius.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cone {
+  center: LonLat,
+  radius: f64,
+}
+impl Cone {
+
+  /// # Params
+  /// * `center`: center of the cone
+  /// * `radius`: radius of the cone, in radians
+  /// # Panics
+  /// * if `radius` is not in `[0, pi]`
+  pub fn new(center: LonLat, radius: f64) -> Self {
+    assert!((0.0..=PI).contains(&radius));
+    Self { center, radius }
+  }
+
+  /// Get the center of the cone
+  pub fn center(&self) -> &LonLat {
+    &self.center
+  }
+
+  /// Get the radius of the cone, in radians
+  pub fn radius(&self) -> f64 {
+    self.radius
+  }
+
+  /// Returns `true` if the given position is inside the cone (or on its border).
+  pub fn contains(&self, lonlat: &LonLat) -> bool {
+    self.contains_xyz(&lonlat.to_xyz())
+  }
+
+  /// Returns `true` if the given position is inside the cone (or on its border).
+  pub fn contains_xyz(&self, xyz: &XYZ) -> bool {
+    sphere::ang_dist(&self.center.to_xyz(), xyz) <= self.radius
+  }
+}
+
 
 /// X, Y coordinates in an image
+#[derive(Debug, Clone, PartialEq)]
 pub struct ImgXY {
   x: f64,
   y: f64,  
@@ -209,6 +283,17 @@ pub trait Projection {
 
   /// Returns `true` if the given point is in the valid projection area.
   fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool;
+
+  /// Returns the boundary of the valid projection area, as a closed polyline (the first point
+  /// being repeated at the end) going counter-clockwise in the projection plane.
+  /// # Params
+  /// * `n`: number of segments approximating each curved part of the boundary
+  /// # Return
+  /// * `None` if the valid projection area is not bounded (e.g. `TAN`, `STG` or `MER`), or if the
+  ///   projection does not provide its boundary (default implementation)
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    None
+  }
   
   /// Project (if possible) from the unit sphere to a projection 2D plane.
   fn proj_xyz(&self, xyz: &XYZ) -> Option<ProjXY>;
@@ -225,6 +310,93 @@ pub trait Projection {
   fn unproj_lonlat(&self, pos: &ProjXY) -> Option<LonLat> {
     self.unproj_xyz(pos).map(|xyz| xyz.to_lonlat())
   }
+
+  /// Jacobian of `proj_xyz` at the given position on the unit sphere:
+  /// `[[dX/dx, dX/dy, dX/dz], [dY/dx, dY/dy, dY/dz]]`.
+  /// # Remark
+  /// * only the derivatives along the sphere are meaningful: the Jacobian is null along the
+  ///   normal to the sphere (i.e. along `xyz`).
+  /// * the default implementation computes the pseudo-inverse of `unproj_jacobian` at
+  ///   `proj_xyz(xyz)`.
+  fn proj_jacobian(&self, xyz: &XYZ) -> Option<[[f64; 3]; 2]> {
+    self.proj_xyz(xyz)
+      .and_then(|pos| self.unproj_jacobian(&pos))
+      .and_then(|j| pseudo_inverse(&j))
+  }
+
+  /// Jacobian of `unproj_xyz` at the given position in the projection plane:
+  /// `[[dx/dX, dx/dY], [dy/dX, dy/dY], [dz/dX, dz/dY]]`.
+  /// # Remark
+  /// * the default implementation uses finite differences.
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    fd_unproj_jacobian(|pos| self.unproj_xyz(pos), pos)
+  }
+
+  /// Jacobian of `proj_lonlat` at the given position: `[[dX/dlon, dX/dlat], [dY/dlon, dY/dlat]]`.
+  fn proj_lonlat_jacobian(&self, lonlat: &LonLat) -> Option<[[f64; 2]; 2]> {
+    let j = self.proj_jacobian(&lonlat.to_xyz())?;
+    let (sinl, cosl) = lonlat.lon.sin_cos();
+    let (sinb, cosb) = lonlat.lat.sin_cos();
+    // Derivatives of (x, y, z) with respect to lon and lat
+    let dlon = [-cosb * sinl, cosb * cosl, 0.0];
+    let dlat = [-sinb * cosl, -sinb * sinl, cosb];
+    let row = |r: &[f64; 3]| [
+      r[0] * dlon[0] + r[1] * dlon[1] + r[2] * dlon[2],
+      r[0] * dlat[0] + r[1] * dlat[1] + r[2] * dlat[2],
+    ];
+    Some([row(&j[0]), row(&j[1])])
+  }
+
+  /// Jacobian of `unproj_lonlat` at the given position: `[[dlon/dX, dlon/dY], [dlat/dX, dlat/dY]]`.
+  /// # Return
+  /// * `None` if the position can't be deprojected or is deprojected on a pole (where the
+  ///   longitude is not differentiable)
+  fn unproj_lonlat_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 2]> {
+    let xyz = self.unproj_xyz(pos)?;
+    xyz2lonlat_jacobian(&xyz, &self.unproj_jacobian(pos)?)
+  }
+
+  /// Project the polyline made of the given vertices connected by great-circle arcs.
+  /// The polyline is split at the projection discontinuities (e.g. the `lon = 180 deg` line in
+  /// `AIT`, `MOL` or `CAR`) and at the borders of the valid projection area, and densified so that
+  /// the projected arcs deviate from the returned segments by less than `tol`.
+  /// # Params
+  /// * `vertices`: the polyline vertices
+  /// * `tol`: the tolerance, in the unit of the projection plane
+  /// # Return
+  /// * the projected pieces of the polyline, each containing at least 2 points
+  fn proj_polyline(&self, vertices: &[XYZ], tol: f64) -> Vec<Vec<ProjXY>> {
+    curve::project_arcs(vertices, false, |xyz| self.proj_xyz(xyz).map(|p| (p.x, p.y)), tol)
+      .into_iter()
+      .map(|piece| piece.into_iter().map(|(x, y)| ProjXY::new(x, y)).collect())
+      .collect()
+  }
+
+  /// Project the polygon made of the given vertices connected by great-circle arcs, the polygon
+  /// being the part of the sphere, smaller than a hemisphere, delimited by the arcs.
+  /// The polygon is split like in `proj_polyline`, and the pieces are then closed along the
+  /// boundary of the valid projection area (see `boundary`) so that the result can be filled.
+  /// # Params
+  /// * `vertices`: the polygon vertices (the first one not being repeated at the end)
+  /// * `tol`: the tolerance, in the unit of the projection plane
+  /// # Return
+  /// * closed rings (the first point being repeated at the end)
+  /// # Remark
+  /// * if the projection is unbounded (see `boundary`), the pieces of a split polygon are
+  ///   returned unclosed
+  fn proj_polygon(&self, vertices: &[XYZ], tol: f64) -> Vec<Vec<ProjXY>> {
+    let boundary: Option<Vec<(f64, f64)>> = self.boundary(curve::BOUNDARY_N_SEGMENTS)
+      .map(|b| b.into_iter().map(|p| (p.x, p.y)).collect());
+    curve::project_polygon(
+      vertices,
+      |xyz| self.proj_xyz(xyz).map(|p| (p.x, p.y)),
+      |(x, y)| self.unproj_xyz(&ProjXY::new(*x, *y)),
+      boundary.as_deref(),
+      tol
+    ).into_iter()
+      .map(|piece| piece.into_iter().map(|(x, y)| ProjXY::new(x, y)).collect())
+      .collect()
+  }
 }
 
 /// The X and Y ranges bounds of a projection in the Euclidean projection plane.
@@ -253,6 +425,108 @@ impl ProjBounds {
 
 }
 
+/// Returns the given polygon as a closed polyline (the first vertex being repeated at the end),
+/// reversing the order of the vertices if necessary so that the polyline goes counter-clockwise.
+pub(crate) fn closed_ccw_polyline(mut vertices: Vec<ProjXY>) -> Vec<ProjXY> {
+  let n = vertices.len();
+  let twice_area: f64 = (0..n)
+    .map(|i| {
+      let (a, b) = (&vertices[i], &vertices[(i + 1) % n]);
+      a.x * b.y - b.x * a.y
+    })
+    .sum();
+  if twice_area < 0.0 {
+    vertices.reverse();
+  }
+  if let Some(first) = vertices.first().cloned() {
+    vertices.push(first);
+  }
+  vertices
+}
+
+/// Returns the closed counter-clockwise polyline made of `n` segments approximating the ellipse
+/// of center `(x0, y0)` and of semi-axes `a` (along the x-axis) and `b` (along the y-axis).
+pub(crate) fn ellipse_polyline(x0: f64, y0: f64, a: f64, b: f64, n: usize) -> Vec<ProjXY> {
+  let n = n.max(3);
+  closed_ccw_polyline(
+    (0..n)
+      .map(|i| {
+        let (s, c) = (2.0 * PI * i as f64 / n as f64).sin_cos();
+        ProjXY::new(x0 + a * c, y0 + b * s)
+      })
+      .collect()
+  )
+}
+
+/// Returns the Jacobian `d(lon, lat)/d(u, v)` from the Jacobian `d(x, y, z)/d(u, v)` at the given
+/// position (`None` at the poles).
+pub(crate) fn xyz2lonlat_jacobian(xyz: &XYZ, j: &[[f64; 2]; 3]) -> Option<[[f64; 2]; 2]> {
+  let r2 = xyz.x.pow2() + xyz.y.pow2();
+  if r2 == 0.0 {
+    None
+  } else {
+    // dlon = (x dy - y dx) / (x^2 + y^2) and dz = cos(lat) dlat
+    let cosb = r2.sqrt();
+    let dlon = |k: usize| (xyz.x * j[1][k] - xyz.y * j[0][k]) / r2;
+    Some([[dlon(0), dlon(1)], [j[2][0] / cosb, j[2][1] / cosb]])
+  }
+}
+
+/// Returns the Jacobian `d(x, y, z)/d(X, Y)` of a deprojection giving the position `xyz` from the
+/// derivatives `[dlon/dX, dlon/dY]` and `[dlat/dX, dlat/dY]` (`None` at the poles).
+pub(crate) fn lonlat_unproj_jacobian(xyz: &XYZ, dlon: [f64; 2], dlat: [f64; 2]) -> Option<[[f64; 2]; 3]> {
+  let cosb = (xyz.x.pow2() + xyz.y.pow2()).sqrt();
+  if cosb == 0.0 {
+    None
+  } else {
+    // d(x, y, z)/dlon = (-y, x, 0) and d(x, y, z)/dlat = (-z cos(lon), -z sin(lon), cos(lat))
+    let (cosl, sinl) = (xyz.x / cosb, xyz.y / cosb);
+    let d = |dxyz_dlon: f64, dxyz_dlat: f64| [
+      dxyz_dlon * dlon[0] + dxyz_dlat * dlat[0],
+      dxyz_dlon * dlon[1] + dxyz_dlat * dlat[1],
+    ];
+    Some([d(-xyz.y, -xyz.z * cosl), d(xyz.x, -xyz.z * sinl), d(0.0, cosb)])
+  }
+}
+
+/// Computes the Jacobian of the given deprojection function by (central, or one-sided near the
+/// boundary of the valid projection area) finite differences.
+pub(crate) fn fd_unproj_jacobian<F>(unproj: F, pos: &ProjXY) -> Option<[[f64; 2]; 3]>
+  where
+    F: Fn(&ProjXY) -> Option<XYZ>
+{
+  const H: f64 = 1e-6;
+  let center = unproj(pos)?;
+  let derivative = |dx: f64, dy: f64| -> Option<[f64; 3]> {
+    let diff = |a: &XYZ, b: &XYZ, h: f64| [(a.x - b.x) / h, (a.y - b.y) / h, (a.z - b.z) / h];
+    match (unproj(&ProjXY::new(pos.x + dx, pos.y + dy)), unproj(&ProjXY::new(pos.x - dx, pos.y - dy))) {
+      (Some(plus), Some(minus)) => Some(diff(&plus, &minus, 2.0 * H)),
+      (Some(plus), None) => Some(diff(&plus, &center, H)),
+      (None, Some(minus)) => Some(diff(&center, &minus, H)),
+      (None, None) => None,
+    }
+  };
+  let (dx, dy) = (derivative(H, 0.0)?, derivative(0.0, H)?);
+  Some([[dx[0], dy[0]], [dx[1], dy[1]], [dx[2], dy[2]]])
+}
+
+/// Returns the pseudo-inverse `(J^t J)^-1 J^t` of the given 3x2 matrix, i.e. the Jacobian of a
+/// projection from the Jacobian `J` of its deprojection (`None` if `J` is singular).
+pub(crate) fn pseudo_inverse(j: &[[f64; 2]; 3]) -> Option<[[f64; 3]; 2]> {
+  let g11 = j[0][0].pow2() + j[1][0].pow2() + j[2][0].pow2();
+  let g12 = j[0][0] * j[0][1] + j[1][0] * j[1][1] + j[2][0] * j[2][1];
+  let g22 = j[0][1].pow2() + j[1][1].pow2() + j[2][1].pow2();
+  let det = g11 * g22 - g12 * g12;
+  if det <= 1e-24 * (g11 * g22) || !det.is_finite() {
+    None
+  } else {
+    let (i11, i12, i22) = (g22 / det, -g12 / det, g11 / det);
+    Some([
+      [i11 * j[0][0] + i12 * j[0][1], i11 * j[1][0] + i12 * j[1][1], i11 * j[2][0] + i12 * j[2][1]],
+      [i12 * j[0][0] + i22 * j[0][1], i12 * j[1][0] + i22 * j[1][1], i12 * j[2][0] + i22 * j[2][1]],
+    ])
+  }
+}
 
 // https://www.aanda.org/articles/aa/full/2002/45/aah3860/aah3860.html
 /// Projection centered around the vernal point.
@@ -270,15 +544,46 @@ pub trait CanonicalProjection {
 
   /// Returns `true` if the given point is in the valid projection area.
   fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
-    // TODO: add the method in each proj to useless computations when the point is valid
+    // Implementations should override this with a cheaper (analytic) test
     self.unproj(pos).is_some()
   }
+
+  /// Returns the boundary of the valid projection area, as a closed polyline (the first point
+  /// being repeated at the end) going counter-clockwise in the projection plane.
+  /// # Params
+  /// * `n`: number of segments approximating each curved part of the boundary
+  /// # Return
+  /// * `None` if the valid projection area is not bounded (e.g. `TAN`, `STG` or `MER`), or if the
+  ///   projection does not provide its boundary (default implementation)
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    None
+  }
   
   /// Project (if possible) from the unit sphere to the canonical projection 2D plane.
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY>;
 
   /// Deproject (if possible) from the canonical projection 2D plane to the unit sphere.
   fn unproj(&self, pos: &ProjXY) -> Option<XYZ>;
+
+  /// Jacobian of `proj`, see `Projection::proj_jacobian`.
+  /// # Remark
+  /// * the default implementation computes the pseudo-inverse of `unproj_jacobian` at `proj(xyz)`.
+  fn proj_jacobian(&self, xyz: &XYZ) -> Option<[[f64; 3]; 2]> {
+    self.proj(xyz)
+      .and_then(|pos| self.unproj_jacobian(&pos))
+      .and_then(|j| pseudo_inverse(&j))
+  }
+
+  /// Jacobian of `unproj`, see `Projection::unproj_jacobian`.
+  /// # Return
+  /// * `None` if the position is not in the valid projection area or if the deprojection is not
+  ///   differentiable at the given position
+  /// # Remark
+  /// * the default implementation uses finite differences; implementations should override it
+  ///   with an analytic computation.
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    fd_unproj_jacobian(|pos| self.unproj(pos), pos)
+  }
   
 }
 
@@ -296,12 +601,24 @@ impl<T: CanonicalProjection> Projection for T {
     CanonicalProjection::is_in_valid_proj_area(self, pos)
   }
 
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    CanonicalProjection::boundary(self, n)
+  }
+
   fn proj_xyz(&self, xyz: &XYZ) -> Option<ProjXY> {
     self.proj(xyz)
   }
   fn unproj_xyz(&self, pos: &ProjXY) -> Option<XYZ> {
     self.unproj(pos)
   }
+
+  fn proj_jacobian(&self, xyz: &XYZ) -> Option<[[f64; 3]; 2]> {
+    CanonicalProjection::proj_jacobian(self, xyz)
+  }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    CanonicalProjection::unproj_jacobian(self, pos)
+  }
 }
 
 /// Structure performing a rotation (due to non-vernal projection origin)
@@ -390,9 +707,13 @@ impl<T: CanonicalProjection> Projection for CenteredProjection<T> {
   fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
     self.inner_proj().is_in_valid_proj_area(pos)
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    self.inner_proj().boundary(n)
+  }
   
   fn proj_xyz(&self, xyz: &XYZ) -> Option<ProjXY> {
-    let rotated_xyz = XYZ::new(
+    let rotated_xyz = XYZ::new_renorming_if_necessary(
       self.r11 * xyz.x + self.r12 * xyz.y + self.r13 * xyz.z,
       self.r21 * xyz.x + self.r22 * xyz.y + self.r23 * xyz.z,
       self.r31 * xyz.x + self.r32 * xyz.y + self.r33 * xyz.z,
@@ -403,11 +724,40 @@ impl<T: CanonicalProjection> Projection for CenteredProjection<T> {
   fn unproj_xyz(&self, pos: &ProjXY) -> Option<XYZ> {
     self.proj.unproj(pos)
       .map(|XYZ{x, y, z}|
-        XYZ::new(
+        XYZ::new_renorming_if_necessary(
            self.r11 * x + self.r21 * y + self.r31 * z,
            self.r12 * x + self.r22 * y + self.r32 * z,
            self.r13 * x + self.r23 * y + self.r33 * z
         )
       )
   }
+
+  fn proj_jacobian(&self, xyz: &XYZ) -> Option<[[f64; 3]; 2]> {
+    let rotated_xyz = XYZ::new_renorming_if_necessary(
+      self.r11 * xyz.x + self.r12 * xyz.y + self.r13 * xyz.z,
+      self.r21 * xyz.x + self.r22 * xyz.y + self.r23 * xyz.z,
+      self.r31 * xyz.x + self.r32 * xyz.y + self.r33 * xyz.z,
+    );
+    // J R
+    self.proj.proj_jacobian(&rotated_xyz)
+      .map(|j| j.map(|r| [
+        r[0] * self.r11 + r[1] * self.r21 + r[2] * self.r31,
+        r[0] * self.r12 + r[1] * self.r22 + r[2] * self.r32,
+        r[0] * self.r13 + r[1] * self.r23 + r[2] * self.r33,
+      ]))
+  }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // R^t J
+    self.proj.unproj_jacobian(pos)
+      .map(|j| {
+        let col = |k: usize| [
+          self.r11 * j[0][k] + self.r21 * j[1][k] + self.r31 * j[2][k],
+          self.r12 * j[0][k] + self.r22 * j[1][k] + self.r32 * j[2][k],
+          self.r13 * j[0][k] + self.r23 * j[1][k] + self.r33 * j[2][k],
+        ];
+        let (c0, c1) = (col(0), col(1));
+        [[c0[0], c1[0]], [c0[1], c1[1]], [c0[2], c1[2]]]
+      })
+  }
 }
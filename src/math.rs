@@ -63,3 +63,65 @@ impl CustomFloat for f64 {
   }
   
 }
+
+/// Returns the bounds `(umin, umax, vmin, vmax)` of the given planar points in the frame rotated
+/// by the given angle, i.e. of `(u, v) = (cos(a) x + sin(a) y, -sin(a) x + cos(a) y)`.
+pub(crate) fn rotated_bounds(points: &[(f64, f64)], angle: f64) -> (f64, f64, f64, f64) {
+  let (sina, cosa) = angle.sin_cos();
+  points.iter()
+    .map(|(x, y)| (cosa * x + sina * y, -sina * x + cosa * y))
+    .fold(
+      (f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY),
+      |(u0, u1, v0, v1), (u, v)| (u0.min(u), u1.max(u), v0.min(v), v1.max(v))
+    )
+}
+
+/// Returns the rotation angle, in `[0, pi/2[`, minimizing the area of the bounding box of the
+/// given planar points (see `rotated_bounds`). The optimal bounding box having a side collinear
+/// with an edge of the convex hull of the points, only the angles of the convex hull edges are
+/// tested.
+pub(crate) fn min_area_angle(points: &[(f64, f64)]) -> f64 {
+  let hull = convex_hull(points);
+  let area = |angle: f64| {
+    let (umin, umax, vmin, vmax) = rotated_bounds(&hull, angle);
+    (umax - umin) * (vmax - vmin)
+  };
+  hull.iter()
+    .zip(hull.iter().cycle().skip(1))
+    .map(|((x1, y1), (x2, y2))| (y2 - y1).atan2(x2 - x1).rem_euclid(HALF_PI))
+    .fold((0.0, area(0.0)), |(best, best_area), angle| {
+      let a = area(angle);
+      // Favor the non-rotated orientation in case of (numerical) equality
+      if a < best_area * (1.0 - 1.0e-12) { (angle, a) } else { (best, best_area) }
+    })
+    .0
+}
+
+/// Returns the convex hull of the given planar points, counter-clockwise (monotone chain algorithm).
+pub(crate) fn convex_hull(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+  let mut sorted = points.to_vec();
+  sorted.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+  sorted.dedup();
+  if sorted.len() < 3 {
+    return sorted;
+  }
+  let cross = |o: &(f64, f64), a: &(f64, f64), b: &(f64, f64)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+  let mut hull: Vec<(f64, f64)> = Vec::with_capacity(2 * sorted.len());
+  for pass in 0..2 {
+    let start = hull.len();
+    let iter: Box<dyn Iterator<Item = &(f64, f64)>> = if pass == 0 {
+      Box::new(sorted.iter())
+    } else {
+      Box::new(sorted.iter().rev())
+    };
+    for p in iter {
+      while hull.len() >= start + 2 && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], p) <= 0.0 {
+        hull.pop();
+      }
+      hull.push(*p);
+    }
+    // The last point of each chain is the first point of the other chain
+    hull.pop();
+  }
+  hull
+}
@@ -0,0 +1,458 @@
+//! Module containing the computation of the outlines of sets of HEALPix cells (e.g. of MOCs,
+//! see the [IVOA MOC standard](https://www.ivoa.net/documents/MOC/)).
+//!
+//! The cells are subdivided down to the largest cell depth, and their edges are cancelled on the
+//! integer lattice of the cell vertices in the `HPX` plane, so that only the outer (and inner, for
+//! holes) boundaries remain.
+//! The HEALPix cell edges being straight lines in the `HPX` plane, the boundaries are sampled
+//! in this plane before being deprojected.
+//! The outline rings are oriented like the footprint polygons, i.e. counter-clockwise as seen from
+//! inside the sphere (North up, East on the left).
+//!
+//! The module also computes the HEALPix coverage of images.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::ops::Range;
+
+use crate::{ImgXY, ProjXY, Projection, XYZ};
+use crate::curve::project_curve;
+use crate::bounded::{BoundedImg2Celestial, DEFAULT_FOOTPRINT_TOL};
+use crate::healpix::{self, base_cell_center, local_to_plane, unproj_plane};
+use crate::img2celestial::ImgXY2Celestial;
+use crate::sphere::{ang_dist, arc_intersection};
+
+/// Number of great-circle arcs approximating each cell edge when computing an image coverage.
+const N_EDGE_ARCS: u32 = 4;
+
+/// A unit edge of the lattice of the cell vertices at the deepest depth.
+struct Edge {
+  base: u8,
+  /// Coordinates, in the base cell, of the starting vertex
+  from: (i64, i64),
+  /// Coordinates, in the base cell, of the ending vertex
+  to: (i64, i64),
+}
+
+/// Decomposes ranges of NESTED indices at the given depth into cells (sorted, of the smallest depth possible).
+/// # Params
+/// * `depth`: the depth of the range bounds (e.g. 29 for the ranges of a MOC serialized in FITS)
+/// * `ranges`: the ranges of cell indices (the upper bound being excluded)
+/// # Return
+/// * `(depth, ipix)` cells
+pub fn cells_from_ranges(depth: u8, ranges: &[Range<u64>]) -> Vec<(u8, u64)> {
+  let mut cells = Vec::new();
+  for range in ranges {
+    let mut start = range.start;
+    while start < range.end {
+      // Largest aligned block starting at `start` and included in the range
+      let mut dd = (start.trailing_zeros() / 2).min(depth as u32) as u8;
+      while start + (1_u64 << (2 * dd)) > range.end {
+        dd -= 1;
+      }
+      cells.push((depth - dd, start >> (2 * dd)));
+      start += 1_u64 << (2 * dd);
+    }
+  }
+  cells.sort_unstable_by_key(|(d, h)| (*h << (2 * (depth - d)), *d));
+  cells
+}
+
+/// Computes the outline rings (on the sphere) of the given set of cells.
+/// # Params
+/// * `cells`: `(depth, ipix)` cells, supposedly not overlapping
+/// * `step`: maximum angular distance, in radians, between two consecutive points
+/// # Return
+/// * the (unclosed) rings, oriented counter-clockwise as seen from inside the sphere
+/// # Remark
+/// * the number of edges is proportional to `2^(max_depth - depth)` for each cell: a set mixing
+///   very large and very small cells may be costly.
+pub fn outline_rings(cells: &[(u8, u64)], step: f64) -> Vec<Vec<XYZ>> {
+  assert!(step > 0.0);
+  let Some(depth) = cells.iter().map(|(d, _)| *d).max() else {
+    return vec![];
+  };
+  let nside = healpix::nside(depth) as f64;
+  outline_segments(cells).iter()
+    .map(|ring| ring.iter()
+      .flat_map(|(base, (fi, fj), (ti, tj))| {
+        let a = unproj_plane_local(*base, *fi as f64 / nside, *fj as f64 / nside);
+        let b = unproj_plane_local(*base, *ti as f64 / nside, *tj as f64 / nside);
+        let n = (ang_dist(&a, &b) / step).ceil().max(1.0) as i64;
+        (0..n).map(move |k| {
+          let t = k as f64 / n as f64;
+          let u = (*fi as f64 + t * (ti - fi) as f64) / nside;
+          let v = (*fj as f64 + t * (tj - fj) as f64) / nside;
+          unproj_plane_local(*base, u, v)
+        })
+      })
+      .collect()
+    )
+    .collect()
+}
+
+/// Computes the outlines of the given set of cells in the projection plane.
+/// The outline rings are split at the projection discontinuities and at the borders of the
+/// projection valid area; unsplit rings repeat their first point at the end.
+/// See `outline_rings` for the parameters.
+pub fn proj_outlines<P: Projection + ?Sized>(cells: &[(u8, u64)], proj: &P, step: f64) -> Vec<Vec<ProjXY>> {
+  outline_rings(cells, step).iter()
+    .flat_map(|ring| project_curve(ring, true, |xyz| proj.proj_xyz(xyz).map(|p| (p.x(), p.y()))))
+    .map(|piece| piece.into_iter().map(|(x, y)| ProjXY::new(x, y)).collect())
+    .collect()
+}
+
+/// Same as `proj_outlines`, but in image coordinates.
+pub fn img_outlines<C: ImgXY2Celestial + ?Sized>(cells: &[(u8, u64)], img2celestial: &C, step: f64) -> Vec<Vec<ImgXY>> {
+  outline_rings(cells, step).iter()
+    .flat_map(|ring| project_curve(ring, true, |xyz| img2celestial.xyz2img(xyz).map(|p| (p.x(), p.y()))))
+    .map(|piece| piece.into_iter().map(|(x, y)| ImgXY::new(x, y)).collect())
+    .collect()
+}
+
+/// Fast path computing the outlines of the given set of cells directly in the `HPX` projection
+/// plane, in which the HEALPix cell edges are straight lines (no sampling is needed).
+/// The rings are split at the `HPX` polar cuts and at `lon = 180`; unsplit rings repeat their
+/// first point at the end.
+pub fn hpx_outlines(cells: &[(u8, u64)]) -> Vec<Vec<ProjXY>> {
+  let Some(depth) = cells.iter().map(|(d, _)| *d).max() else {
+    return vec![];
+  };
+  let nside = healpix::nside(depth) as f64;
+  let connected = |a: &ProjXY, b: &ProjXY| (a.x() - b.x()).abs() < 1e-12 && (a.y() - b.y()).abs() < 1e-12;
+  let mut polylines = Vec::new();
+  for ring in outline_segments(cells) {
+    let segments: Vec<(ProjXY, ProjXY)> = ring.iter()
+      .flat_map(|(base, (fi, fj), (ti, tj))| to_proj_segments(
+        local_to_plane(*base, *fi as f64 / nside, *fj as f64 / nside),
+        local_to_plane(*base, *ti as f64 / nside, *tj as f64 / nside),
+      ))
+      .collect();
+    // Start after a split (if any) so that no polyline wraps around the ring end
+    let n = segments.len();
+    let start = (0..n)
+      .find(|k| !connected(&segments[(k + n - 1) % n].1, &segments[*k].0))
+      .unwrap_or(0);
+    let mut current: Vec<ProjXY> = Vec::new();
+    for k in 0..n {
+      let (a, b) = &segments[(start + k) % n];
+      if current.last().map(|last| !connected(last, a)).unwrap_or(false) {
+        polylines.push(std::mem::take(&mut current));
+      }
+      if current.is_empty() {
+        current.push(a.clone());
+      }
+      current.push(b.clone());
+    }
+    polylines.push(current);
+  }
+  polylines
+}
+
+/// Converts a segment of the scaled `HPX` plane, the `x` coordinates being in the range of its
+/// base cell (i.e. in `[-1, 9]`), into one or two segments of the `HPX` projection plane, splitting
+/// it at `x = 4` (i.e. `lon = 180`).
+fn to_proj_segments((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> Vec<(ProjXY, ProjXY)> {
+  // `x` in `[-1, 4]` is mapped to `X` in `[-pi/4, pi]`, `x` in `[4, 9]` to `X` in `[-pi, pi/4]`
+  let to_proj = |x: f64, y: f64, low: bool| {
+    ProjXY::new(if low { x } else { x - 8.0 } * PI / 4.0, y * PI / 4.0)
+  };
+  if x1.max(x2) <= 4.0 {
+    vec![(to_proj(x1, y1, true), to_proj(x2, y2, true))]
+  } else if x1.min(x2) >= 4.0 {
+    vec![(to_proj(x1, y1, false), to_proj(x2, y2, false))]
+  } else {
+    let y = y1 + (4.0 - x1) * (y2 - y1) / (x2 - x1);
+    let low = x1 < 4.0;
+    vec![
+      (to_proj(x1, y1, low), to_proj(4.0, y, low)),
+      (to_proj(4.0, y, !low), to_proj(x2, y2, !low)),
+    ]
+  }
+}
+
+/// Computes the HEALPix coverage of an image, i.e. the list of cells of given depth overlapping
+/// the image footprint.
+/// The overlap is computed from the exact footprint (see `BoundedImg2Celestial::footprint`) and
+/// not from a bounding cone, so that thin or rotated images are not over-covered.
+/// # Params
+/// * `img`: the image
+/// * `depth`: the depth of the cells
+/// # Return
+/// * `None` if the image footprint can't be computed
+/// * else, the NESTED indices (sorted) of the cells overlapping the image, with a flag telling if the
+///   cell is fully inside the image
+/// # Remark
+/// * the cell edges are approximated by `N_EDGE_ARCS` great-circle arcs
+pub fn image_coverage<T: ImgXY2Celestial>(img: &BoundedImg2Celestial<T>, depth: u8) -> Option<Vec<(u64, bool)>> {
+  assert!(depth <= healpix::MAX_DEPTH);
+  let footprint = img.footprint_xyz(DEFAULT_FOOTPRINT_TOL)?;
+  let cone = img.bounding_cone()?;
+  let cone_center = cone.center().to_xyz();
+  let mut coverage = Vec::new();
+  let mut stack: Vec<(u8, u64)> = (0..12).rev().map(|h| (0, h)).collect();
+  while let Some((d, h)) = stack.pop() {
+    let cell_cone = healpix::bounding_cone(d, h);
+    if ang_dist(&cone_center, &cell_cone.center().to_xyz()) > cone.radius() + cell_cone.radius() {
+      continue;
+    }
+    match cell_overlap(img, &footprint, d, h) {
+      Overlap::None => {},
+      Overlap::Full => {
+        let dd = 2 * (depth - d) as u32;
+        coverage.extend(((h << dd)..((h + 1) << dd)).map(|h| (h, true)));
+      },
+      Overlap::Partial if d < depth => stack.extend((0..4).rev().map(|k| (d + 1, (h << 2) | k))),
+      Overlap::Partial => coverage.push((h, false)),
+    }
+  }
+  Some(coverage)
+}
+
+/// Overlap between a cell and an image.
+enum Overlap {
+  None,
+  Partial,
+  Full,
+}
+
+/// Computes the overlap between the given cell and the image of given footprint.
+fn cell_overlap<T: ImgXY2Celestial>(img: &BoundedImg2Celestial<T>, footprint: &[XYZ], depth: u8, hash: u64) -> Overlap {
+  let step = 1.0 / N_EDGE_ARCS as f64;
+  // Cell boundary, South -> East -> North -> West
+  let boundary: Vec<XYZ> = [(0.0, 0.0, 1.0, 0.0), (1.0, 0.0, 0.0, 1.0), (1.0, 1.0, -1.0, 0.0), (0.0, 1.0, 0.0, -1.0)]
+    .iter()
+    .flat_map(|(u0, v0, du, dv)| (0..N_EDGE_ARCS)
+      .map(move |k| (u0 + k as f64 * step * du, v0 + k as f64 * step * dv)))
+    .map(|(u, v)| healpix::sph_coo(depth, hash, u, v))
+    .collect();
+  let n_inside = boundary.iter().filter(|p| img.contains_xyz(p)).count();
+  let crossing = boundary.iter()
+    .zip(boundary.iter().cycle().skip(1))
+    .any(|(a, b)| footprint.iter()
+      .zip(footprint.iter().cycle().skip(1))
+      .any(|(c, d)| arc_intersection(a, b, c, d).is_some())
+    );
+  let vertex_inside = footprint.iter().any(|v| healpix::hash_xyz(depth, v) == hash);
+  if n_inside == boundary.len() && !crossing && !vertex_inside {
+    Overlap::Full
+  } else if n_inside > 0 || crossing || vertex_inside {
+    Overlap::Partial
+  } else {
+    Overlap::None
+  }
+}
+
+/// Deprojects the point of local coordinates `(u, v)` in the given base cell.
+fn unproj_plane_local(base: u8, u: f64, v: f64) -> XYZ {
+  let (x, y) = local_to_plane(base, u, v);
+  unproj_plane(x, y)
+}
+
+/// Returns the outline rings as lists of segments `(base, from, to)`, the coordinates being the
+/// coordinates of the vertices in their base cell at the largest depth of the cells, consecutive
+/// unit edges of same direction in a same base cell being merged.
+#[allow(clippy::type_complexity)]
+fn outline_segments(cells: &[(u8, u64)]) -> Vec<Vec<(u8, (i64, i64), (i64, i64))>> {
+  let Some(depth) = cells.iter().map(|(d, _)| *d).max() else {
+    return vec![];
+  };
+  let n = healpix::nside(depth) as i64;
+  let mut cells = cells.to_vec();
+  cells.sort_unstable();
+  cells.dedup();
+  // Unit edges, cancelled when they appear in both directions
+  let mut edges: HashMap<((i64, i64), (i64, i64)), Edge> = HashMap::new();
+  for (d, h) in cells {
+    let (base, i, j) = healpix::decompose(d, h);
+    let s = 1_i64 << (depth - d);
+    let (i0, j0) = (i as i64 * s, j as i64 * s);
+    // South -> West -> North -> East
+    let walk = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+    let mut from = (i0, j0);
+    for (di, dj) in walk {
+      for _ in 0..s {
+        let to = (from.0 + di, from.1 + dj);
+        let key = (vertex_key(base, from, n), vertex_key(base, to, n));
+        if edges.remove(&(key.1, key.0)).is_none() {
+          edges.insert(key, Edge { base, from, to });
+        }
+        from = to;
+      }
+    }
+  }
+  // Chain the remaining edges
+  let mut by_start: HashMap<(i64, i64), Vec<(i64, i64)>> = HashMap::new();
+  let mut keys: Vec<((i64, i64), (i64, i64))> = edges.keys().cloned().collect();
+  keys.sort_unstable();
+  for (a, b) in keys.iter() {
+    by_start.entry(*a).or_default().push(*b);
+  }
+  let mut rings = Vec::new();
+  for key in keys {
+    if !edges.contains_key(&key) {
+      continue;
+    }
+    let first = key.0;
+    let mut current = key;
+    let mut ring: Vec<(u8, (i64, i64), (i64, i64))> = Vec::new();
+    loop {
+      let edge = edges.remove(&current).unwrap();
+      let dir = (edge.to.0 - edge.from.0, edge.to.1 - edge.from.1);
+      match ring.last_mut() {
+        Some((base, from, to)) if *base == edge.base && *to == edge.from
+          && (to.0 - from.0).signum() == dir.0 && (to.1 - from.1).signum() == dir.1 => *to = edge.to,
+        _ => ring.push((edge.base, edge.from, edge.to)),
+      }
+      if current.1 == first {
+        break;
+      }
+      let next = by_start.get(&current.1)
+        .and_then(|ends| ends.iter().find(|end| edges.contains_key(&(current.1, **end))).cloned());
+      match next {
+        Some(end) => current = (current.1, end),
+        None => break,
+      }
+    }
+    rings.push(ring);
+  }
+  rings
+}
+
+/// Returns the key of the given vertex (coordinates in its base cell at the depth of `nside`),
+/// i.e. its global coordinates on the lattice of the scaled `HPX` plane, the vertices on the
+/// polar cuts being moved on the edge of the neighbouring base cell so that a same vertex has a
+/// unique key.
+fn vertex_key(base: u8, (i, j): (i64, i64), n: i64) -> (i64, i64) {
+  let (cx, cy) = base_cell_center(base);
+  let x = cx as i64 * n + i - j;
+  let y = cy as i64 * n + i + j - n;
+  let x = if base < 4 {
+    if i == n && j == n {
+      // North pole
+      return (0, 2 * n);
+    } else if i == n {
+      // North-East edge of the base cell, moved on the North-West edge of the next base cell
+      x + 2 * y - 2 * n
+    } else {
+      x
+    }
+  } else if base >= 8 {
+    if i == 0 && j == 0 {
+      // South pole
+      return (0, -2 * n);
+    } else if j == 0 {
+      // South-East edge of the base cell, moved on the South-West edge of the next base cell
+      x - 2 * y - 2 * n
+    } else {
+      x
+    }
+  } else {
+    x
+  };
+  (x.rem_euclid(8 * n), y)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::LonLat;
+  use crate::bounded::tan_image;
+  use crate::pseudocyl::mol::Mol;
+  use crate::sphere::orientation;
+
+  #[test]
+  fn test_outlines() {
+    // Ranges decomposition
+    let cells = cells_from_ranges(2, &[0..16, 17..20]);
+    assert_eq!(cells, vec![(0, 0), (2, 17), (2, 18), (2, 19)]);
+
+    // A single cell
+    let rings = outline_rings(&[(3, 100)], 0.01);
+    assert_eq!(rings.len(), 1);
+    let c = healpix::center(3, 100).to_xyz();
+    assert!(orientation(&rings[0], &c) < 0.0);
+
+    // The 4 children of a cell have the same outline as the cell
+    let rings = outline_rings(&[(4, 400), (4, 401), (4, 402), (4, 403)], 1.0);
+    assert_eq!(rings.len(), 1);
+    // (the collinear unit edges being merged, and the sampling step being large)
+    assert_eq!(rings[0].len(), 4);
+
+    // The North polar cap (4 base cells) has a single outline (the vertices on the polar cuts match)
+    let rings = outline_rings(&[(0, 0), (0, 1), (0, 2), (0, 3)], 0.1);
+    assert_eq!(rings.len(), 1);
+    assert!(rings[0].iter().all(|p| p.z() <= 2.0 / 3.0 + 1e-12));
+
+    // A hole: the 8 neighbours of cell 5 of base cell 4 at depth 2 ((i, j) = (3, 0))
+    // => use the 9 cells (i, j) in [1, 3]^2 minus the center (2, 2)
+    let cells: Vec<(u8, u64)> = (1..4).flat_map(|i| (1..4).map(move |j| (i, j)))
+      .filter(|(i, j)| (*i, *j) != (2, 2))
+      .map(|(i, j)| (2, (4 << 4) | healpix::interleave(i, j)))
+      .collect();
+    assert_eq!(outline_rings(&cells, 1.0).len(), 2);
+
+    // Base cell 6 (centered on lon = 180) is split by the Mollweide discontinuity
+    let pieces = proj_outlines(&[(0, 6)], &Mol::new(), 0.01);
+    assert_eq!(pieces.len(), 2);
+    assert!(pieces.iter().all(|piece| piece.len() > 10));
+
+    // Hpx fast path: base cell 6 is split at lon = 180
+    let polylines = hpx_outlines(&[(0, 6)]);
+    assert_eq!(polylines.len(), 2);
+    // Base cell 0 is a closed diamond
+    let polylines = hpx_outlines(&[(0, 0)]);
+    assert_eq!(polylines.len(), 1);
+    assert_eq!(polylines[0].len(), 5);
+  }
+
+  #[test]
+  fn test_image_coverage() {
+    // Thin (200 x 10 arcmin) image rotated by 45 deg
+    let center = LonLat::new(20_f64.to_radians(), 10_f64.to_radians());
+    let img = tan_image(&center, (100.5, 5.5), 45.0, 1.0 / 60.0, (200, 10));
+    let depth = 8;
+    let coverage = image_coverage(&img, depth).unwrap();
+    // Cells of ~13.7 arcmin: much less than the ~ 15 x 15 cells of the bounding cone
+    assert!(coverage.len() > 15 && coverage.len() < 60, "{}", coverage.len());
+    assert!(coverage.windows(2).all(|w| w[0].0 < w[1].0));
+    // All pixel centers are covered
+    for j in 0..10 {
+      for i in 0..200 {
+        let h = healpix::hash_xyz(depth, &img.img2xyz(&img.pixel_center(i, j)).unwrap());
+        assert!(coverage.iter().any(|(c, _)| *c == h));
+      }
+    }
+    // Cells fully inside a large image
+    let img = tan_image(&center, (100.5, 100.5), 0.0, 1.0 / 60.0, (200, 200));
+    let coverage = image_coverage(&img, depth).unwrap();
+    let n_full = coverage.iter().filter(|(_, full)| *full).count();
+    assert!(n_full > 100 && n_full < coverage.len());
+    for (h, full) in coverage {
+      let c = healpix::center(depth, h).to_xyz();
+      assert!(!full || img.contains_xyz(&c));
+    }
+  }
+
+  #[test]
+  fn test_image_coverage_pole() {
+    // 60 x 60 pixels of 1 arcmin, the North pole being the bottom left corner of the image
+    let pole = LonLat::new(0.0, 0.5 * std::f64::consts::PI);
+    let img = tan_image(&pole, (0.5, 0.5), 0.0, 1.0 / 60.0, (60, 60));
+    let depth = 6;
+    let coverage = image_coverage(&img, depth).unwrap();
+    assert!(coverage.iter().any(|(h, _)| *h == healpix::hash(depth, &pole)));
+    // Image centered on the North pole: the 4 polar base cells are covered
+    let img = tan_image(&pole, (30.5, 30.5), 0.0, 1.0 / 60.0, (60, 60));
+    let coverage = image_coverage(&img, depth).unwrap();
+    for base in 0..4_u64 {
+      assert!(coverage.iter().any(|(h, _)| h >> (2 * depth) == base));
+    }
+    for j in 0..60 {
+      for i in 0..60 {
+        let h = healpix::hash_xyz(depth, &img.img2xyz(&img.pixel_center(i, j)).unwrap());
+        assert!(coverage.iter().any(|(c, _)| *c == h));
+      }
+    }
+  }
+}
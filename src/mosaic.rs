@@ -0,0 +1,184 @@
+//! Module containing the computation of the output frame (WCS + image size) of a mosaic,
+//! i.e. of an image covering a set of input images.
+//!
+//! The projection center is the center of the smallest cone enclosing the union of the
+//! input image footprints, and the image is either North-up or rotated so that its area is minimal.
+
+use crate::{CanonicalProjection, CenteredProjection, LonLat, Projection, XYZ};
+use crate::bounded::{BoundedImg2Celestial, PixelConvention, DEFAULT_FOOTPRINT_TOL};
+use crate::img2celestial::{Img2Celestial, ImgXY2Celestial};
+use crate::img2proj::WcsImgXY2ProjXY;
+use crate::math::{min_area_angle, rotated_bounds};
+use crate::sphere::smallest_enclosing_cone;
+use crate::zenithal::tan::Tan;
+
+/// Orientation of the mosaic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MosaicRotation {
+  /// The North is up (and the East on the left).
+  NorthUp,
+  /// The image is rotated so that its area (number of pixels) is minimal.
+  MinArea,
+}
+
+/// Output frame of a mosaic, using the FITS pixel convention.
+pub struct MosaicFrame<P: CanonicalProjection> {
+  center: LonLat,
+  crpix1: f64,
+  crpix2: f64,
+  cd: [[f64; 2]; 2],
+  angle: f64,
+  naxis1: u32,
+  naxis2: u32,
+  proj: CenteredProjection<P>,
+}
+
+impl<P: CanonicalProjection> MosaicFrame<P> {
+
+  /// Get the projection center, i.e. the values of the `CRVAL1` and `CRVAL2` keywords (in radians)
+  pub fn center(&self) -> &LonLat {
+    &self.center
+  }
+
+  /// Get the values of the `CRPIX1` and `CRPIX2` keywords
+  pub fn crpix(&self) -> (f64, f64) {
+    (self.crpix1, self.crpix2)
+  }
+
+  /// Get the values of the `CDi_j` keywords, in degrees (`[[CD1_1, CD1_2], [CD2_1, CD2_2]]`)
+  pub fn cd(&self) -> [[f64; 2]; 2] {
+    self.cd
+  }
+
+  /// Get the rotation angle of the image, in radians (`0` for a North-up image, positive from
+  /// the North toward the East like the `CROTA2` keyword)
+  pub fn angle(&self) -> f64 {
+    self.angle
+  }
+
+  /// Get the number of pixels along the x-axis (value of the `NAXIS1` keyword)
+  pub fn naxis1(&self) -> u32 {
+    self.naxis1
+  }
+
+  /// Get the number of pixels along the y-axis (value of the `NAXIS2` keyword)
+  pub fn naxis2(&self) -> u32 {
+    self.naxis2
+  }
+
+  /// Get the (centered) projection
+  pub fn proj(&self) -> &CenteredProjection<P> {
+    &self.proj
+  }
+
+  /// Returns the transformation from the image to the projection plane.
+  pub fn img2proj(&self) -> WcsImgXY2ProjXY {
+    WcsImgXY2ProjXY::from_cd(
+      self.crpix1, self.crpix2,
+      self.cd[0][0], self.cd[0][1],
+      self.cd[1][0], self.cd[1][1],
+    )
+  }
+
+  /// Returns the image-bounded transformation from the mosaic image to celestial coordinates.
+  pub fn into_bounded(self) -> BoundedImg2Celestial<Img2Celestial<P, WcsImgXY2ProjXY>> {
+    let img2proj = self.img2proj();
+    BoundedImg2Celestial::new(
+      Img2Celestial::new(img2proj, self.proj),
+      self.naxis1, self.naxis2, PixelConvention::Fits
+    )
+  }
+}
+
+/// Computes the `TAN` frame of a mosaic covering all given images.
+/// See `mosaic_frame_with_proj`.
+pub fn mosaic_frame<T: ImgXY2Celestial>(
+  images: &[BoundedImg2Celestial<T>],
+  cdelt: f64,
+  rotation: MosaicRotation
+) -> Option<MosaicFrame<Tan>> {
+  mosaic_frame_with_proj(images, Tan::new(), cdelt, rotation)
+}
+
+/// Computes the frame of a mosaic covering all given images.
+/// # Params
+/// * `images`: the images to be covered by the mosaic
+/// * `proj`: the projection of the mosaic
+/// * `cdelt`: the size of the mosaic pixels, in degrees
+/// * `rotation`: the mosaic orientation
+/// # Return
+/// * `None` if `images` is empty, if the footprint of an image can't be computed or if a footprint
+///   vertex can't be projected (e.g. `TAN` for a set of images larger than a hemisphere)
+/// # Panics
+/// * if `cdelt` is not strictly positive
+pub fn mosaic_frame_with_proj<P: CanonicalProjection, T: ImgXY2Celestial>(
+  images: &[BoundedImg2Celestial<T>],
+  proj: P,
+  cdelt: f64,
+  rotation: MosaicRotation
+) -> Option<MosaicFrame<P>> {
+  assert!(cdelt > 0.0);
+  let vertices = images.iter()
+    .map(|img| img.footprint_xyz(DEFAULT_FOOTPRINT_TOL))
+    .collect::<Option<Vec<Vec<XYZ>>>>()?
+    .concat();
+  let (center, _) = smallest_enclosing_cone(&vertices)?;
+  let center = center.to_lonlat();
+  let mut proj = CenteredProjection::new(proj);
+  proj.set_proj_center_from_lonlat(&center);
+  let points = vertices.iter()
+    .map(|v| proj.proj_xyz(v).map(|xy| (xy.x(), xy.y())))
+    .collect::<Option<Vec<(f64, f64)>>>()?;
+  let angle = match rotation {
+    MosaicRotation::NorthUp => 0.0,
+    MosaicRotation::MinArea => min_area_angle(&points),
+  };
+  // Coordinates in the rotated frame: (x, y) = R(angle) . (u, v)
+  let (umin, umax, vmin, vmax) = rotated_bounds(&points, angle);
+  let s = cdelt.to_radians();
+  let naxis1 = ((umax - umin) / s).ceil().max(1.0) as u32;
+  let naxis2 = ((vmax - vmin) / s).ceil().max(1.0) as u32;
+  // East on the left: CDELT1 = -cdelt, CDELT2 = cdelt
+  let crpix1 = 0.5 * (naxis1 as f64 + 1.0) + 0.5 * (umin + umax) / s;
+  let crpix2 = 0.5 * (naxis2 as f64 + 1.0) - 0.5 * (vmin + vmax) / s;
+  let (sina, cosa) = angle.sin_cos();
+  let cd = [
+    [-cosa * cdelt, -sina * cdelt],
+    [-sina * cdelt, cosa * cdelt],
+  ];
+  Some(MosaicFrame { center, crpix1, crpix2, cd, angle, naxis1, naxis2, proj })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bounded::tan_image;
+
+  #[test]
+  fn test_mosaic_frame() {
+    // 100 x 50 pixels of 1 arcmin
+    let lonlat = |lon: f64, lat: f64| LonLat::new(lon.to_radians(), lat.to_radians());
+    let images = [
+      tan_image(&lonlat(359.5, 10.0), (50.5, 25.5), 0.0, 1.0 / 60.0, (100, 50)),
+      tan_image(&lonlat(1.0, 10.5), (50.5, 25.5), 0.0, 1.0 / 60.0, (100, 50)),
+    ];
+    let frame = mosaic_frame(&images, 1.0 / 60.0, MosaicRotation::NorthUp).unwrap();
+    assert_eq!(frame.angle(), 0.0);
+    let mosaic = frame.into_bounded();
+    for img in images.iter() {
+      for v in img.footprint(DEFAULT_FOOTPRINT_TOL).unwrap() {
+        assert!(mosaic.contains_lonlat(&v));
+      }
+    }
+    // ~3.15 deg x 1.33 deg
+    assert!((186..=192).contains(&mosaic.naxis1()), "{}", mosaic.naxis1());
+    assert!((80..=84).contains(&mosaic.naxis2()), "{}", mosaic.naxis2());
+
+    // A single rotated image: the minimal area frame has the same size
+    let images = [tan_image(&lonlat(120.0, -30.0), (50.5, 25.5), 30.0, 1.0 / 60.0, (100, 50))];
+    let frame = mosaic_frame(&images, 1.0 / 60.0, MosaicRotation::MinArea).unwrap();
+    assert!((frame.angle().to_degrees() - 30.0).abs() < 1e-6, "{}", frame.angle().to_degrees());
+    assert!(frame.naxis1() == 100 || frame.naxis1() == 101);
+    assert!(frame.naxis2() == 50 || frame.naxis2() == 51);
+  }
+}
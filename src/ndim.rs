@@ -0,0 +1,185 @@
+//! Module containing the structure to convert back on forth from the pixel coordinates
+//! of an N-dimensional image (or cube) to world coordinates.
+//!
+//! The celestial pair of axes is handled by an `ImgXY2Celestial` transform while
+//! each of the other axes (time, spectral, ...) is handled by its own `WcsAxis`.
+//! Like in most FITS files, axes are assumed to be separable, i.e. the `PCi_j` (or `CDi_j`)
+//! matrix does not mix the celestial pair with the other axes.
+
+use crate::{ImgXY, LonLat};
+use crate::img2celestial::ImgXY2Celestial;
+
+/// A (non-celestial) WCS axis, transforming a pixel coordinate into a world coordinate
+/// and back.
+pub trait WcsAxis {
+
+  /// Transforms a pixel coordinate into a world coordinate (in the axis `CUNITi`).
+  fn pix2world(&self, pix: f64) -> Option<f64>;
+
+  /// Transforms a world coordinate (in the axis `CUNITi`) into a pixel coordinate.
+  fn world2pix(&self, world: f64) -> Option<f64>;
+}
+
+/// Simple linear axis: `world = CRVAL + CDELT * (pix - CRPIX)`.
+#[derive(Debug, Clone)]
+pub struct LinearAxis {
+  /// Value of the `CRPIXi` keyword.
+  crpix: f64,
+  /// Value of the `CDELTi` keyword.
+  cdelt: f64,
+  /// Value of the `CRVALi` keyword.
+  crval: f64,
+}
+
+impl LinearAxis {
+
+  /// # Params
+  /// * `crpix`: value of the `CRPIXi` keyword (pixel units, so no units)
+  /// * `cdelt`: value of the `CDELTi` keyword (in the axis `CUNITi`)
+  /// * `crval`: value of the `CRVALi` keyword (in the axis `CUNITi`)
+  /// # Panics
+  /// * if `cdelt` equals 0 or is not finite
+  pub fn new(crpix: f64, cdelt: f64, crval: f64) -> Self {
+    assert!(cdelt != 0.0 && cdelt.is_finite());
+    Self { crpix, cdelt, crval }
+  }
+}
+
+impl WcsAxis for LinearAxis {
+
+  fn pix2world(&self, pix: f64) -> Option<f64> {
+    Some(self.crval + self.cdelt * (pix - self.crpix))
+  }
+
+  fn world2pix(&self, world: f64) -> Option<f64> {
+    Some(self.crpix + (world - self.crval) / self.cdelt)
+  }
+}
+
+/// Transforms the pixel coordinates of an N-dimensional image into world coordinates.
+/// The world coordinates of the celestial pair are in radians (like everywhere in this library),
+/// the world coordinates of the other axes are in their respective units.
+pub struct ImgNd2World<C: ImgXY2Celestial> {
+  /// Number of axes (`NAXIS`).
+  n_axes: usize,
+  /// Index of the longitude axis (starting at 0).
+  lon_axis: usize,
+  /// Index of the latitude axis (starting at 0).
+  lat_axis: usize,
+  /// Transformation of the celestial pair.
+  celestial: C,
+  /// Others axes, together with their index (starting at 0).
+  axes: Vec<(usize, Box<dyn WcsAxis>)>,
+}
+
+impl<C: ImgXY2Celestial> ImgNd2World<C> {
+
+  /// # Params
+  /// * `n_axes`: number of axes (value of the `NAXIS` keyword)
+  /// * `lon_axis`: index, starting at 0, of the longitude axis (e.g. `0` for `CTYPE1 = RA---TAN`)
+  /// * `lat_axis`: index, starting at 0, of the latitude axis (e.g. `1` for `CTYPE2 = DEC--TAN`)
+  /// * `celestial`: transformation of the celestial pair (e.g. an `Img2Celestial`)
+  /// # Panics
+  /// * if `lon_axis` or `lat_axis` is not in `[0, n_axes[` or if both are equals
+  pub fn new(n_axes: usize, lon_axis: usize, lat_axis: usize, celestial: C) -> Self {
+    assert!(lon_axis < n_axes && lat_axis < n_axes && lon_axis != lat_axis);
+    Self { n_axes, lon_axis, lat_axis, celestial, axes: Default::default() }
+  }
+
+  /// Set the transformation of the (non-celestial) axis of given index.
+  /// Axes without transformation are returned untouched, i.e. their world coordinate
+  /// equals their pixel coordinate.
+  /// # Params
+  /// * `i`: index, starting at 0, of the axis
+  /// * `axis`: the axis transformation
+  /// # Panics
+  /// * if `i` is not in `[0, n_axes[` or if it is the index of one of the celestial axes
+  pub fn set_axis(&mut self, i: usize, axis: Box<dyn WcsAxis>) {
+    assert!(i < self.n_axes && i != self.lon_axis && i != self.lat_axis);
+    self.axes.retain(|(j, _)| *j != i);
+    self.axes.push((i, axis));
+  }
+
+  /// Returns the number of axes.
+  pub fn n_axes(&self) -> usize {
+    self.n_axes
+  }
+
+  /// Returns the transformation of the celestial pair.
+  pub fn celestial(&self) -> &C {
+    &self.celestial
+  }
+
+  /// Returns the transformation of the (non-celestial) axis of given index, if any.
+  pub fn axis(&self, i: usize) -> Option<&dyn WcsAxis> {
+    self.axes.iter().find(|(j, _)| *j == i).map(|(_, axis)| axis.as_ref())
+  }
+
+  /// Transforms pixel coordinates into world coordinates.
+  /// # Params
+  /// * `pix`: pixel coordinates, one per axis
+  /// # Panics
+  /// * if `pix` does not contains exactly `n_axes` values
+  pub fn pix2world(&self, pix: &[f64]) -> Option<Vec<f64>> {
+    assert_eq!(pix.len(), self.n_axes);
+    let mut world = pix.to_vec();
+    let lonlat = self.celestial.img2lonlat(&ImgXY::new(pix[self.lon_axis], pix[self.lat_axis]))?;
+    world[self.lon_axis] = lonlat.lon();
+    world[self.lat_axis] = lonlat.lat();
+    for (i, axis) in self.axes.iter() {
+      world[*i] = axis.pix2world(pix[*i])?;
+    }
+    Some(world)
+  }
+
+  /// Transforms world coordinates into pixel coordinates.
+  /// # Params
+  /// * `world`: world coordinates, one per axis (longitude and latitude in radians)
+  /// # Panics
+  /// * if `world` does not contains exactly `n_axes` values
+  pub fn world2pix(&self, world: &[f64]) -> Option<Vec<f64>> {
+    assert_eq!(world.len(), self.n_axes);
+    let mut pix = world.to_vec();
+    let img_pos = self.celestial.lonlat2img(&LonLat::new(world[self.lon_axis], world[self.lat_axis]))?;
+    pix[self.lon_axis] = img_pos.x();
+    pix[self.lat_axis] = img_pos.y();
+    for (i, axis) in self.axes.iter() {
+      pix[*i] = axis.world2pix(world[*i])?;
+    }
+    Some(pix)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::CenteredProjection;
+  use crate::img2celestial::Img2Celestial;
+  use crate::img2proj::WcsImgXY2ProjXY;
+  use crate::time::{TimeAxis, TimeScale, TimeUnit};
+  use crate::zenithal::sin::Sin;
+
+  #[test]
+  fn test_nd_round_trip() {
+    // NAXIS = 4: (FREQ, DEC--SIN, TIME, RA---SIN)
+    let mut proj = CenteredProjection::new(Sin::new());
+    proj.set_proj_center_from_lonlat(&LonLat::new(1.0, -0.5));
+    let celestial = Img2Celestial::new(WcsImgXY2ProjXY::from_cr(50.5, 40.5, 0.1, -0.01, 0.01), proj);
+    let mut img2world = ImgNd2World::new(4, 3, 1, celestial);
+    img2world.set_axis(0, Box::new(LinearAxis::new(1.0, 1.0e6, 1.4e9)));
+    img2world.set_axis(2, Box::new(TimeAxis::new(1.0, 60.0, 0.0, 57753.0, 0.0, TimeScale::TT, TimeUnit::Second)));
+    assert_eq!(img2world.n_axes(), 4);
+    assert!(img2world.axis(1).is_none() && img2world.axis(2).is_some());
+    let pix = [11.0, 40.5, 3.0, 50.5];
+    let world = img2world.pix2world(&pix).unwrap();
+    assert!((world[0] - 1.41e9).abs() < 1e-3);
+    assert!((world[1] + 0.5).abs() < 1e-12 && (world[3] - 1.0).abs() < 1e-12);
+    assert!((world[2] - 120.0).abs() < 1e-12);
+    let pix2 = img2world.world2pix(&[1.45e9, -0.49, 600.0, 1.02]).unwrap();
+    let world2 = img2world.pix2world(&pix2).unwrap();
+    for (w, expected) in world2.iter().zip([1.45e9, -0.49, 600.0, 1.02]) {
+      assert!((w - expected).abs() < 1e-9 * expected.abs());
+    }
+    assert!((pix2[2] - 11.0).abs() < 1e-12);
+  }
+}
@@ -1,7 +1,7 @@
 //! Hammer-Aitoff (equal area) projection.
 
 use std::f64::consts::SQRT_2;
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{ellipse_polyline, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
 
 /// Hammer-Aitoff (equal area) projection.
 ///
@@ -65,6 +65,14 @@ impl CanonicalProjection for Ait {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    Some(ellipse_polyline(0.0, 0.0, 2.0 * SQRT_2, SQRT_2, n))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    0.125 * pos.x.pow2() + pos.y.pow2().half() <= 1.0
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     let r = (xyz.x.pow2() + xyz.y.pow2()).sqrt();
@@ -102,4 +110,35 @@ impl CanonicalProjection for Ait {
       // Some(XYZ::new(x, y, z))
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    let r = 0.125 * pos.x.pow2() + pos.y.pow2().half();
+    if r > 1.0  {
+      None
+    } else {
+      // Position (x1, y1, z) at the longitude lon/2, and its derivatives
+      let w = (1.0 - r.half()).sqrt();
+      let dr = [0.25 * pos.x, pos.y];
+      let dw = [-0.25 * dr[0] / w, -0.25 * dr[1] / w];
+      let (x1, y1) = (1.0 - r, pos.x.half() * w);
+      let dx1 = [-dr[0], -dr[1]];
+      let dy1 = [0.5 * w + pos.x.half() * dw[0], pos.x.half() * dw[1]];
+      let dz = [pos.y * dw[0], w + pos.y * dw[1]];
+      // Doubling the longitude: (x1, y1) = rho (cos a, sin a) => (x, y) = rho (cos 2a, sin 2a)
+      let rho2 = x1.pow2() + y1.pow2();
+      if rho2 == 0.0 {
+        None
+      } else {
+        let rho = rho2.sqrt();
+        let (x, y) = ((x1.pow2() - y1.pow2()) / rho, (x1 * y1 / rho).twice());
+        let d = |k: usize| {
+          let drho_o_rho = (x1 * dx1[k] + y1 * dy1[k]) / rho2;
+          let da = (x1 * dy1[k] - y1 * dx1[k]) / rho2;
+          (drho_o_rho * x - 2.0 * y * da, drho_o_rho * y + 2.0 * x * da)
+        };
+        let (d0, d1) = (d(0), d(1));
+        Some([[d0.0, d1.0], [d0.1, d1.1], dz])
+      }
+    }
+  }
 }
\ No newline at end of file
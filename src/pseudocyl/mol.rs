@@ -2,7 +2,7 @@
 
 use std::f64::consts::{PI, SQRT_2};
 
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{ellipse_polyline, lonlat_unproj_jacobian, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
 
 static HALF_PI: f64 = 0.5 * PI;
 
@@ -69,6 +69,14 @@ impl CanonicalProjection for Mol {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    Some(ellipse_polyline(0.0, 0.0, 2.0 * SQRT_2, SQRT_2, n))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    pos.x.pow2() / 8.0 + pos.y.pow2() / 2.0 <= 1.0
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     // find g iteratively using Newton-Raphson
@@ -91,27 +99,32 @@ impl CanonicalProjection for Mol {
   }
 
   fn unproj(&self, pos: &ProjXY) -> Option<XYZ> {
-    let x2 = pos.x.pow2();
-    let y2 = pos.y.pow2();
-    if x2 / 8.0 + y2 / 2.0 > 1.0 {
+    if !self.is_in_valid_proj_area(pos) {
       None
     } else {
-      let sqrt_2_m_y2 = 2.0 - y2;
+      let sqrt_2_m_y2 = 2.0 - pos.y.pow2();
       if sqrt_2_m_y2 <= 0.0 {
         let z = if pos.y > 0.0 { 1.0 } else { -1.0 };
         Some(XYZ::new(0.0, 0.0, z))
       } else {
         let sqrt_2_m_y2 = sqrt_2_m_y2.sqrt();
-        let z = ((pos.y / SQRT_2).asin().twice() + pos.y * sqrt_2_m_y2) / PI;
-        if (-1.0..=1.0).contains(&z) {
-          let (sinl, cosl) = ((pos.x * HALF_PI) / sqrt_2_m_y2).sin_cos();
-          let r = (1.0 - z.pow2()).sqrt();
-          Some(XYZ::new(r * cosl, r * sinl, z))
-        } else {
-          // Should not happen!
-          None
-        }
+        // Inside the ellipse, |z| > 1 may only be due to numerical approximations
+        let z = (((pos.y / SQRT_2).asin().twice() + pos.y * sqrt_2_m_y2) / PI).clamp(-1.0, 1.0);
+        let (sinl, cosl) = ((pos.x * HALF_PI) / sqrt_2_m_y2).sin_cos();
+        let r = (1.0 - z.pow2()).sqrt();
+        Some(XYZ::new(r * cosl, r * sinl, z))
       }
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // lon = pi X / (2 s), sin(lat) = (2 asin(Y / sqrt(2)) + Y s) / pi, with s = sqrt(2 - Y^2)
+    // => dsin(lat)/dY = 2 s / pi
+    self.unproj(pos).and_then(|xyz| {
+      let s = (2.0 - pos.y.pow2()).sqrt();
+      let lon = (pos.x * HALF_PI) / s;
+      let cosb = (xyz.x.pow2() + xyz.y.pow2()).sqrt();
+      lonlat_unproj_jacobian(&xyz, [HALF_PI / s, lon * pos.y / s.pow2()], [0.0, s.twice() / (PI * cosb)])
+    })
+  }
 }
@@ -1,7 +1,7 @@
 //! Parabolic projection.
 
 use std::f64::consts::PI;
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{closed_ccw_polyline, lonlat_unproj_jacobian, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
 
 /// Parabolic projection.
 pub struct Par;
@@ -31,6 +31,25 @@ impl CanonicalProjection for Par {
     &PROJ_BOUNDS
   }
 
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    // Meridians lon = +-PI: x = +-PI * (1 - 4y^2), y in [-1/2, 1/2]
+    let n = n.max(2);
+    let y = |i: usize| i as f64 / n as f64 - 0.5;
+    Some(closed_ccw_polyline(
+      (0..=n).map(|i| ProjXY::new(PI * (1.0 - 4.0 * y(i).pow2()), y(i)))
+        .chain((1..n).rev().map(|i| ProjXY::new(-PI * (1.0 - 4.0 * y(i).pow2()), y(i))))
+        .collect()
+    ))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    (-0.5_f64..=0.5_f64).contains(&pos.y) && {
+      let m = 1.0 - pos.y.twice().pow2();
+      let l = if m == 0.0 { 0.0 } else { pos.x / m };
+      (-PI..=PI).contains(&l)
+    }
+  }
+
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     // more computations, but more precise than lat = xyz.z.asin()
     let r2 = xyz.x.pow2() + xyz.y.pow2();
@@ -58,4 +77,13 @@ impl CanonicalProjection for Par {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // lat = 3 asin(Y), lon = X / (1 - 4 Y^2)
+    self.unproj(pos).and_then(|xyz| {
+      let m = 1.0 - pos.y.twice().pow2();
+      let lon = pos.x / m;
+      lonlat_unproj_jacobian(&xyz, [1.0 / m, lon * 8.0 * pos.y / m], [0.0, 3.0 / (1.0 - pos.y.pow2()).sqrt()])
+    })
+  }
 }
@@ -1,6 +1,6 @@
 //! Samson-Flamsteed projection.
 use std::f64::consts::PI;
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{closed_ccw_polyline, lonlat_unproj_jacobian, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
 use crate::math::HALF_PI;
 
 /// Samson-Flamsteed projection.
@@ -35,6 +35,25 @@ impl CanonicalProjection for Sfl {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    // Meridians lon = +-PI: x = +-PI * cos(y), y in [-PI/2, PI/2]
+    let n = n.max(2);
+    let y = |i: usize| PI * i as f64 / n as f64 - HALF_PI;
+    Some(closed_ccw_polyline(
+      (0..=n).map(|i| ProjXY::new(PI * y(i).cos(), y(i)))
+        .chain((1..n).rev().map(|i| ProjXY::new(-PI * y(i).cos(), y(i))))
+        .collect()
+    ))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    (-HALF_PI..=HALF_PI).contains(&pos.y) && {
+      let r = pos.y.cos();
+      let l = if r == 0.0 { 0.0 } else { pos.x / r };
+      (-PI..=PI).contains(&l)
+    }
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     // We use x^2 + y^2 instead of 1 - z^2 for numerical precision on small distances
@@ -62,4 +81,13 @@ impl CanonicalProjection for Sfl {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // lat = Y, lon = X / cos(Y)
+    self.unproj(pos).and_then(|xyz| {
+      let (siny, cosy) = pos.y.sin_cos();
+      let lon = pos.x / cosy;
+      lonlat_unproj_jacobian(&xyz, [1.0 / cosy, lon * siny / cosy], [0.0, 1.0])
+    })
+  }
 }
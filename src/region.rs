@@ -0,0 +1,446 @@
+//! Module containing simple spherical regions (cone, ellipse, polygon, box and union of regions)
+//! that can be tested for containment and projected into any projection plane or image.
+//!
+//! The region boundaries are sampled on the sphere, densified where the projection curves and then
+//! projected, the polylines being split where the projection fails and at the projection discontinuities.
+//! Image masks are computed by testing the position of each pixel center.
+
+use crate::{Cone, CustomFloat, ImgXY, LonLat, ProjXY, Projection, XYZ};
+use crate::bounded::BoundedImg2Celestial;
+use crate::curve::{densify, project_curve};
+use crate::img2celestial::ImgXY2Celestial;
+use crate::sphere::{
+  ang_dist, great_arc_points, is_in_polygon, normalize, outside_point, small_circle_points, tangent_frame
+};
+
+/// Default maximum angular distance, in radians, between two consecutive sampled points of a region
+/// boundary (~0.1 deg).
+pub const DEFAULT_STEP: f64 = 1.7453292519943295e-3;
+
+/// Polygon on the unit sphere, made of great-circle arcs.
+/// The polygon is supposed to be smaller than a hemisphere, its inside being the smallest
+/// of the two parts of the sphere it delimits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SphericalPolygon {
+  vertices: Vec<LonLat>,
+  /// Euclidean coordinates of the vertices, computed once for all containment tests.
+  xyz_vertices: Vec<XYZ>,
+  /// Point outside of the polygon (`None` if the polygon is degenerated).
+  outside: Option<XYZ>,
+}
+
+impl SphericalPolygon {
+
+  /// # Params
+  /// * `vertices`: vertices of the polygon, which is implicitly closed (i.e. the last vertex is
+  ///   connected to the first one)
+  /// # Panics
+  /// * if less than 3 vertices are provided
+  pub fn new(vertices: Vec<LonLat>) -> Self {
+    assert!(vertices.len() >= 3);
+    let xyz_vertices: Vec<XYZ> = vertices.iter().map(|v| v.to_xyz()).collect();
+    let outside = outside_point(&xyz_vertices);
+    Self { vertices, xyz_vertices, outside }
+  }
+
+  /// Returns the polygon vertices.
+  pub fn vertices(&self) -> &[LonLat] {
+    &self.vertices
+  }
+
+  /// Returns `true` if the given position is inside the polygon.
+  pub fn contains(&self, lonlat: &LonLat) -> bool {
+    self.contains_xyz(&lonlat.to_xyz())
+  }
+
+  /// Same as `contains`, but for a position given in Euclidean coordinates.
+  pub fn contains_xyz(&self, xyz: &XYZ) -> bool {
+    self.outside.as_ref()
+      .map(|outside| is_in_polygon(&self.xyz_vertices, outside, xyz))
+      .unwrap_or(false)
+  }
+}
+
+/// Box on the unit sphere, as defined in STC: the sides of the box are great circles
+/// perpendicular to the arms of a cross centered on the box center, the arms having
+/// lengths `width` and `height`.
+/// In other words, the box is a rectangle in the `TAN` projection centered on the box center.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SphericalBox {
+  center: LonLat,
+  width: f64,
+  height: f64,
+  angle: f64,
+  /// The box as a polygon, computed once for all containment tests.
+  polygon: SphericalPolygon,
+}
+
+impl SphericalBox {
+
+  /// # Params
+  /// * `center`: center of the box
+  /// * `width`: full size of the box, in radians, along the East direction (for a `0` angle)
+  /// * `height`: full size of the box, in radians, along the North direction (for a `0` angle)
+  /// * `angle`: position angle, in radians, of the height axis, counted from the North toward the East
+  /// # Panics
+  /// * if `width` or `height` is not in `]0, pi[`
+  pub fn new(center: LonLat, width: f64, height: f64, angle: f64) -> Self {
+    assert!(0.0 < width && width < std::f64::consts::PI);
+    assert!(0.0 < height && height < std::f64::consts::PI);
+    let polygon = SphericalPolygon::new(box_vertices(&center, width, height, angle).to_vec());
+    Self { center, width, height, angle, polygon }
+  }
+
+  /// Get the center of the box
+  pub fn center(&self) -> &LonLat {
+    &self.center
+  }
+
+  /// Get the width of the box, in radians
+  pub fn width(&self) -> f64 {
+    self.width
+  }
+
+  /// Get the height of the box, in radians
+  pub fn height(&self) -> f64 {
+    self.height
+  }
+
+  /// Get the position angle of the box, in radians
+  pub fn angle(&self) -> f64 {
+    self.angle
+  }
+
+  /// Returns the 4 corners of the box, counter-clockwise when seen from the inside of the sphere.
+  pub fn vertices(&self) -> [LonLat; 4] {
+    box_vertices(&self.center, self.width, self.height, self.angle)
+  }
+
+  /// Returns `true` if the given position is inside the box.
+  pub fn contains(&self, lonlat: &LonLat) -> bool {
+    self.polygon.contains(lonlat)
+  }
+
+  /// Same as `contains`, but for a position given in Euclidean coordinates.
+  pub fn contains_xyz(&self, xyz: &XYZ) -> bool {
+    self.polygon.contains_xyz(xyz)
+  }
+
+  /// Returns the box as a polygon.
+  pub fn to_polygon(&self) -> SphericalPolygon {
+    self.polygon.clone()
+  }
+}
+
+/// Ellipse on the unit sphere: the locus of the points whose sum of the angular distances to
+/// the two foci is constant. Equivalently, the ellipse is a plane ellipse in the `TAN` projection
+/// centered on the ellipse center, of semi-axes `tan(a)` and `tan(b)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SphericalEllipse {
+  center: LonLat,
+  a: f64,
+  b: f64,
+  angle: f64,
+}
+
+impl SphericalEllipse {
+
+  /// # Params
+  /// * `center`: center of the ellipse
+  /// * `a`: semi-major axis, in radians
+  /// * `b`: semi-minor axis, in radians
+  /// * `angle`: position angle, in radians, of the major axis, counted from the North toward the East
+  /// # Panics
+  /// * if we do not have `0 < b <= a < pi/2`
+  pub fn new(center: LonLat, a: f64, b: f64, angle: f64) -> Self {
+    assert!(0.0 < b && b <= a && a < 0.5 * std::f64::consts::PI);
+    Self { center, a, b, angle }
+  }
+
+  /// Get the center of the ellipse
+  pub fn center(&self) -> &LonLat {
+    &self.center
+  }
+
+  /// Get the semi-major axis, in radians
+  pub fn a(&self) -> f64 {
+    self.a
+  }
+
+  /// Get the semi-minor axis, in radians
+  pub fn b(&self) -> f64 {
+    self.b
+  }
+
+  /// Get the position angle of the major axis, in radians
+  pub fn angle(&self) -> f64 {
+    self.angle
+  }
+
+  /// Returns `true` if the given position is inside the ellipse (or on its border).
+  pub fn contains(&self, lonlat: &LonLat) -> bool {
+    self.contains_xyz(&lonlat.to_xyz())
+  }
+
+  /// Same as `contains`, but for a position given in Euclidean coordinates.
+  pub fn contains_xyz(&self, xyz: &XYZ) -> bool {
+    let (c, ma, mi) = self.frame();
+    let d = xyz.scalar(&c);
+    d > 0.0 && {
+      let x = xyz.scalar(&ma) / (d * self.a.tan());
+      let y = xyz.scalar(&mi) / (d * self.b.tan());
+      x.pow2() + y.pow2() <= 1.0
+    }
+  }
+
+  /// Returns `n` points regularly sampling (in the `TAN` projection) the ellipse border,
+  /// clockwise in the (East, North) tangent plane, starting from the end of the major axis.
+  pub fn border_points(&self, n: usize) -> Vec<XYZ> {
+    let (c, ma, mi) = self.frame();
+    let (ta, tb) = (self.a.tan(), self.b.tan());
+    (0..n)
+      .map(|i| {
+        let (sint, cost) = (std::f64::consts::TAU * i as f64 / n as f64).sin_cos();
+        let (x, y) = (ta * cost, tb * sint);
+        normalize([
+          c.x() + x * ma.x() + y * mi.x(),
+          c.y() + x * ma.y() + y * mi.y(),
+          c.z() + x * ma.z() + y * mi.z(),
+        ]).unwrap()
+      })
+      .collect()
+  }
+
+  /// Returns the center and the unit vectors along the major and the minor axes.
+  fn frame(&self) -> (XYZ, XYZ, XYZ) {
+    let c = self.center.to_xyz();
+    let (e, n) = tangent_frame(&c);
+    let (sina, cosa) = self.angle.sin_cos();
+    let ma = XYZ::new(
+      n.x() * cosa + e.x() * sina, n.y() * cosa + e.y() * sina, n.z() * cosa + e.z() * sina
+    );
+    let mi = XYZ::new(
+      e.x() * cosa - n.x() * sina, e.y() * cosa - n.y() * sina, e.z() * cosa - n.z() * sina
+    );
+    (c, ma, mi)
+  }
+}
+
+/// A region on the unit sphere.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkyRegion {
+  Cone(Cone),
+  Ellipse(SphericalEllipse),
+  Polygon(SphericalPolygon),
+  Box(SphericalBox),
+  Union(Vec<SkyRegion>),
+}
+
+impl SkyRegion {
+
+  /// Returns `true` if the given position is inside the region.
+  pub fn contains(&self, lonlat: &LonLat) -> bool {
+    self.contains_xyz(&lonlat.to_xyz())
+  }
+
+  /// Same as `contains`, but for a position given in Euclidean coordinates.
+  pub fn contains_xyz(&self, xyz: &XYZ) -> bool {
+    match self {
+      SkyRegion::Cone(cone) => cone.contains_xyz(xyz),
+      SkyRegion::Ellipse(ellipse) => ellipse.contains_xyz(xyz),
+      SkyRegion::Polygon(polygon) => polygon.contains_xyz(xyz),
+      SkyRegion::Box(sbox) => sbox.contains_xyz(xyz),
+      SkyRegion::Union(regions) => regions.iter().any(|r| r.contains_xyz(xyz)),
+    }
+  }
+
+  /// Returns `true` if the given image position is inside the region
+  /// (`false` if the position can't be deprojected).
+  pub fn contains_img<C: ImgXY2Celestial + ?Sized>(&self, img2celestial: &C, img_pos: &ImgXY) -> bool {
+    img2celestial.img2xyz(img_pos)
+      .map(|xyz| self.contains_xyz(&xyz))
+      .unwrap_or(false)
+  }
+
+  /// Returns the mask of the region in the given image: `true` for the pixels whose center is
+  /// inside the region.
+  /// The mask is stored row by row, i.e. the value of pixel `(i, j)` is at index `j * naxis1 + i`.
+  pub fn img_mask<T: ImgXY2Celestial>(&self, img: &BoundedImg2Celestial<T>) -> Vec<bool> {
+    (0..img.naxis2())
+      .flat_map(|j| (0..img.naxis1()).map(move |i| (i, j)))
+      .map(|(i, j)| {
+        img.img2celestial().img2xyz(&img.pixel_center(i, j))
+          .map(|xyz| self.contains_xyz(&xyz))
+          .unwrap_or(false)
+      })
+      .collect()
+  }
+
+  /// Returns the boundaries of the region, sampled on the sphere.
+  /// Each boundary is a closed ring (the last point is not a copy of the first one).
+  /// # Params
+  /// * `step`: maximum angular distance, in radians, between two consecutive points
+  pub fn boundaries(&self, step: f64) -> Vec<Vec<XYZ>> {
+    match self {
+      SkyRegion::Cone(cone) => {
+        let n = ((std::f64::consts::TAU * cone.radius().sin() / step).ceil() as usize).max(8);
+        vec![small_circle_points(&cone.center().to_xyz(), cone.radius(), n)]
+      },
+      SkyRegion::Ellipse(ellipse) => {
+        let n = ((std::f64::consts::TAU * ellipse.a().sin() / step).ceil() as usize).max(8);
+        vec![ellipse.border_points(n)]
+      },
+      SkyRegion::Polygon(polygon) => vec![sample_polygon(polygon.vertices(), step)],
+      SkyRegion::Box(sbox) => vec![sample_polygon(&sbox.vertices(), step)],
+      SkyRegion::Union(regions) => regions.iter().flat_map(|r| r.boundaries(step)).collect(),
+    }
+  }
+
+  /// Returns the boundaries of the region projected in the given projection plane.
+  /// Each boundary is returned as one or several polylines: a closed boundary fully projected
+  /// is returned with its first point repeated at the end; the polylines are densified where
+  /// the projection curves and split where the projection fails and at the projection
+  /// discontinuities (e.g. the `lon = 180 deg` line in `AIT`).
+  /// # Params
+  /// * `proj`: the projection
+  /// * `step`: maximum angular distance, in radians, between two consecutive sampled points
+  ///   before densification
+  /// * `tol`: the maximum distance, in the projection plane, between the polylines and the
+  ///   projected boundaries
+  pub fn proj_boundaries<P: Projection + ?Sized>(&self, proj: &P, step: f64, tol: f64) -> Vec<Vec<ProjXY>> {
+    self.projected_boundaries(|xyz| proj.proj_xyz(xyz).map(|p| (p.x(), p.y())), step, tol, |(x, y)| ProjXY::new(x, y))
+  }
+
+  /// Same as `proj_boundaries`, but in image coordinates (`tol` being in pixels).
+  pub fn img_boundaries<C: ImgXY2Celestial + ?Sized>(&self, img2celestial: &C, step: f64, tol: f64) -> Vec<Vec<ImgXY>> {
+    self.projected_boundaries(|xyz| img2celestial.xyz2img(xyz).map(|p| (p.x(), p.y())), step, tol, |(x, y)| ImgXY::new(x, y))
+  }
+
+  fn projected_boundaries<T, F, G>(&self, f: F, step: f64, tol: f64, to_t: G) -> Vec<Vec<T>>
+    where
+      F: Fn(&XYZ) -> Option<(f64, f64)>,
+      G: Fn((f64, f64)) -> T
+  {
+    assert!(tol > 0.0);
+    self.boundaries(step).iter()
+      .flat_map(|ring| project_curve(&densify(ring, true, &f, tol), true, &f))
+      .map(|polyline| polyline.into_iter().map(&to_t).collect())
+      .collect()
+  }
+}
+
+/// Returns the 4 corners of the given box, see `SphericalBox::vertices`.
+fn box_vertices(center: &LonLat, width: f64, height: f64, angle: f64) -> [LonLat; 4] {
+  let c = center.to_xyz();
+  let (e, n) = tangent_frame(&c);
+  let (sina, cosa) = angle.sin_cos();
+  // Unit vectors along the width and along the height axes
+  let w = [e.x() * cosa - n.x() * sina, e.y() * cosa - n.y() * sina, e.z() * cosa - n.z() * sina];
+  let h = [n.x() * cosa + e.x() * sina, n.y() * cosa + e.y() * sina, n.z() * cosa + e.z() * sina];
+  let tw = (0.5 * width).tan();
+  let th = (0.5 * height).tan();
+  let corner = |sw: f64, sh: f64| {
+    let (dw, dh) = (sw * tw, sh * th);
+    normalize([
+      c.x() + dw * w[0] + dh * h[0],
+      c.y() + dw * w[1] + dh * h[1],
+      c.z() + dw * w[2] + dh * h[2],
+    ]).unwrap().to_lonlat()
+  };
+  // North-West (top right in a sky image), North-East, South-East, South-West
+  [corner(-1.0, 1.0), corner(1.0, 1.0), corner(1.0, -1.0), corner(-1.0, -1.0)]
+}
+
+/// Sample the great-circle arcs of the given closed polygon.
+fn sample_polygon(vertices: &[LonLat], step: f64) -> Vec<XYZ> {
+  let vertices: Vec<XYZ> = vertices.iter().map(|v| v.to_xyz()).collect();
+  vertices.iter()
+    .zip(vertices.iter().cycle().skip(1))
+    .filter(|(a, b)| ang_dist(a, b) > 0.0)
+    .flat_map(|(a, b)| great_arc_points(a, b, step)
+      // Undefined arc between antipodal vertices
+      .unwrap_or_else(|| vec![a.clone()]))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::CenteredProjection;
+  use crate::bounded::tan_image;
+  use crate::pseudocyl::mol::Mol;
+  use crate::cylindrical::car::Car;
+  use crate::sphere::arc_middle;
+  use crate::zenithal::sin::Sin;
+
+  #[test]
+  fn test_box_and_polygon() {
+    let center = LonLat::new(10_f64.to_radians(), 20_f64.to_radians());
+    let sbox = SkyRegion::Box(SphericalBox::new(center.clone(), 2_f64.to_radians(), 1_f64.to_radians(), 0.0));
+    assert!(sbox.contains(&center));
+    assert!(sbox.contains(&LonLat::new(10_f64.to_radians(), 20.45_f64.to_radians())));
+    assert!(!sbox.contains(&LonLat::new(10_f64.to_radians(), 20.55_f64.to_radians())));
+    // Width along the East: ~1 deg / cos(20 deg) in longitude
+    assert!(sbox.contains(&LonLat::new(11.05_f64.to_radians(), 20_f64.to_radians())));
+    assert!(!sbox.contains(&LonLat::new(11.1_f64.to_radians(), 20_f64.to_radians())));
+
+    let mut proj = CenteredProjection::new(Sin::new());
+    proj.set_proj_center_from_lonlat(&center);
+    let boundaries = sbox.proj_boundaries(&proj, DEFAULT_STEP, 1e-6);
+    assert_eq!(boundaries.len(), 1);
+    assert_eq!(boundaries[0].first(), boundaries[0].last());
+    // Opposite side of the sky: SIN fails everywhere
+    let far = SkyRegion::Cone(Cone::new(LonLat::new(190_f64.to_radians(), -20_f64.to_radians()), 0.1));
+    assert!(far.proj_boundaries(&proj, DEFAULT_STEP, 1e-6).is_empty());
+  }
+
+  #[test]
+  fn test_ellipse_and_mask() {
+    let center = LonLat::new(179_f64.to_radians(), 10_f64.to_radians());
+    // 3 deg x 1 deg, major axis along the North
+    let ellipse = SphericalEllipse::new(center.clone(), 3_f64.to_radians(), 1_f64.to_radians(), 0.0);
+    assert!(ellipse.contains(&LonLat::new(179_f64.to_radians(), 12.9_f64.to_radians())));
+    assert!(!ellipse.contains(&LonLat::new(179_f64.to_radians(), 13.1_f64.to_radians())));
+    assert!(!ellipse.contains(&LonLat::new(177.9_f64.to_radians(), 10_f64.to_radians())));
+    assert!(ellipse.contains(&LonLat::new(178.1_f64.to_radians(), 10_f64.to_radians())));
+    let region = SkyRegion::Ellipse(ellipse.clone());
+    let c = center.to_xyz();
+    let axes = ellipse.border_points(4);
+    assert!((ang_dist(&c, &axes[0]) - 3_f64.to_radians()).abs() < 1e-12);
+    assert!((ang_dist(&c, &axes[1]) - 1_f64.to_radians()).abs() < 1e-12);
+    // Cut by the lon = 180 deg discontinuity of MOL
+    assert_eq!(region.proj_boundaries(&Mol::new(), DEFAULT_STEP, 1e-6).len(), 2);
+    // Coarse sampling densified where the great-circle arcs of a polygon are curved in CAR
+    let polygon = SkyRegion::Polygon(SphericalPolygon::new(vec![
+      LonLat::new(0.2, 0.1), LonLat::new(0.9, 0.7), LonLat::new(0.1, 1.2)
+    ]));
+    let car = Car::new();
+    let coarse = polygon.proj_boundaries(&car, 10_f64.to_radians(), 1.0);
+    let boundaries = polygon.proj_boundaries(&car, 10_f64.to_radians(), 1e-4);
+    assert_eq!(boundaries.len(), 1);
+    assert!(boundaries[0].len() > 2 * coarse[0].len());
+    for (a, b) in boundaries[0].iter().zip(boundaries[0].iter().skip(1)) {
+      let m = arc_middle(&car.unproj_xyz(a).unwrap(), &car.unproj_xyz(b).unwrap()).unwrap();
+      let pm = car.proj_xyz(&m).unwrap();
+      assert!((pm.x() - 0.5 * (a.x() + b.x())).hypot(pm.y() - 0.5 * (a.y() + b.y())) < 1e-4);
+    }
+    // Mask in a 1 arcmin/pixel TAN image
+    let img = tan_image(&center, (100.5, 200.5), 0.0, 1.0 / 60.0, (200, 400));
+    let mask = region.img_mask(&img);
+    assert_eq!(mask.len(), 200 * 400);
+    // Area of the ellipse in the TAN plane: pi tan(a) tan(b) ~ 565.6 pixels of 1 arcmin^2
+    let n = mask.iter().filter(|b| **b).count() as f64;
+    let expected = std::f64::consts::PI * 3_f64.to_radians().tan() * 1_f64.to_radians().tan() * (60.0 * 180.0 / std::f64::consts::PI).powi(2);
+    assert!((n - expected).abs() / expected < 0.01);
+    assert!(mask[200 * 200 + 99] && !mask[0]);
+    // Polygon and box masks
+    let sbox = SphericalBox::new(center.clone(), 2_f64.to_radians(), 4_f64.to_radians(), 0.3);
+    let region = SkyRegion::Union(vec![SkyRegion::Box(sbox.clone()), SkyRegion::Polygon(sbox.to_polygon())]);
+    let mask = region.img_mask(&img);
+    for (k, m) in mask.iter().enumerate().step_by(97) {
+      let pos = img.pixel_center(k as u32 % 200, k as u32 / 200);
+      assert_eq!(*m, region.contains_img(&img, &pos));
+    }
+    assert!(mask[200 * 200 + 99] && !mask[0]);
+  }
+}
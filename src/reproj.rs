@@ -0,0 +1,523 @@
+//! Module containing the reprojection (resampling) of an image from one WCS to another.
+//!
+//! Images are 2D arrays stored row by row in a slice (`index = j * naxis1 + i`, with `i` the index
+//! of the pixel along the x-axis and `j` its index along the y-axis), the geometry of each image
+//! being given by a `BoundedImg2Celestial`.
+//! For each target pixel, the center of the pixel is mapped into the source image (using a
+//! `PixMapper`) and the source image is interpolated at that position.
+//! Alternatively, the exact-area (drizzle-like) mode distributes each source pixel value among
+//! the target pixels it overlaps, conserving the flux.
+
+use std::f64::consts::PI;
+
+use crate::{CustomFloat, ImgXY};
+use crate::bounded::BoundedImg2Celestial;
+use crate::img2celestial::ImgXY2Celestial;
+
+/// Maximum distance, relative to the size of a projected source pixel, between the projection of
+/// the middle of an edge of the source pixel and the middle of the projected edge, in the
+/// exact-area reprojection (larger distances reveal a discontinuity of the target projection).
+const MAX_EDGE_DEVIATION: f64 = 0.25;
+
+/// Interpolation kernel used to resample the source image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+  /// Value of the closest source pixel.
+  Nearest,
+  /// Bilinear interpolation on the 2x2 closest source pixels.
+  Bilinear,
+  /// Bicubic (Keys, `a = -0.5`) interpolation on the 4x4 closest source pixels.
+  Bicubic,
+  /// Lanczos interpolation of given order `a` (usually 2 or 3) on the `2a x 2a` closest source pixels.
+  Lanczos(u8),
+}
+
+impl Interpolation {
+
+  /// Returns the half width, in pixels, of the kernel support.
+  fn radius(&self) -> i64 {
+    match self {
+      Interpolation::Nearest => 0,
+      Interpolation::Bilinear => 1,
+      Interpolation::Bicubic => 2,
+      Interpolation::Lanczos(a) => *a as i64,
+    }
+  }
+
+  /// Returns the 1D kernel weight at the given distance (in pixels) from the interpolated position.
+  fn weight(&self, t: f64) -> f64 {
+    let t = t.abs();
+    match self {
+      Interpolation::Nearest => if t <= 0.5 { 1.0 } else { 0.0 },
+      Interpolation::Bilinear => (1.0 - t).max(0.0),
+      Interpolation::Bicubic => {
+        const A: f64 = -0.5;
+        if t <= 1.0 {
+          ((A + 2.0) * t - (A + 3.0)) * t.pow2() + 1.0
+        } else if t < 2.0 {
+          ((A * t - 5.0 * A) * t + 8.0 * A) * t - 4.0 * A
+        } else {
+          0.0
+        }
+      },
+      Interpolation::Lanczos(a) => {
+        let a = *a as f64;
+        if t < a {
+          (PI * t).sinc() * (PI * t / a).sinc()
+        } else {
+          0.0
+        }
+      },
+    }
+  }
+}
+
+/// Maps the pixel coordinates of a target image into the pixel coordinates of a source image.
+pub trait PixMapper {
+
+  /// Returns the position in the source image of the given position of the target image.
+  fn map(&self, target_pos: &ImgXY) -> Option<ImgXY>;
+
+  /// Maps a batch of positions (see `map`).
+  fn map_batch(&self, target_pos: &[ImgXY]) -> Vec<Option<ImgXY>> {
+    target_pos.iter().map(|pos| self.map(pos)).collect()
+  }
+}
+
+/// Exact pixel mapper: each target position is transformed into celestial coordinates which
+/// are then transformed into source pixel coordinates.
+pub struct ExactPixMapper<'a, S: ImgXY2Celestial + ?Sized, T: ImgXY2Celestial + ?Sized> {
+  src: &'a S,
+  target: &'a T,
+}
+
+impl<'a, S: ImgXY2Celestial + ?Sized, T: ImgXY2Celestial + ?Sized> ExactPixMapper<'a, S, T> {
+
+  /// # Params
+  /// * `src`: transformation of the source image
+  /// * `target`: transformation of the target image
+  pub fn new(src: &'a S, target: &'a T) -> Self {
+    Self { src, target }
+  }
+}
+
+impl<S: ImgXY2Celestial + ?Sized, T: ImgXY2Celestial + ?Sized> PixMapper for ExactPixMapper<'_, S, T> {
+  fn map(&self, target_pos: &ImgXY) -> Option<ImgXY> {
+    self.target.img2xyz(target_pos).and_then(|xyz| self.src.xyz2img(&xyz))
+  }
+}
+
+/// Resamples a source image into the pixel grid of a target image.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+  interpolation: Interpolation,
+  blank: f64,
+}
+
+impl Default for Resampler {
+  fn default() -> Self {
+    Self::new(Interpolation::Bilinear)
+  }
+}
+
+impl Resampler {
+
+  /// New resampler using the given interpolation kernel and `NaN` as blank value.
+  /// # Panics
+  /// * if the order of a Lanczos kernel equals 0
+  pub fn new(interpolation: Interpolation) -> Self {
+    assert_ne!(interpolation, Interpolation::Lanczos(0));
+    Self { interpolation, blank: f64::NAN }
+  }
+
+  /// Set the blank value, i.e. the value of both the source pixels to be ignored
+  /// and the target pixels without value. Source pixels having a `NaN` value are always ignored.
+  pub fn set_blank(&mut self, blank: f64) {
+    self.blank = blank;
+  }
+
+  /// Get the interpolation kernel
+  pub fn interpolation(&self) -> Interpolation {
+    self.interpolation
+  }
+
+  /// Get the blank value
+  pub fn blank(&self) -> f64 {
+    self.blank
+  }
+
+  /// Reprojects the source image into the target image.
+  /// # Params
+  /// * `src_data`: values of the source image pixels, row by row
+  /// * `src`: geometry of the source image
+  /// * `target`: geometry of the target image
+  /// # Return
+  /// * the values of the target image pixels, row by row
+  /// # Panics
+  /// * if the length of `src_data` is not `src.naxis1() * src.naxis2()`
+  pub fn reproject<S, T>(
+    &self,
+    src_data: &[f64],
+    src: &BoundedImg2Celestial<S>,
+    target: &BoundedImg2Celestial<T>
+  ) -> Vec<f64>
+    where
+      S: ImgXY2Celestial,
+      T: ImgXY2Celestial
+  {
+    self.reproject_with_mapper(src_data, src, target, &ExactPixMapper::new(src, target)).0
+  }
+
+  /// Same as `reproject` but also returning the footprint (or weight) map of the target image:
+  /// the fraction, in `[0, 1]`, of the interpolation kernel weight coming from valid source pixels
+  /// (`0` for pixels outside of the source image, `1` for pixels fully inside).
+  pub fn reproject_with_footprint<S, T>(
+    &self,
+    src_data: &[f64],
+    src: &BoundedImg2Celestial<S>,
+    target: &BoundedImg2Celestial<T>
+  ) -> (Vec<f64>, Vec<f64>)
+    where
+      S: ImgXY2Celestial,
+      T: ImgXY2Celestial
+  {
+    self.reproject_with_mapper(src_data, src, target, &ExactPixMapper::new(src, target))
+  }
+
+  /// Same as `reproject_with_footprint` but using the given pixel mapper
+  /// (e.g. the approximate, faster, `gridmap::AdaptiveGridMapper`) instead of the exact one.
+  /// # Params
+  /// * `src_data`: values of the source image pixels, row by row
+  /// * `src`: geometry of the source image (its transformation is not used)
+  /// * `target`: geometry of the target image (its transformation is not used)
+  /// * `mapper`: maps the target pixel coordinates into source pixel coordinates
+  pub fn reproject_with_mapper<S, T, M>(
+    &self,
+    src_data: &[f64],
+    src: &BoundedImg2Celestial<S>,
+    target: &BoundedImg2Celestial<T>,
+    mapper: &M
+  ) -> (Vec<f64>, Vec<f64>)
+    where
+      S: ImgXY2Celestial,
+      T: ImgXY2Celestial,
+      M: PixMapper + ?Sized
+  {
+    let (src_w, src_h) = (src.naxis1() as usize, src.naxis2() as usize);
+    assert_eq!(src_data.len(), src_w * src_h);
+    let src_first = src.convention().first_pixel_center();
+    let (w, h) = (target.naxis1() as usize, target.naxis2() as usize);
+    let mut values = Vec::with_capacity(w * h);
+    let mut footprint = Vec::with_capacity(w * h);
+    let mut row = Vec::with_capacity(w);
+    for j in 0..target.naxis2() {
+      row.clear();
+      row.extend((0..target.naxis1()).map(|i| target.pixel_center(i, j)));
+      for src_pos in mapper.map_batch(&row) {
+        let (value, weight) = src_pos
+          .filter(|pos| src.contains_img(pos))
+          .map(|pos| self.interpolate(src_data, src_w, src_h, pos.x() - src_first, pos.y() - src_first))
+          .unwrap_or((self.blank, 0.0));
+        values.push(value);
+        footprint.push(weight);
+      }
+    }
+    (values, footprint)
+  }
+
+  /// Exact-area (flux-conserving) reprojection of the source image into the target image.
+  /// The four corners of each source pixel are projected into the target image, and the value
+  /// of the source pixel is distributed among the target pixels proportionally to the area of
+  /// their overlap with the projected source pixel.
+  /// The interpolation kernel is not used.
+  /// # Params
+  /// * `src_data`: values of the source image pixels, row by row
+  /// * `src`: geometry of the source image
+  /// * `target`: geometry of the target image
+  /// * `conserve_flux`: if `true`, the source values are fluxes (e.g. counts) which are split
+  ///   among the target pixels so that the total flux is conserved; if `false`, the source values
+  ///   are surface brightnesses and each target value is the overlap-weighted mean of the source values
+  /// # Return
+  /// * `(values, weights)`, `weights` being the coverage of each target pixel, i.e. the sum of
+  ///   the overlap areas (`1` for a target pixel fully covered by valid source pixels)
+  /// # Remark
+  /// * the overlaps are computed in the target pixel plane, approximating the projected source pixels
+  ///   by quadrilaterals, which is accurate as long as the pixels are small.
+  /// * projected source pixels crossing a discontinuity of the target projection (detected by
+  ///   mapping the middle of the edges of the source pixels larger than a target pixel) are ignored.
+  pub fn reproject_exact<S, T>(
+    &self,
+    src_data: &[f64],
+    src: &BoundedImg2Celestial<S>,
+    target: &BoundedImg2Celestial<T>,
+    conserve_flux: bool
+  ) -> (Vec<f64>, Vec<f64>)
+    where
+      S: ImgXY2Celestial,
+      T: ImgXY2Celestial
+  {
+    self.reproject_exact_with_mapper(src_data, src, target, &ExactPixMapper::new(target, src), conserve_flux)
+  }
+
+  /// Same as `reproject_exact` but using the given pixel mapper, mapping the source pixel
+  /// coordinates into the target pixel coordinates (the reverse of the mapper used in
+  /// `reproject_with_mapper`).
+  pub fn reproject_exact_with_mapper<S, T, M>(
+    &self,
+    src_data: &[f64],
+    src: &BoundedImg2Celestial<S>,
+    target: &BoundedImg2Celestial<T>,
+    src2target: &M,
+    conserve_flux: bool
+  ) -> (Vec<f64>, Vec<f64>)
+    where
+      S: ImgXY2Celestial,
+      T: ImgXY2Celestial,
+      M: PixMapper + ?Sized
+  {
+    let (src_w, src_h) = (src.naxis1() as usize, src.naxis2() as usize);
+    assert_eq!(src_data.len(), src_w * src_h);
+    let (w, h) = (target.naxis1() as usize, target.naxis2() as usize);
+    let target_first = target.convention().first_pixel_center();
+    let mut sums = vec![0.0; w * h];
+    let mut weights = vec![0.0; w * h];
+    // Corners of the source pixels, in the target image, for the lower and upper edges of the current row
+    let corner_row = |y: f64| -> Vec<Option<(f64, f64)>> {
+      let (xmin, _) = src.x_edges();
+      let corners: Vec<ImgXY> = (0..=src_w).map(|i| ImgXY::new(xmin + i as f64, y)).collect();
+      src2target.map_batch(&corners).into_iter()
+        // 0-based coordinates with the target pixel k covering [k, k + 1]
+        .map(|pos| pos.map(|p| (p.x() - target_first + 0.5, p.y() - target_first + 0.5)))
+        .collect()
+    };
+    let (xmin, _) = src.x_edges();
+    let (ymin, _) = src.y_edges();
+    // The middle of each edge of a continuous quad is mapped close to the middle of its corners
+    let is_continuous = |quad: &[(f64, f64); 4], i: usize, j: usize| -> bool {
+      let (x0, y0) = (xmin + i as f64, ymin + j as f64);
+      let middles = [
+        ImgXY::new(x0 + 0.5, y0), ImgXY::new(x0 + 1.0, y0 + 0.5),
+        ImgXY::new(x0 + 0.5, y0 + 1.0), ImgXY::new(x0, y0 + 0.5),
+      ];
+      let size = quad.iter()
+        .zip(quad.iter().cycle().skip(1))
+        .map(|((x1, y1), (x2, y2))| (x2 - x1).hypot(y2 - y1))
+        .fold(0.0, f64::max);
+      src2target.map_batch(&middles).into_iter()
+        .zip(quad.iter().zip(quad.iter().cycle().skip(1)))
+        .all(|(m, ((x1, y1), (x2, y2)))| m.is_some_and(|m| {
+          let (mx, my) = (m.x() - target_first + 0.5, m.y() - target_first + 0.5);
+          (mx - 0.5 * (x1 + x2)).hypot(my - 0.5 * (y1 + y2)) < MAX_EDGE_DEVIATION * size
+        }))
+    };
+    let mut lower = corner_row(ymin);
+    for j in 0..src_h {
+      let upper = corner_row(ymin + (j + 1) as f64);
+      for i in 0..src_w {
+        let value = src_data[j * src_w + i];
+        if self.is_blank(value) {
+          continue;
+        }
+        let quad = match (lower[i], lower[i + 1], upper[i + 1], upper[i]) {
+          (Some(a), Some(b), Some(c), Some(d)) => [a, b, c, d],
+          _ => continue,
+        };
+        let (qxmin, qxmax, qymin, qymax) = quad.iter().fold(
+          (f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY),
+          |(x0, x1, y0, y1), (x, y)| (x0.min(*x), x1.max(*x), y0.min(*y), y1.max(*y))
+        );
+        let quad_area = polygon_area(&quad);
+        if quad_area <= 0.0 || qxmax <= 0.0 || qymax <= 0.0 || qxmin >= w as f64 || qymin >= h as f64
+          || ((qxmax - qxmin > 1.0 || qymax - qymin > 1.0) && !is_continuous(&quad, i, j)) {
+          continue;
+        }
+        for ty in (qymin.floor().max(0.0) as usize)..(qymax.ceil().min(h as f64) as usize) {
+          for tx in (qxmin.floor().max(0.0) as usize)..(qxmax.ceil().min(w as f64) as usize) {
+            let overlap = polygon_area(&clip_to_box(&quad, tx as f64, ty as f64));
+            if overlap > 0.0 {
+              let k = ty * w + tx;
+              weights[k] += overlap;
+              sums[k] += value * if conserve_flux { overlap / quad_area } else { overlap };
+            }
+          }
+        }
+      }
+      lower = upper;
+    }
+    let values = sums.iter().zip(weights.iter())
+      .map(|(sum, weight)| match (*weight > 0.0, conserve_flux) {
+        (false, _) => self.blank,
+        (true, true) => *sum,
+        (true, false) => sum / weight,
+      })
+      .collect();
+    (values, weights)
+  }
+
+  /// Returns `true` if the given source value must be ignored.
+  fn is_blank(&self, value: f64) -> bool {
+    value.is_nan() || value == self.blank
+  }
+
+  /// Interpolates the source image at the given 0-based pixel coordinates, returning
+  /// the interpolated value and the fraction of the kernel weight from valid source pixels.
+  fn interpolate(&self, data: &[f64], w: usize, h: usize, u: f64, v: f64) -> (f64, f64) {
+    if self.interpolation == Interpolation::Nearest {
+      let i = (u.round().max(0.0) as usize).min(w - 1);
+      let j = (v.round().max(0.0) as usize).min(h - 1);
+      let value = data[j * w + i];
+      return if self.is_blank(value) { (self.blank, 0.0) } else { (value, 1.0) };
+    }
+    let r = self.interpolation.radius();
+    let (i0, j0) = (u.floor() as i64, v.floor() as i64);
+    let (mut sum, mut sum_w, mut sum_w_tot) = (0.0, 0.0, 0.0);
+    for j in (j0 - r + 1)..=(j0 + r) {
+      let wy = self.interpolation.weight(v - j as f64);
+      if wy == 0.0 {
+        continue;
+      }
+      for i in (i0 - r + 1)..=(i0 + r) {
+        let wxy = wy * self.interpolation.weight(u - i as f64);
+        if wxy == 0.0 {
+          continue;
+        }
+        sum_w_tot += wxy;
+        if (0..w as i64).contains(&i) && (0..h as i64).contains(&j) {
+          let value = data[j as usize * w + i as usize];
+          if !self.is_blank(value) {
+            sum += wxy * value;
+            sum_w += wxy;
+          }
+        }
+      }
+    }
+    if sum_w.abs() > 1.0e-6 * sum_w_tot.abs() && sum_w != 0.0 {
+      (sum / sum_w, (sum_w / sum_w_tot).clamp(0.0, 1.0))
+    } else {
+      (self.blank, 0.0)
+    }
+  }
+}
+
+/// Returns the (unsigned) area of the given planar polygon.
+fn polygon_area(vertices: &[(f64, f64)]) -> f64 {
+  vertices.iter()
+    .zip(vertices.iter().cycle().skip(1))
+    .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+    .sum::<f64>()
+    .abs()
+    .half()
+}
+
+/// Clips the given planar polygon by the unit square `[x, x + 1] x [y, y + 1]`
+/// (Sutherland-Hodgman algorithm).
+fn clip_to_box(vertices: &[(f64, f64)], x: f64, y: f64) -> Vec<(f64, f64)> {
+  // Each clipping edge is defined by: (coordinate index, limit, keep values greater than limit)
+  let edges = [(0, x, true), (0, x + 1.0, false), (1, y, true), (1, y + 1.0, false)];
+  let mut output = vertices.to_vec();
+  for (axis, limit, keep_greater) in edges {
+    let input = std::mem::take(&mut output);
+    let coo = |p: &(f64, f64)| if axis == 0 { p.0 } else { p.1 };
+    let inside = |p: &(f64, f64)| (coo(p) >= limit) == keep_greater || coo(p) == limit;
+    for (k, cur) in input.iter().enumerate() {
+      let prev = &input[(k + input.len() - 1) % input.len()];
+      let intersect = || {
+        let t = (limit - coo(prev)) / (coo(cur) - coo(prev));
+        (prev.0 + t * (cur.0 - prev.0), prev.1 + t * (cur.1 - prev.1))
+      };
+      match (inside(prev), inside(cur)) {
+        (true, true) => output.push(*cur),
+        (true, false) => output.push(intersect()),
+        (false, true) => {
+          output.push(intersect());
+          output.push(*cur);
+        },
+        (false, false) => {},
+      }
+    }
+    if output.is_empty() {
+      break;
+    }
+  }
+  output
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{CenteredProjection, LonLat};
+  use crate::bounded::{tan_image, PixelConvention};
+  use crate::img2celestial::Img2Celestial;
+  use crate::img2proj::WcsImgXY2ProjXY;
+  use crate::cylindrical::car::Car;
+
+  #[test]
+  fn test_reproject() {
+    let center = LonLat::new(0.5, 0.2);
+    let n = 50;
+    let src = tan_image(&center, (25.5, 25.5), 0.0, 1.0 / 3600.0, (n, n));
+    // Linear ramp
+    let data: Vec<f64> = (0..n * n).map(|k| (k % n) as f64 + 2.0 * (k / n) as f64).collect();
+    // Same grid: all kernels return the input
+    for interpolation in [Interpolation::Nearest, Interpolation::Bilinear, Interpolation::Bicubic, Interpolation::Lanczos(3)] {
+      let (values, footprint) = Resampler::new(interpolation).reproject_with_footprint(&data, &src, &src);
+      for k in 0..(n * n) as usize {
+        assert!((values[k] - data[k]).abs() < 1e-6, "{:?}: {} != {}", interpolation, values[k], data[k]);
+      }
+      assert!(footprint.iter().all(|w| *w > 0.0));
+    }
+    // Target shifted by half a pixel along x and twice as large: bilinear is exact for a ramp
+    let target = tan_image(&center, (25.0, 25.5), 0.0, 1.0 / 3600.0, (2 * n, 2 * n));
+    let (values, footprint) = Resampler::new(Interpolation::Bilinear).reproject_with_footprint(&data, &src, &target);
+    // Pixel (i, j) of the target is at x = i + 1.5, y = j + 1.0 in the source
+    let (i, j) = (10, 20);
+    assert!((values[j * 100 + i] - (i as f64 + 0.5 + 2.0 * j as f64)).abs() < 1e-6);
+    assert_eq!(footprint[j * 100 + i], 1.0);
+    // Outside of the source image
+    assert!(values[99 * 100 + 99].is_nan());
+    assert_eq!(footprint[99 * 100 + 99], 0.0);
+  }
+
+  #[test]
+  fn test_reproject_exact() {
+    let center = LonLat::new(0.5, 0.2);
+    let n = 20;
+    let src = tan_image(&center, (10.5, 10.5), 0.0, 2.0 / 3600.0, (n, n));
+    let data: Vec<f64> = (0..n * n).map(|k| 1.0 + (k % 7) as f64).collect();
+    let total: f64 = data.iter().sum();
+    // Target with pixels twice smaller, fully covering the source image
+    let target = tan_image(&center, (30.5, 30.5), 0.0, 1.0 / 3600.0, (3 * n, 3 * n));
+    let resampler = Resampler::default();
+    let (values, weights) = resampler.reproject_exact(&data, &src, &target, true);
+    let total_target: f64 = values.iter().filter(|v| !v.is_nan()).sum();
+    assert!((total_target - total).abs() < 1e-6 * total);
+    // Target pixel fully inside the source pixel (0, 0), i.e. the target pixels (10..12, 10..12)
+    assert!((weights[10 * 60 + 10] - 1.0).abs() < 1e-6);
+    assert!((values[10 * 60 + 10] - 0.25 * data[0]).abs() < 1e-6);
+    let (values, _) = resampler.reproject_exact(&data, &src, &target, false);
+    assert!((values[10 * 60 + 10] - data[0]).abs() < 1e-6);
+    assert!(values[0].is_nan());
+  }
+
+  #[test]
+  fn test_reproject_exact_zoom_in() {
+    let center = LonLat::new(0.5, 0.2);
+    // Source pixels 60 times larger than the target pixels, the target covering less than 1 source pixel
+    let src = tan_image(&center, (5.5, 5.5), 0.0, 60.0 / 3600.0, (10, 10));
+    let data: Vec<f64> = (0..100).map(|k| k as f64).collect();
+    let target = tan_image(&center, (10.5, 10.5), 0.0, 1.0 / 3600.0, (20, 20));
+    let (values, weights) = Resampler::default().reproject_exact(&data, &src, &target, false);
+    assert!(weights.iter().all(|w| (w - 1.0).abs() < 1e-6));
+    // The target center is at the corner of the source pixels (4, 4), (5, 4), (4, 5) and (5, 5)
+    assert_eq!(values[0], data[4 * 10 + 4]);
+    assert_eq!(values[19 * 20 + 19], data[5 * 10 + 5]);
+    // Source pixels crossing the lon = 180 deg discontinuity of a full sky CAR target are ignored
+    let src = tan_image(&LonLat::new(PI, 0.0), (5.0, 5.5), 0.0, 1.0, (10, 10));
+    let img2proj = WcsImgXY2ProjXY::from_cr(180.5, 90.5, 0.0, -1.0, 1.0);
+    let target = BoundedImg2Celestial::new(Img2Celestial::new(img2proj, CenteredProjection::new(Car::new())), 360, 180, PixelConvention::Fits);
+    let (_, weights) = Resampler::default().reproject_exact(&data, &src, &target, false);
+    let total: f64 = weights.iter().sum();
+    assert!((85.0..95.0).contains(&total), "{}", total);
+    assert!((0..180).all(|j| weights[j * 360 + 180] == 0.0));
+  }
+}
@@ -0,0 +1,183 @@
+//! Module containing the rendering of a HEALPix skymap in any projection.
+//!
+//! For each output pixel, the pixel center (or a set of sub-pixel positions in case of
+//! supersampling) is transformed into the projection plane, deprojected, and the value of the
+//! HEALPix cell containing the deprojected position is looked up in the skymap.
+
+use crate::{ImgXY, Projection};
+use crate::healpix::{self, Scheme};
+use crate::img2proj::ImgXY2ProjXY;
+
+/// Renderer of HEALPix skymaps.
+pub struct SkymapRenderer {
+  supersampling: u8,
+  blank: f64,
+}
+
+impl Default for SkymapRenderer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl SkymapRenderer {
+
+  /// New renderer without supersampling, using `NaN` as blank value.
+  pub fn new() -> Self {
+    Self { supersampling: 1, blank: f64::NAN }
+  }
+
+  /// Set the number of sub-pixels along each axis of an output pixel (`1`, the default, means
+  /// no supersampling). The value of an output pixel is the mean of the values at its sub-pixel
+  /// centers, for antialiasing when the output pixels are larger than the skymap cells.
+  /// # Panics
+  /// * if `supersampling` is `0`
+  pub fn set_supersampling(&mut self, supersampling: u8) {
+    assert!(supersampling > 0);
+    self.supersampling = supersampling;
+  }
+
+  /// Set the value of the output pixels outside of the projection valid area.
+  pub fn set_blank(&mut self, blank: f64) {
+    self.blank = blank;
+  }
+
+  /// Get the number of sub-pixels along each axis of an output pixel
+  pub fn supersampling(&self) -> u8 {
+    self.supersampling
+  }
+
+  /// Get the value of the output pixels outside of the projection valid area
+  pub fn blank(&self) -> f64 {
+    self.blank
+  }
+
+  /// Renders the given skymap.
+  /// # Params
+  /// * `skymap`: the value of each HEALPix cell, of length `12 * nside^2`
+  /// * `scheme`: the numbering scheme of the skymap
+  /// * `proj`: the projection
+  /// * `img2proj`: transformation from the output image to the projection plane (e.g. `BasicImgXY2ProjXY`),
+  ///   the center of the first output pixel being at `(0, 0)`
+  /// * `img_size`: `(width, height)` number of pixels of the output image
+  /// # Return
+  /// * the values of the output image pixels, row by row
+  /// # Remark
+  /// * sub-pixels outside of the projection valid area, or falling in a `NaN` skymap cell, are
+  ///   ignored when computing the mean
+  /// # Panics
+  /// * if the length of `skymap` is not `12 * nside^2`, `nside` being a power of 2
+  pub fn render<P, W>(
+    &self,
+    skymap: &[f64],
+    scheme: Scheme,
+    proj: &P,
+    img2proj: &W,
+    img_size: (u32, u32)
+  ) -> Vec<f64>
+    where
+      P: Projection + ?Sized,
+      W: ImgXY2ProjXY
+  {
+    let depth = skymap_depth(skymap.len()).expect("Wrong skymap length");
+    let n = self.supersampling as usize;
+    let offsets: Vec<f64> = (0..n).map(|k| (k as f64 + 0.5) / n as f64 - 0.5).collect();
+    let (width, height) = img_size;
+    let mut values = Vec::with_capacity(width as usize * height as usize);
+    for j in 0..height {
+      for i in 0..width {
+        let (sum, count) = offsets.iter()
+          .flat_map(|dy| offsets.iter().map(move |dx| ImgXY::new(i as f64 + dx, j as f64 + dy)))
+          .filter_map(|img_pos| {
+            let proj_pos = img2proj.img2proj(&img_pos);
+            if proj.is_in_valid_proj_area(&proj_pos) {
+              proj.unproj_xyz(&proj_pos)
+            } else {
+              None
+            }
+          })
+          .map(|xyz| skymap[healpix::hash_with_scheme(depth, scheme, &xyz) as usize])
+          .filter(|v| !v.is_nan())
+          .fold((0.0, 0_u32), |(s, c), v| (s + v, c + 1));
+        values.push(if count > 0 { sum / count as f64 } else { self.blank });
+      }
+    }
+    values
+  }
+}
+
+/// Returns the depth of a skymap of given length, `None` if the length is not `12 * nside^2`
+/// with `nside` a power of 2.
+#[allow(clippy::manual_is_multiple_of)] // `is_multiple_of` requires Rust 1.87
+fn skymap_depth(len: usize) -> Option<u8> {
+  if len % 12 != 0 {
+    return None;
+  }
+  let n = (len / 12) as u64;
+  let nside = (n as f64).sqrt().round() as u64;
+  if nside * nside == n && nside <= u32::MAX as u64 {
+    healpix::depth_from_nside(nside as u32)
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f64::consts::PI;
+  use crate::{CenteredProjection, LonLat};
+  use crate::cylindrical::car::Car;
+  use crate::img2proj::BasicImgXY2ProjXY;
+  use crate::math::HALF_PI;
+  use crate::pseudocyl::mol::Mol;
+  use crate::zenithal::sin::Sin;
+
+  #[test]
+  fn test_render() {
+    // Skymap (depth 3) in which each cell value is its NESTED index
+    let depth = 3;
+    let nested: Vec<f64> = (0..healpix::n_hash(depth)).map(|h| h as f64).collect();
+    let mut ring = vec![0.0; nested.len()];
+    for (h, v) in nested.iter().enumerate() {
+      ring[healpix::to_ring(depth, h as u64) as usize] = *v;
+    }
+    let proj = Mol::new();
+    let img2proj = BasicImgXY2ProjXY::from((200, 100), (&(-2.9..=2.9), &(-1.45..=1.45)));
+    let mut renderer = SkymapRenderer::new();
+    let img_nested = renderer.render(&nested, Scheme::Nested, &proj, &img2proj, (200, 100));
+    let img_ring = renderer.render(&ring, Scheme::Ring, &proj, &img2proj, (200, 100));
+    assert_eq!(img_nested.len(), 200 * 100);
+    assert!(img_nested.iter().zip(img_ring.iter()).all(|(a, b)| a == b || (a.is_nan() && b.is_nan())));
+    // Corners are outside of the Mollweide ellipse
+    assert!(img_nested[0].is_nan());
+    // Value at the center
+    let lonlat = proj.unproj_lonlat(&img2proj.img2proj(&ImgXY::new(100.0, 50.0))).unwrap();
+    assert_eq!(img_nested[50 * 200 + 100], healpix::hash(depth, &lonlat) as f64);
+    // Supersampling of a constant map
+    renderer.set_supersampling(3);
+    let img = renderer.render(&vec![1.0; 12 * 16 * 16], Scheme::Ring, &proj, &img2proj, (200, 100));
+    assert_eq!(img[50 * 200 + 100], 1.0);
+    assert!(img[0].is_nan());
+  }
+
+  #[test]
+  fn test_render_poles() {
+    let depth = 3;
+    let nested: Vec<f64> = (0..healpix::n_hash(depth)).map(|h| h as f64).collect();
+    let renderer = SkymapRenderer::new();
+    // All-sky CAR grid of 1 deg pixels, centered on the poles and on the polar cap cuts
+    let s = 1_f64.to_radians();
+    let img2proj = BasicImgXY2ProjXY::from((361, 181), (&(-PI - 0.5 * s..=PI + 0.5 * s), &(-HALF_PI - 0.5 * s..=HALF_PI + 0.5 * s)));
+    let img = renderer.render(&nested, Scheme::Nested, &Car::new(), &img2proj, (361, 181));
+    assert_eq!(img.len(), 361 * 181);
+    assert_eq!(img[90 * 361 + 180], healpix::hash(depth, &LonLat::new(0.0, 0.0)) as f64);
+    // SIN view centered on the North pole
+    let mut proj = CenteredProjection::new(Sin::new());
+    proj.set_proj_center_from_lonlat(&LonLat::new(0.0, HALF_PI));
+    let img2proj = BasicImgXY2ProjXY::from((101, 101), (&(-0.5..=0.5), &(-0.5..=0.5)));
+    let img = renderer.render(&nested, Scheme::Ring, &proj, &img2proj, (101, 101));
+    assert!(img.iter().all(|v| !v.is_nan()));
+    assert_eq!(img[50 * 101 + 50], healpix::to_ring(depth, healpix::hash(depth, &LonLat::new(0.0, HALF_PI))) as f64);
+  }
+}
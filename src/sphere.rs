@@ -0,0 +1,517 @@
+//! Spherical geometry utilities working on positions on the unit sphere: angular distances,
+//! position angles and destination points, great-circle arcs and small circles, spherical
+//! polygons (containment test and area).
+//!
+//! All angles are in radians. Position angles are counted from the North toward the East.
+//! Polygons are made of great-circle arcs and are supposed to be smaller than a hemisphere.
+
+use std::f64::consts::{PI, TAU};
+
+use crate::{CustomFloat, XYZ};
+
+/// Returns the (non-normalized) cross product `a x b`.
+pub fn cross(a: &XYZ, b: &XYZ) -> [f64; 3] {
+  [
+    a.y * b.z - a.z * b.y,
+    a.z * b.x - a.x * b.z,
+    a.x * b.y - a.y * b.x,
+  ]
+}
+
+/// Returns the dot product of the given vector with the given position.
+pub fn dot(v: &[f64; 3], p: &XYZ) -> f64 {
+  v[0] * p.x + v[1] * p.y + v[2] * p.z
+}
+
+/// Returns the norm of the given vector.
+pub fn norm(v: &[f64; 3]) -> f64 {
+  (v[0].pow2() + v[1].pow2() + v[2].pow2()).sqrt()
+}
+
+/// Normalizes the given vector (`None` if the vector is null).
+pub fn normalize(v: [f64; 3]) -> Option<XYZ> {
+  let n = norm(&v);
+  if n > 0.0 && n.is_finite() {
+    Some(XYZ::new_renorming_if_necessary(v[0] / n, v[1] / n, v[2] / n))
+  } else {
+    None
+  }
+}
+
+/// Angular distance between two positions, numerically stable for all distances in `[0, pi]`.
+pub fn ang_dist(a: &XYZ, b: &XYZ) -> f64 {
+  norm(&cross(a, b)).atan2(a.scalar(b))
+}
+
+/// Returns the middle of the (shortest) great-circle arc joining the two given positions
+/// (`None` for antipodal positions).
+pub fn arc_middle(a: &XYZ, b: &XYZ) -> Option<XYZ> {
+  normalize([a.x + b.x, a.y + b.y, a.z + b.z])
+}
+
+/// Returns the sum of `(v_i x v_{i+1}) . ref` over the edges of the given closed polygon.
+/// The result is negative if the polygon is counter-clockwise when seen from the inside of the
+/// sphere (i.e. on a sky image with the North up and the East on the left), and
+/// positive if it is clockwise, `ref` being a point inside the polygon.
+pub(crate) fn orientation(vertices: &[XYZ], reference: &XYZ) -> f64 {
+  vertices.iter()
+    .zip(vertices.iter().cycle().skip(1))
+    .map(|(a, b)| dot(&cross(a, b), reference))
+    .sum()
+}
+
+/// Returns the smallest cone `(center, radius)` containing all the given positions.
+/// The algorithm (incremental version of Welzl's algorithm) assumes that the cone radius
+/// is smaller than `pi/2`; if not, a (non-minimal) cone centered on the mean position is returned.
+/// Returns `None` if the input is empty.
+/// # Remark
+/// * the positions are first shuffled (with a deterministic permutation) since the incremental
+///   algorithm is `O(n^3)` in the worst case, which is approached by ordered inputs like sampled
+///   boundaries; the expected cost is then `O(n)`.
+pub(crate) fn smallest_enclosing_cone(points: &[XYZ]) -> Option<(XYZ, f64)> {
+  const EPS: f64 = 1.0e-14;
+  let mut shuffled: Vec<&XYZ> = points.iter().collect();
+  deterministic_shuffle(&mut shuffled);
+  let points = &shuffled[..];
+  let first = points.first()?;
+  let mut center = (*first).clone();
+  let mut radius = 0.0_f64;
+  for (i, pi) in points.iter().enumerate() {
+    if ang_dist(&center, pi) <= radius + EPS {
+      continue;
+    }
+    center = (*pi).clone();
+    radius = 0.0;
+    for (j, pj) in points[..i].iter().enumerate() {
+      if ang_dist(&center, pj) <= radius + EPS {
+        continue;
+      }
+      match arc_middle(pi, pj) {
+        Some(c) => {
+          radius = ang_dist(&c, pi);
+          center = c;
+        },
+        None => return Some(mean_cone(points)),
+      }
+      for pk in points[..j].iter() {
+        if ang_dist(&center, pk) <= radius + EPS {
+          continue;
+        }
+        // Circumcenter of the three points (the pole of the plane containing them)
+        let ab = [pj.x - pi.x, pj.y - pi.y, pj.z - pi.z];
+        let ac = [pk.x - pi.x, pk.y - pi.y, pk.z - pi.z];
+        let n = [
+          ab[1] * ac[2] - ab[2] * ac[1],
+          ab[2] * ac[0] - ab[0] * ac[2],
+          ab[0] * ac[1] - ab[1] * ac[0],
+        ];
+        match normalize(n) {
+          Some(c) => {
+            let c = if c.scalar(pi) < 0.0 { XYZ::new(-c.x, -c.y, -c.z) } else { c };
+            radius = ang_dist(&c, pi);
+            center = c;
+          },
+          None => return Some(mean_cone(points)),
+        }
+      }
+    }
+  }
+  if radius < std::f64::consts::FRAC_PI_2 && points.iter().all(|p| ang_dist(&center, p) <= radius + 1.0e-12) {
+    Some((center, radius))
+  } else {
+    Some(mean_cone(points))
+  }
+}
+
+/// Shuffles the given slice (Fisher-Yates) using a fixed-seed pseudo-random generator
+/// (SplitMix64), so that the result is reproducible.
+fn deterministic_shuffle<T>(values: &mut [T]) {
+  let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+  for i in (1..values.len()).rev() {
+    state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    values.swap(i, (z % (i as u64 + 1)) as usize);
+  }
+}
+
+/// Cone centered on the (normalized) mean position and containing all given positions.
+fn mean_cone(points: &[&XYZ]) -> (XYZ, f64) {
+  let sum = points.iter().fold([0.0; 3], |s, p| [s[0] + p.x, s[1] + p.y, s[2] + p.z]);
+  let center = normalize(sum).unwrap_or_else(|| points[0].clone());
+  let radius = points.iter().map(|p| ang_dist(&center, p)).fold(0.0, f64::max);
+  (center, radius)
+}
+
+/// Returns the unit vectors pointing toward the East and toward the North at the given position
+/// (`None` at the poles, where the East direction is undefined).
+pub fn east_north(p: &XYZ) -> Option<(XYZ, XYZ)> {
+  let r = (p.x.pow2() + p.y.pow2()).sqrt();
+  if r > 0.0 {
+    let east = XYZ::new_renorming_if_necessary(-p.y / r, p.x / r, 0.0);
+    let north = XYZ::new_renorming_if_necessary(-p.z * p.x / r, -p.z * p.y / r, r);
+    Some((east, north))
+  } else {
+    None
+  }
+}
+
+/// Same as `east_north`, but returning at the poles the limit of the East and North vectors along
+/// the `lon = 0` meridian.
+pub fn tangent_frame(p: &XYZ) -> (XYZ, XYZ) {
+  east_north(p)
+    .unwrap_or_else(|| (XYZ::new(0.0, 1.0, 0.0), XYZ::new(-p.z.signum(), 0.0, 0.0)))
+}
+
+/// Returns the position angle of `to` seen from `from`, i.e. the initial bearing of the
+/// (shortest) great-circle arc going from `from` to `to`, in `[0, 2pi[`.
+/// # Remark
+/// * the final bearing, at `to`, is `(position_angle(to, from) + pi) % 2pi`
+pub fn position_angle(from: &XYZ, to: &XYZ) -> f64 {
+  let (east, north) = tangent_frame(from);
+  to.scalar(&east).atan2(to.scalar(&north)).rem_euclid(TAU)
+}
+
+/// Returns the position at the given angular distance from `from`, in the direction of the
+/// given position angle.
+pub fn destination(from: &XYZ, dist: f64, position_angle: f64) -> XYZ {
+  let (east, north) = tangent_frame(from);
+  let (sind, cosd) = dist.sin_cos();
+  let (sina, cosa) = position_angle.sin_cos();
+  let (cn, ce) = (sind * cosa, sind * sina);
+  XYZ::new_renorming_if_necessary(
+    cosd * from.x + cn * north.x + ce * east.x,
+    cosd * from.y + cn * north.y + ce * east.y,
+    cosd * from.z + cn * north.z + ce * east.z,
+  )
+}
+
+/// Returns the position at the given fraction `t` in `[0, 1]` of the (shortest) great-circle arc
+/// going from `a` to `b` (spherical linear interpolation).
+/// Returns `None` for (nearly) antipodal positions, the great-circle arc being then undefined.
+pub fn slerp(a: &XYZ, b: &XYZ, t: f64) -> Option<XYZ> {
+  let d = ang_dist(a, b);
+  if d < 1.0e-12 {
+    return Some(a.clone());
+  } else if PI - d < 1.0e-12 {
+    return None;
+  }
+  let s = d.sin();
+  let wa = ((1.0 - t) * d).sin() / s;
+  let wb = (t * d).sin() / s;
+  Some(XYZ::new_renorming_if_necessary(wa * a.x + wb * b.x, wa * a.y + wb * b.y, wa * a.z + wb * b.z))
+}
+
+/// Returns points along the great-circle arc going from `a` to `b` (`a` included, `b` excluded),
+/// consecutive points being separated by at most `step` radians.
+/// Returns `None` for (nearly) antipodal positions (see `slerp`).
+pub fn great_arc_points(a: &XYZ, b: &XYZ, step: f64) -> Option<Vec<XYZ>> {
+  let n = ((ang_dist(a, b) / step).ceil() as usize).max(1);
+  (0..n).map(|i| slerp(a, b, i as f64 / n as f64)).collect()
+}
+
+/// Returns `n` points regularly sampled along the small circle of given center and radius,
+/// starting North of the center and going toward the East (i.e. counter-clockwise when seen
+/// from the inside of the sphere).
+pub fn small_circle_points(center: &XYZ, radius: f64, n: usize) -> Vec<XYZ> {
+  let (east, north) = tangent_frame(center);
+  let (sinr, cosr) = radius.sin_cos();
+  (0..n)
+    .map(|i| {
+      let (sint, cost) = (TAU * i as f64 / n as f64).sin_cos();
+      let (cn, ce) = (cost * sinr, sint * sinr);
+      XYZ::new_renorming_if_necessary(
+        cosr * center.x + cn * north.x + ce * east.x,
+        cosr * center.y + cn * north.y + ce * east.y,
+        cosr * center.z + cn * north.z + ce * east.z,
+      )
+    })
+    .collect()
+}
+
+/// Returns `true` if `x`, supposedly on the great circle of normal `n = a x b`,
+/// is on the (shortest) arc going from `a` to `b`.
+fn is_on_arc(a: &XYZ, b: &XYZ, n: &[f64; 3], x: &XYZ) -> bool {
+  let ax = cross(a, x);
+  let xb = cross(x, b);
+  ax[0] * n[0] + ax[1] * n[1] + ax[2] * n[2] >= 0.0
+    && xb[0] * n[0] + xb[1] * n[1] + xb[2] * n[2] >= 0.0
+}
+
+/// Returns the intersection (if any) of the two great-circle arcs `ab` and `cd`.
+/// # Remark
+/// * arcs sharing an endpoint intersect at this endpoint;
+/// * arcs lying on a same great circle (even if overlapping) are considered as not intersecting.
+pub fn arc_intersection(a: &XYZ, b: &XYZ, c: &XYZ, d: &XYZ) -> Option<XYZ> {
+  if let Some(shared) = [a, b].into_iter().find(|p| *p == c || *p == d) {
+    return Some(shared.clone());
+  }
+  let n1 = cross(a, b);
+  let n2 = cross(c, d);
+  if dot(&n1, c) * dot(&n1, d) > 0.0 || dot(&n2, a) * dot(&n2, b) > 0.0 {
+    return None;
+  }
+  let i = [
+    n1[1] * n2[2] - n1[2] * n2[1],
+    n1[2] * n2[0] - n1[0] * n2[2],
+    n1[0] * n2[1] - n1[1] * n2[0],
+  ];
+  // (Nearly) same great circles
+  if norm(&i) <= 1.0e-15 * norm(&n1) * norm(&n2) {
+    return None;
+  }
+  let i = normalize(i)?;
+  let j = XYZ::new(-i.x, -i.y, -i.z);
+  [i, j].into_iter()
+    .find(|x| is_on_arc(a, b, &n1, x) && is_on_arc(c, d, &n2, x))
+}
+
+/// Returns `true` if the given point is inside the given polygon (made of great-circle arcs).
+/// The inside of the polygon is taken as the part of the sphere not containing
+/// `outside`, a point supposedly outside of the polygon.
+pub fn is_in_polygon(vertices: &[XYZ], outside: &XYZ, p: &XYZ) -> bool {
+  let count_crossings = |from: &XYZ, to: &XYZ| vertices.iter()
+    .zip(vertices.iter().cycle().skip(1))
+    .filter(|(a, b)| arc_intersection(a, b, from, to).is_some())
+    .count();
+  let n_crossings = if p.scalar(outside) > 0.0 {
+    count_crossings(p, outside)
+  } else {
+    // Avoid (nearly) antipodal arcs: go through a point at pi/2 from `outside`
+    let w = perpendicular_toward(outside, p);
+    count_crossings(p, &w) + count_crossings(&w, outside)
+  };
+  n_crossings % 2 == 1
+}
+
+/// Returns, among 4 unit vectors perpendicular to `v`, the one the closest to `p`.
+fn perpendicular_toward(v: &XYZ, p: &XYZ) -> XYZ {
+  let axis = if v.x.abs() < 0.5 { XYZ::new(1.0, 0.0, 0.0) } else { XYZ::new(0.0, 1.0, 0.0) };
+  let w1 = normalize(cross(v, &axis)).unwrap();
+  let w2 = normalize(cross(v, &w1)).unwrap();
+  [w1.clone(), XYZ::new(-w1.x, -w1.y, -w1.z), w2.clone(), XYZ::new(-w2.x, -w2.y, -w2.z)]
+    .into_iter()
+    .max_by(|a, b| a.scalar(p).total_cmp(&b.scalar(p)))
+    .unwrap()
+}
+
+/// Returns a point supposedly outside of the given polygon: the opposite of the mean of its
+/// vertices (which is valid as long as the polygon is smaller than a hemisphere).
+pub fn outside_point(vertices: &[XYZ]) -> Option<XYZ> {
+  let sum = vertices.iter().fold([0.0; 3], |s, p| [s[0] + p.x, s[1] + p.y, s[2] + p.z]);
+  normalize([-sum[0], -sum[1], -sum[2]])
+}
+
+/// Returns the area, in steradians, of the given polygon (the smallest of the two parts of the
+/// sphere it delimits), computed as the sum of the signed areas of the triangles of a fan.
+pub fn polygon_area(vertices: &[XYZ]) -> f64 {
+  let first = match vertices.first() {
+    Some(first) => first,
+    None => return 0.0,
+  };
+  let area = vertices[1..].windows(2)
+    .map(|w| {
+      let (b, c) = (&w[0], &w[1]);
+      // Van Oosterom & Strackee (1983) formula
+      let num = dot(&cross(b, c), first);
+      let den = 1.0 + first.scalar(b) + b.scalar(c) + c.scalar(first);
+      num.atan2(den).twice()
+    })
+    .sum::<f64>()
+    .abs();
+  area.min(4.0 * PI - area)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::LonLat;
+
+  fn xyz(lon_deg: f64, lat_deg: f64) -> XYZ {
+    LonLat::new(lon_deg.to_radians(), lat_deg.to_radians()).to_xyz()
+  }
+
+  #[test]
+  fn test_cross_dot_norm_normalize() {
+    let (a, b) = (xyz(0.0, 0.0), xyz(90.0, 0.0));
+    let n = cross(&a, &b);
+    assert!((norm(&n) - 1.0).abs() < 1e-15 && dot(&n, &xyz(0.0, 90.0)) > 1.0 - 1e-15);
+    assert!(dot(&n, &a).abs() < 1e-15);
+    let v = normalize([3.0, 0.0, 4.0]).unwrap();
+    assert!((v.x() - 0.6).abs() < 1e-15 && (v.z() - 0.8).abs() < 1e-15);
+    assert!(normalize([0.0; 3]).is_none());
+    assert!(normalize([f64::NAN, 0.0, 1.0]).is_none());
+  }
+
+  #[test]
+  fn test_ang_dist() {
+    // Stable for antipodes, unlike the haversine formula
+    assert!((ang_dist(&xyz(10.0, 20.0), &xyz(190.0, -20.0)) - PI).abs() < 1e-12);
+    assert_eq!(ang_dist(&xyz(10.0, 20.0), &xyz(10.0, 20.0)), 0.0);
+    assert!((ang_dist(&xyz(0.0, 0.0), &xyz(0.0, 1e-10)) - 1e-10_f64.to_radians()).abs() < 1e-24);
+    let (a, b) = (LonLat::new(0.1, 0.2), LonLat::new(0.1 + PI, -0.2));
+    assert!((a.ang_dist(&b) - PI).abs() < 1e-12);
+    assert!((a.ang_dist(&LonLat::new(0.1, 0.3)) - 0.1).abs() < 1e-15);
+  }
+
+  #[test]
+  fn test_arc_middle() {
+    let m = arc_middle(&xyz(10.0, 0.0), &xyz(30.0, 0.0)).unwrap();
+    assert!(ang_dist(&m, &xyz(20.0, 0.0)) < 1e-15);
+    assert!(arc_middle(&XYZ::new(1.0, 0.0, 0.0), &XYZ::new(-1.0, 0.0, 0.0)).is_none());
+  }
+
+  #[test]
+  fn test_orientation() {
+    let octant = [xyz(0.0, 0.0), xyz(90.0, 0.0), xyz(0.0, 90.0)];
+    let inside = xyz(30.0, 30.0);
+    assert!(orientation(&octant, &inside) > 0.0);
+    let reversed: Vec<XYZ> = octant.iter().rev().cloned().collect();
+    assert!(orientation(&reversed, &inside) < 0.0);
+  }
+
+  #[test]
+  fn test_smallest_enclosing_cone() {
+    assert!(smallest_enclosing_cone(&[]).is_none());
+    let (center, radius) = smallest_enclosing_cone(&[xyz(10.0, 20.0)]).unwrap();
+    assert!(ang_dist(&center, &xyz(10.0, 20.0)) < 1e-15 && radius == 0.0);
+    // Two points: the middle of the arc
+    let (center, radius) = smallest_enclosing_cone(&[xyz(0.0, 0.0), xyz(20.0, 0.0)]).unwrap();
+    assert!(ang_dist(&center, &xyz(10.0, 0.0)) < 1e-12 && (radius - 10_f64.to_radians()).abs() < 1e-12);
+    // Densely sampled (ordered) boundary
+    let c = xyz(40.0, -20.0);
+    let (center, radius) = smallest_enclosing_cone(&small_circle_points(&c, 0.3, 100_000)).unwrap();
+    assert!(ang_dist(&center, &c) < 1e-9 && (radius - 0.3).abs() < 1e-9);
+    // Larger than a hemisphere: non-minimal cone containing all the points
+    let points = [xyz(0.0, 0.0), xyz(120.0, 0.0), xyz(240.0, 0.0), xyz(0.0, 10.0)];
+    let (center, radius) = smallest_enclosing_cone(&points).unwrap();
+    assert!(points.iter().all(|p| ang_dist(&center, p) <= radius + 1e-12));
+  }
+
+  #[test]
+  fn test_east_north_and_tangent_frame() {
+    let (e, n) = east_north(&xyz(90.0, 0.0)).unwrap();
+    assert!(ang_dist(&e, &xyz(180.0, 0.0)) < 1e-15 && ang_dist(&n, &xyz(0.0, 90.0)) < 1e-15);
+    assert!(east_north(&XYZ::new(0.0, 0.0, 1.0)).is_none());
+    // At the poles: limit along the lon = 0 meridian
+    let (e, n) = tangent_frame(&XYZ::new(0.0, 0.0, 1.0));
+    assert!(ang_dist(&e, &xyz(90.0, 0.0)) < 1e-15 && ang_dist(&n, &xyz(180.0, 0.0)) < 1e-15);
+    let (e, n) = tangent_frame(&XYZ::new(0.0, 0.0, -1.0));
+    assert!(ang_dist(&e, &xyz(90.0, 0.0)) < 1e-15 && ang_dist(&n, &xyz(0.0, 0.0)) < 1e-15);
+  }
+
+  #[test]
+  fn test_position_angle() {
+    assert!((position_angle(&xyz(0.0, 0.0), &xyz(10.0, 0.0)) - 0.5 * PI).abs() < 1e-12);
+    assert!(position_angle(&xyz(0.0, 0.0), &xyz(0.0, 10.0)).abs() < 1e-12);
+    assert!((position_angle(&xyz(0.0, 0.0), &xyz(-10.0, 0.0)) - 1.5 * PI).abs() < 1e-12);
+    assert!((position_angle(&xyz(0.0, 10.0), &xyz(0.0, 0.0)) - PI).abs() < 1e-12);
+  }
+
+  #[test]
+  fn test_destination() {
+    let (from, to) = (xyz(30.0, 40.0), xyz(-50.0, 10.0));
+    let dest = destination(&from, ang_dist(&from, &to), position_angle(&from, &to));
+    assert!(ang_dist(&dest, &to) < 1e-12);
+    assert!(ang_dist(&destination(&from, 0.0, 1.0), &from) < 1e-15);
+    assert!(ang_dist(&destination(&xyz(0.0, 0.0), PI, 0.3), &xyz(180.0, 0.0)) < 1e-12);
+  }
+
+  #[test]
+  fn test_slerp() {
+    let (a, b) = (xyz(10.0, 20.0), xyz(-30.0, 50.0));
+    assert!(ang_dist(&slerp(&a, &b, 0.0).unwrap(), &a) < 1e-15);
+    assert!(ang_dist(&slerp(&a, &b, 1.0).unwrap(), &b) < 1e-15);
+    assert!(ang_dist(&slerp(&a, &b, 0.5).unwrap(), &arc_middle(&a, &b).unwrap()) < 1e-15);
+    let p = slerp(&a, &b, 0.25).unwrap();
+    assert!((ang_dist(&a, &p) - 0.25 * ang_dist(&a, &b)).abs() < 1e-15);
+    // Same positions
+    assert_eq!(slerp(&a, &a, 0.3), Some(a.clone()));
+    // (Nearly) antipodal positions
+    assert!(slerp(&a, &xyz(190.0, -20.0), 0.5).is_none());
+    assert!(slerp(&xyz(0.0, 0.0), &xyz(180.0, 1e-13_f64.to_degrees()), 0.5).is_none());
+    assert!(slerp(&xyz(0.0, 0.0), &xyz(180.0, 1e-9_f64.to_degrees()), 0.5).is_some());
+  }
+
+  #[test]
+  fn test_great_arc_points() {
+    let (a, b) = (xyz(0.0, 0.0), xyz(10.0, 0.0));
+    let points = great_arc_points(&a, &b, 1_f64.to_radians()).unwrap();
+    assert_eq!(points.len(), 10);
+    assert!(ang_dist(&points[0], &a) < 1e-15);
+    assert!(points.windows(2).all(|w| (ang_dist(&w[0], &w[1]) - 1_f64.to_radians()).abs() < 1e-12));
+    assert!((ang_dist(&points[9], &b) - 1_f64.to_radians()).abs() < 1e-12);
+    // Step larger than the arc, same positions
+    assert_eq!(great_arc_points(&a, &b, 1.0).unwrap().len(), 1);
+    assert_eq!(great_arc_points(&a, &a, 0.1), Some(vec![a.clone()]));
+    // Antipodal positions
+    assert!(great_arc_points(&a, &xyz(180.0, 0.0), 0.1).is_none());
+  }
+
+  #[test]
+  fn test_small_circle_points() {
+    let c = xyz(0.0, 90.0);
+    let points = small_circle_points(&c, 0.3, 12);
+    assert_eq!(points.len(), 12);
+    assert!(points.iter().all(|p| (ang_dist(&c, p) - 0.3).abs() < 1e-12));
+    // Starting North of the center, going toward the East
+    let c = xyz(0.0, 0.0);
+    let points = small_circle_points(&c, 0.3, 4);
+    assert!(position_angle(&c, &points[0]).abs() < 1e-12);
+    assert!((position_angle(&c, &points[1]) - 0.5 * PI).abs() < 1e-12);
+  }
+
+  #[test]
+  fn test_arc_intersection() {
+    let x = arc_intersection(&xyz(-10.0, 0.0), &xyz(10.0, 0.0), &xyz(0.0, -10.0), &xyz(0.0, 10.0)).unwrap();
+    assert!(ang_dist(&x, &xyz(0.0, 0.0)) < 1e-12);
+    // Same great circles, but not crossing arcs
+    assert!(arc_intersection(&xyz(-10.0, 0.0), &xyz(10.0, 0.0), &xyz(20.0, -10.0), &xyz(20.0, 10.0)).is_none());
+    assert!(arc_intersection(&xyz(-10.0, 0.0), &xyz(10.0, 0.0), &xyz(180.0, -10.0), &xyz(180.0, 10.0)).is_none());
+    // Shared endpoint
+    let x = arc_intersection(&xyz(-10.0, 0.0), &xyz(10.0, 0.0), &xyz(10.0, 0.0), &xyz(10.0, 10.0)).unwrap();
+    assert!(ang_dist(&x, &xyz(10.0, 0.0)) < 1e-12);
+    // Endpoint on the other arc
+    let x = arc_intersection(&xyz(-10.0, 0.0), &xyz(10.0, 0.0), &xyz(0.0, 0.0), &xyz(0.0, 10.0)).unwrap();
+    assert!(ang_dist(&x, &xyz(0.0, 0.0)) < 1e-12);
+    // Arcs on a same great circle, overlapping or not
+    assert!(arc_intersection(&xyz(-10.0, 0.0), &xyz(10.0, 0.0), &xyz(0.0, 0.0), &xyz(20.0, 0.0)).is_none());
+    assert!(arc_intersection(&xyz(-10.0, 0.0), &xyz(10.0, 0.0), &xyz(20.0, 0.0), &xyz(30.0, 0.0)).is_none());
+    let (a, b) = (xyz(10.0, 20.0), xyz(50.0, -30.0));
+    let (c, d) = (slerp(&a, &b, 0.25).unwrap(), slerp(&a, &b, 0.75).unwrap());
+    assert!(arc_intersection(&a, &b, &c, &d).is_none());
+  }
+
+  #[test]
+  fn test_is_in_polygon_and_outside_point() {
+    let octant = [xyz(0.0, 0.0), xyz(90.0, 0.0), xyz(0.0, 90.0)];
+    let outside = outside_point(&octant).unwrap();
+    assert!(ang_dist(&outside, &xyz(225.0, -35.264389682754654)) < 1e-12);
+    assert!(is_in_polygon(&octant, &outside, &xyz(30.0, 30.0)));
+    assert!(!is_in_polygon(&octant, &outside, &xyz(-30.0, 30.0)));
+    // Position (nearly) antipodal to the outside point
+    assert!(is_in_polygon(&octant, &outside, &xyz(45.0, 35.264389682754654)));
+    assert!(!is_in_polygon(&octant, &outside, &outside));
+    // Degenerated polygon
+    assert!(outside_point(&[XYZ::new(1.0, 0.0, 0.0), XYZ::new(-1.0, 0.0, 0.0)]).is_none());
+  }
+
+  #[test]
+  fn test_polygon_area() {
+    assert_eq!(polygon_area(&[]), 0.0);
+    let octant = [xyz(0.0, 0.0), xyz(90.0, 0.0), xyz(0.0, 90.0)];
+    assert!((polygon_area(&octant) - 0.5 * PI).abs() < 1e-12);
+    let reversed: Vec<XYZ> = octant.iter().rev().cloned().collect();
+    assert!((polygon_area(&reversed) - 0.5 * PI).abs() < 1e-12);
+    // Squares with vertices at lat = 1, 0 and -1 deg, i.e. close to a hemisphere.
+    // Area of the square of circumradius r: 4 E, with tan(E/2) = tan^2(r/2)
+    let square = |lat: f64| [xyz(0.0, lat), xyz(90.0, lat), xyz(180.0, lat), xyz(270.0, lat)];
+    let expected = 4.0 * (44.5_f64.to_radians().tan().powi(2)).atan().twice();
+    assert!((polygon_area(&square(1.0)) - expected).abs() < 1e-12);
+    assert!((polygon_area(&square(0.0)) - 2.0 * PI).abs() < 1e-12);
+    // The smallest part of the sphere, i.e. the complement of the square at lat = -1 deg
+    assert!((polygon_area(&square(-1.0)) - expected).abs() < 1e-12);
+  }
+}
@@ -0,0 +1,251 @@
+//! Module containing the serialization of regions (e.g. image footprints) in
+//! [STC-S](https://www.ivoa.net/documents/STC-S/), as used in e.g. the ObsCore `s_region` column,
+//! and the parsing of the STC-S `CIRCLE`, `ELLIPSE`, `POLYGON`, `BOX` and `UNION` shapes.
+//!
+//! In STC-S, positions and sizes are in degrees.
+
+use crate::{Cone, LonLat};
+use crate::bounded::BoundedImg2Celestial;
+use crate::img2celestial::ImgXY2Celestial;
+use crate::region::{SkyRegion, SphericalBox, SphericalEllipse, SphericalPolygon};
+
+/// Returns the STC-S string of the given region.
+/// # Params
+/// * `region`: the region to be serialized
+/// * `frame`: the STC-S coordinate frame, e.g. `ICRS`
+/// * `n_decimals`: number of decimals of the values (in degrees)
+/// # Remark
+/// * the position angle of a box is lost since it is not supported by STC-S: rotated boxes are
+///   serialized as polygons.
+pub fn to_stcs(region: &SkyRegion, frame: &str, n_decimals: usize) -> String {
+  let mut s = String::new();
+  append_stcs(&mut s, region, Some(frame), n_decimals);
+  s
+}
+
+/// Returns the STC-S `POLYGON` of the footprint of the given image.
+/// # Params
+/// * `img`: the image
+/// * `frame`: the STC-S coordinate frame, e.g. `ICRS`
+/// * `tol`: footprint tolerance, in pixels (see `BoundedImg2Celestial::footprint`)
+/// * `n_decimals`: number of decimals of the values (in degrees)
+/// # Return
+/// * `None` if the footprint of the image can't be computed
+pub fn footprint_to_stcs<T: ImgXY2Celestial>(
+  img: &BoundedImg2Celestial<T>,
+  frame: &str,
+  tol: f64,
+  n_decimals: usize
+) -> Option<String> {
+  img.footprint(tol)
+    .map(|vertices| to_stcs(&SkyRegion::Polygon(SphericalPolygon::new(vertices)), frame, n_decimals))
+}
+
+fn append_stcs(s: &mut String, region: &SkyRegion, frame: Option<&str>, n_decimals: usize) {
+  let push_shape = |s: &mut String, name: &str| {
+    s.push_str(name);
+    if let Some(frame) = frame {
+      s.push(' ');
+      s.push_str(frame);
+    }
+  };
+  let push_val = |s: &mut String, v: f64| {
+    s.push_str(&format!(" {:.*}", n_decimals, v));
+  };
+  let push_lonlat = |s: &mut String, lonlat: &LonLat| {
+    let lon = lonlat.lon().to_degrees().rem_euclid(360.0);
+    push_val(s, lon);
+    push_val(s, lonlat.lat().to_degrees());
+  };
+  match region {
+    SkyRegion::Cone(cone) => {
+      push_shape(s, "CIRCLE");
+      push_lonlat(s, cone.center());
+      push_val(s, cone.radius().to_degrees());
+    },
+    SkyRegion::Ellipse(ellipse) => {
+      push_shape(s, "ELLIPSE");
+      push_lonlat(s, ellipse.center());
+      push_val(s, ellipse.a().to_degrees());
+      push_val(s, ellipse.b().to_degrees());
+      push_val(s, ellipse.angle().to_degrees());
+    },
+    SkyRegion::Polygon(polygon) => {
+      push_shape(s, "POLYGON");
+      for v in polygon.vertices() {
+        push_lonlat(s, v);
+      }
+    },
+    SkyRegion::Box(sbox) if sbox.angle() == 0.0 => {
+      push_shape(s, "BOX");
+      push_lonlat(s, sbox.center());
+      push_val(s, sbox.width().to_degrees());
+      push_val(s, sbox.height().to_degrees());
+    },
+    SkyRegion::Box(sbox) => append_stcs(s, &SkyRegion::Polygon(sbox.to_polygon()), frame, n_decimals),
+    SkyRegion::Union(regions) => {
+      push_shape(s, "UNION");
+      s.push_str(" (");
+      for r in regions {
+        s.push(' ');
+        append_stcs(s, r, None, n_decimals);
+      }
+      s.push_str(" )");
+    },
+  }
+}
+
+/// Parses an STC-S string containing a `CIRCLE`, an `ELLIPSE`, a `POLYGON`, a `BOX` or a `UNION` of those shapes.
+/// Coordinate frame, reference position and coordinate flavor tokens (e.g. `ICRS TOPOCENTER`)
+/// are accepted but ignored except the frame, which is returned.
+/// # Return
+/// * `(frame, region)`: the frame is `None` if not given in the input string
+pub fn parse_stcs(stcs: &str) -> Result<(Option<String>, SkyRegion), String> {
+  let spaced = stcs.replace('(', " ( ").replace(')', " ) ");
+  let tokens: Vec<&str> = spaced.split_whitespace().collect();
+  let mut parser = Parser { tokens, pos: 0 };
+  let (frame, region) = parser.parse_shape()?;
+  match parser.next() {
+    None => Ok((frame, region)),
+    Some(token) => Err(format!("Unexpected token '{}' at the end of the STC-S string", token)),
+  }
+}
+
+struct Parser<'a> {
+  tokens: Vec<&'a str>,
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+
+  fn next(&mut self) -> Option<&'a str> {
+    let token = self.tokens.get(self.pos).copied();
+    self.pos += 1;
+    token
+  }
+
+  fn peek(&self) -> Option<&'a str> {
+    self.tokens.get(self.pos).copied()
+  }
+
+  /// Skips the frame, reference position and flavor tokens, returning the first of them.
+  fn parse_frame(&mut self) -> Option<String> {
+    let mut frame = None;
+    while let Some(token) = self.peek() {
+      if token == "(" || token == ")" || token.parse::<f64>().is_ok() || is_shape(token) {
+        break;
+      }
+      frame.get_or_insert_with(|| token.to_string());
+      self.pos += 1;
+    }
+    frame
+  }
+
+  fn parse_values(&mut self) -> Result<Vec<f64>, String> {
+    let mut values = Vec::new();
+    while let Some(v) = self.peek().and_then(|token| token.parse::<f64>().ok()) {
+      if !v.is_finite() {
+        return Err(format!("Non finite value: {}", v));
+      }
+      values.push(v);
+      self.pos += 1;
+    }
+    // Skip an optional unit, only degrees being supported
+    if let Some(token) = self.peek() {
+      if token.eq_ignore_ascii_case("unit") {
+        self.pos += 1;
+        match self.next() {
+          Some(unit) if unit.eq_ignore_ascii_case("deg") => {},
+          unit => return Err(format!("Unsupported unit: {:?}", unit)),
+        }
+      }
+    }
+    Ok(values)
+  }
+
+  fn parse_shape(&mut self) -> Result<(Option<String>, SkyRegion), String> {
+    let shape = self.next().ok_or_else(|| String::from("Empty STC-S string"))?;
+    let frame = self.parse_frame();
+    let region = match shape.to_uppercase().as_str() {
+      "CIRCLE" => match self.parse_values()?.as_slice() {
+        [lon, lat, r] if (0.0..=180.0).contains(r) =>
+          SkyRegion::Cone(Cone::new(lonlat(*lon, *lat)?, r.to_radians())),
+        values => return Err(format!("Wrong CIRCLE parameters: {:?}", values)),
+      },
+      "ELLIPSE" => match self.parse_values()?.as_slice() {
+        [lon, lat, a, b, pa] if 0.0 < *b && b <= a && *a < 90.0 =>
+          SkyRegion::Ellipse(SphericalEllipse::new(lonlat(*lon, *lat)?, a.to_radians(), b.to_radians(), pa.to_radians())),
+        values => return Err(format!("Wrong ELLIPSE parameters: {:?}", values)),
+      },
+      "POLYGON" => {
+        let values = self.parse_values()?;
+        if values.len() < 6 || values.len() % 2 != 0 {
+          return Err(format!("Wrong POLYGON parameters: {:?}", values));
+        }
+        let vertices = values.chunks(2)
+          .map(|lonlat_deg| lonlat(lonlat_deg[0], lonlat_deg[1]))
+          .collect::<Result<Vec<LonLat>, String>>()?;
+        SkyRegion::Polygon(SphericalPolygon::new(vertices))
+      },
+      "BOX" => match self.parse_values()?.as_slice() {
+        [lon, lat, w, h] if 0.0 < *w && *w < 180.0 && 0.0 < *h && *h < 180.0 =>
+          SkyRegion::Box(SphericalBox::new(lonlat(*lon, *lat)?, w.to_radians(), h.to_radians(), 0.0)),
+        values => return Err(format!("Wrong BOX parameters: {:?}", values)),
+      },
+      "UNION" => {
+        if self.next() != Some("(") {
+          return Err(String::from("Missing '(' after UNION"));
+        }
+        let mut regions = Vec::new();
+        while self.peek() != Some(")") {
+          if self.peek().is_none() {
+            return Err(String::from("Missing ')' at the end of UNION"));
+          }
+          regions.push(self.parse_shape()?.1);
+        }
+        self.pos += 1;
+        if regions.len() < 2 {
+          return Err(String::from("UNION must contains at least 2 regions"));
+        }
+        SkyRegion::Union(regions)
+      },
+      _ => return Err(format!("Unsupported STC-S shape: '{}'", shape)),
+    };
+    Ok((frame, region))
+  }
+}
+
+fn is_shape(token: &str) -> bool {
+  matches!(
+    token.to_uppercase().as_str(),
+    "CIRCLE" | "POLYGON" | "BOX" | "UNION" | "INTERSECTION" | "NOT" | "POSITION" | "ELLIPSE"
+  )
+}
+
+/// Creates a `LonLat` from a longitude and a latitude in degrees.
+fn lonlat(lon_deg: f64, lat_deg: f64) -> Result<LonLat, String> {
+  if (-90.0..=90.0).contains(&lat_deg) {
+    Ok(LonLat::new(lon_deg.rem_euclid(360.0).to_radians(), lat_deg.to_radians()))
+  } else {
+    Err(format!("Latitude out of [-90, 90]: {}", lat_deg))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_stcs_round_trip() {
+    let s = "UNION ICRS ( CIRCLE 10.0000 20.0000 1.5000 ELLIPSE 10.0000 20.0000 1.5000 0.5000 30.0000 POLYGON 1.0000 2.0000 3.0000 2.0000 2.0000 4.0000 BOX 359.0000 -10.0000 2.0000 1.0000 )";
+    let (frame, region) = parse_stcs(s).unwrap();
+    assert_eq!(frame.as_deref(), Some("ICRS"));
+    assert_eq!(to_stcs(&region, "ICRS", 4), s);
+
+    let (frame, region) = parse_stcs("Circle ICRS TOPOCENTER 10 20 1 unit deg").unwrap();
+    assert_eq!(frame.as_deref(), Some("ICRS"));
+    assert!(region.contains(&LonLat::new(10.5_f64.to_radians(), 20_f64.to_radians())));
+    assert!(parse_stcs("POLYGON ICRS 1 2 3").is_err());
+    assert!(parse_stcs("INTERSECTION ICRS ( CIRCLE 1 2 3 CIRCLE 1 2 1 )").is_err());
+  }
+}
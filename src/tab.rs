@@ -0,0 +1,480 @@
+//! Implementation of the `-TAB` (tabular coordinates) algorithm, see section 6 of
+//! "Representations of spectral coordinates in FITS" by E. W. Greisen et al. (2006),
+//! a.k.a. WCS Paper III.
+//!
+//! The coordinate array and the (optional) index vectors are usually stored in a binary table
+//! (see the `PSi_0a`, `PVi_1a`, `PVi_2a` and `PVi_3a` keywords): this library does not read
+//! FITS files, so they have to be provided as in-memory slices.
+
+use crate::{CustomFloat, ImgXY, LonLat, XYZ};
+use crate::img2celestial::ImgXY2Celestial;
+use crate::img2proj::{ImgXY2ProjXY, ProjXY2ImgXY};
+use crate::ndim::WcsAxis;
+use crate::ProjXY;
+
+/// Max number of iteration of the Newton-Raphson method used in the searched inversion.
+const N_ITER: usize = 30;
+/// Precision, in the coordinate array units, of the searched inversion.
+const EPS: f64 = 1.0e-12;
+
+/// Multi-dimensional coordinate lookup table (`-TAB` algorithm).
+///
+/// Given `M` intermediate world coordinates `psi_m`:
+/// * each `psi_m` is converted into an index `Upsilon_m` (1-based) using its index vector (if any),
+/// * the `M` world coordinates are then computed by multi-linear interpolation of the
+///   coordinate array at `(Upsilon_1, ..., Upsilon_M)`.
+///
+/// As allowed by the standard, the table is linearly extrapolated by half a cell beyond its
+/// first and last elements, i.e. `Upsilon_m` must be in `[0.5, K_m + 0.5]`.
+#[derive(Debug, Clone)]
+pub struct Tab {
+  /// Number of elements `K_m` along each of the `M` axes of the coordinate array.
+  dims: Box<[usize]>,
+  /// Coordinate array, of size `M x K_1 x ... x K_M`, the `M` coordinates varying first.
+  coords: Box<[f64]>,
+  /// Index vectors (`None` if `Upsilon_m = psi_m`).
+  indexes: Box<[Option<Box<[f64]>>]>,
+}
+
+impl Tab {
+
+  /// # Params
+  /// * `dims`: number of elements `(K_1, ..., K_M)` along each axis of the coordinate array
+  /// * `coords`: coordinate array of size `M x K_1 x ... x K_M`, in the FITS order
+  ///   (i.e. the `M` coordinates of a same element are contiguous, then `K_1` varies first)
+  /// * `indexes`: the `M` index vectors, `None` if there is no index vector for the axis
+  /// # Return
+  /// * `None` if the sizes are not consistent or if an index vector is not monotonic
+  pub fn new(dims: &[usize], coords: &[f64], indexes: &[Option<&[f64]>]) -> Option<Self> {
+    let m = dims.len();
+    if m == 0 || indexes.len() != m || dims.contains(&0)
+      || coords.len() != m * dims.iter().product::<usize>() {
+      return None;
+    }
+    for (k, index) in dims.iter().zip(indexes.iter()) {
+      if let Some(index) = index {
+        if index.len() != *k || !is_strictly_monotonic(index) {
+          return None;
+        }
+      }
+    }
+    Some(Self {
+      dims: dims.into(),
+      coords: coords.into(),
+      indexes: indexes.iter().map(|index| index.map(|v| v.into())).collect(),
+    })
+  }
+
+  /// Returns the number `M` of coordinates.
+  pub fn n_coords(&self) -> usize {
+    self.dims.len()
+  }
+
+  /// Transforms the `M` intermediate world coordinates into the `M` world coordinates.
+  /// # Params
+  /// * `psi`: the `M` intermediate world coordinates (`x_m + CRVAL_m`)
+  /// # Panics
+  /// * if `psi` does not contains `M` values
+  pub fn psi2world(&self, psi: &[f64]) -> Option<Vec<f64>> {
+    assert_eq!(psi.len(), self.n_coords());
+    let upsilon = psi.iter().enumerate()
+      .map(|(m, p)| self.psi2upsilon(m, *p))
+      .collect::<Option<Vec<f64>>>()?;
+    Some(self.interpolate(&upsilon))
+  }
+
+  /// Transforms the `M` world coordinates into the `M` intermediate world coordinates,
+  /// by searching (through the whole table) the first cell containing the given values.
+  /// # Params
+  /// * `world`: the `M` world coordinates
+  /// # Panics
+  /// * if `world` does not contains `M` values
+  pub fn world2psi(&self, world: &[f64]) -> Option<Vec<f64>> {
+    assert_eq!(world.len(), self.n_coords());
+    let upsilon = self.search(world)?;
+    upsilon.iter().enumerate()
+      .map(|(m, u)| self.upsilon2psi(m, *u))
+      .collect()
+  }
+
+  /// Converts an intermediate world coordinate into a (1-based) index in the coordinate array.
+  fn psi2upsilon(&self, m: usize, psi: f64) -> Option<f64> {
+    let k = self.dims[m];
+    let upsilon = match &self.indexes[m] {
+      None => psi,
+      Some(index) if k == 1 => if psi == index[0] { 1.0 } else { return None },
+      Some(index) => {
+        // Index of the first element of the segment containing psi
+        let increasing = index[1] > index[0];
+        let i = index.partition_point(|v| if increasing { *v <= psi } else { *v >= psi });
+        let i = i.clamp(1, k - 1) - 1;
+        (i + 1) as f64 + (psi - index[i]) / (index[i + 1] - index[i])
+      },
+    };
+    if (0.5..=k as f64 + 0.5).contains(&upsilon) {
+      Some(upsilon)
+    } else {
+      None
+    }
+  }
+
+  /// Converts a (1-based) index in the coordinate array into an intermediate world coordinate.
+  fn upsilon2psi(&self, m: usize, upsilon: f64) -> Option<f64> {
+    match &self.indexes[m] {
+      None => Some(upsilon),
+      Some(index) if index.len() == 1 => Some(index[0]),
+      Some(index) => {
+        let (i, t) = cell_and_offset(upsilon, index.len());
+        Some(index[i] + t * (index[i + 1] - index[i]))
+      },
+    }
+  }
+
+  /// Multi-linear interpolation of the coordinate array at the given (1-based) indices.
+  fn interpolate(&self, upsilon: &[f64]) -> Vec<f64> {
+    let (cells, offsets): (Vec<usize>, Vec<f64>) = upsilon.iter().zip(self.dims.iter())
+      .map(|(u, k)| cell_and_offset(*u, *k))
+      .unzip();
+    self.interpolate_in_cell(&cells, &offsets)
+  }
+
+  /// Multi-linear interpolation inside the cell of given lower corner,
+  /// at the given offsets (usually in `[0, 1]`) from the lower corner.
+  fn interpolate_in_cell(&self, cells: &[usize], offsets: &[f64]) -> Vec<f64> {
+    let m = self.n_coords();
+    let mut res = vec![0.0; m];
+    for corner in 0..(1_usize << m) {
+      let mut w = 1.0;
+      let mut idx = 0;
+      let mut stride = m;
+      for (j, ((c, t), k)) in cells.iter().zip(offsets.iter()).zip(self.dims.iter()).enumerate() {
+        let (i, wj) = if *k == 1 {
+          (0, if corner & (1 << j) == 0 { 1.0 } else { 0.0 })
+        } else if corner & (1 << j) == 0 {
+          (*c, 1.0 - t)
+        } else {
+          (c + 1, *t)
+        };
+        w *= wj;
+        idx += i * stride;
+        stride *= k;
+      }
+      if w != 0.0 {
+        for (r, v) in res.iter_mut().zip(&self.coords[idx..idx + m]) {
+          *r += w * v;
+        }
+      }
+    }
+    res
+  }
+
+  /// Derivatives of the multi-linear interpolation with respect to each offset.
+  /// Returns a `M x M` row-major matrix, `d[i * M + j]` being `d world_i / d offset_j`.
+  fn jacobian_in_cell(&self, cells: &[usize], offsets: &[f64]) -> Vec<f64> {
+    let m = self.n_coords();
+    let mut jac = vec![0.0; m * m];
+    let mut t = offsets.to_vec();
+    for j in 0..m {
+      // Multi-linear => linear along each axis, the derivative is a simple difference
+      t[j] = 1.0;
+      let v1 = self.interpolate_in_cell(cells, &t);
+      t[j] = 0.0;
+      let v0 = self.interpolate_in_cell(cells, &t);
+      t[j] = offsets[j];
+      for i in 0..m {
+        jac[i * m + j] = v1[i] - v0[i];
+      }
+    }
+    jac
+  }
+
+  /// Searches for the first cell containing the given world coordinates and returns the
+  /// associated (1-based) indices.
+  fn search(&self, world: &[f64]) -> Option<Vec<f64>> {
+    let m = self.n_coords();
+    let n_cells: Vec<usize> = self.dims.iter().map(|k| (*k).max(2) - 1).collect();
+    let tot: usize = n_cells.iter().product();
+    let mut cells = vec![0_usize; m];
+    for mut c in 0..tot {
+      for (cell, n) in cells.iter_mut().zip(n_cells.iter()) {
+        *cell = c % n;
+        c /= n;
+      }
+      // Allowed offset range, extrapolating by half a cell on the table borders
+      let ranges: Vec<(f64, f64)> = cells.iter().zip(self.dims.iter())
+        .map(|(c, k)| {
+          if *k == 1 {
+            (-0.5, 0.5)
+          } else {
+            (if *c == 0 { -0.5 } else { 0.0 }, if *c + 2 == *k { 1.5 } else { 1.0 })
+          }
+        }).collect();
+      if !self.may_contain(&cells, &ranges, world) {
+        continue;
+      }
+      if let Some(t) = self.newton_in_cell(&cells, &ranges, world) {
+        return Some(
+          cells.iter().zip(t.iter()).zip(self.dims.iter())
+            .map(|((c, t), k)| if *k == 1 { 1.0 + t } else { (c + 1) as f64 + t })
+            .collect()
+        );
+      }
+    }
+    None
+  }
+
+  /// Quick rejection test: is the given world position in the bounding box of the
+  /// values at the corners of the (possibly extrapolated) cell?
+  fn may_contain(&self, cells: &[usize], ranges: &[(f64, f64)], world: &[f64]) -> bool {
+    let m = self.n_coords();
+    let mut min = vec![f64::INFINITY; m];
+    let mut max = vec![f64::NEG_INFINITY; m];
+    let mut t = vec![0.0; m];
+    for corner in 0..(1_usize << m) {
+      for (j, (tj, (a, b))) in t.iter_mut().zip(ranges.iter()).enumerate() {
+        *tj = if corner & (1 << j) == 0 { *a } else { *b };
+      }
+      let v = self.interpolate_in_cell(cells, &t);
+      for i in 0..m {
+        min[i] = min[i].min(v[i]);
+        max[i] = max[i].max(v[i]);
+      }
+    }
+    world.iter().zip(min.iter().zip(max.iter()))
+      .all(|(w, (a, b))| {
+        let eps = 1.0e-9 * (b - a).abs().max(1.0);
+        (a - eps..=b + eps).contains(w)
+      })
+  }
+
+  /// Solves `interpolate_in_cell(cells, t) = world` for `t` using the Newton-Raphson method.
+  fn newton_in_cell(&self, cells: &[usize], ranges: &[(f64, f64)], world: &[f64]) -> Option<Vec<f64>> {
+    let m = self.n_coords();
+    let mut t: Vec<f64> = ranges.iter().map(|(a, b)| (a + b).half()).collect();
+    let scale = world.iter().fold(1.0_f64, |acc, w| acc.max(w.abs()));
+    for _ in 0..N_ITER {
+      let v = self.interpolate_in_cell(cells, &t);
+      let mut f: Vec<f64> = v.iter().zip(world.iter()).map(|(v, w)| v - w).collect();
+      if f.iter().all(|f| f.abs() <= EPS * scale) {
+        const TOL: f64 = 1.0e-9;
+        return if t.iter().zip(ranges.iter()).all(|(t, (a, b))| (a - TOL..=b + TOL).contains(t)) {
+          Some(t)
+        } else {
+          None
+        };
+      }
+      let mut jac = self.jacobian_in_cell(cells, &t);
+      if !solve_linear(&mut jac, &mut f, m) {
+        return None;
+      }
+      for (t, dt) in t.iter_mut().zip(f.iter()) {
+        *t -= dt;
+      }
+    }
+    None
+  }
+}
+
+/// Returns the 0-based index of the lower element of the segment of the table to be used to
+/// interpolate/extrapolate at the given 1-based index, and the offset from this element.
+fn cell_and_offset(upsilon: f64, k: usize) -> (usize, f64) {
+  if k == 1 {
+    (0, 0.0)
+  } else {
+    let u = upsilon - 1.0;
+    let i = (u.floor().max(0.0) as usize).min(k - 2);
+    (i, u - i as f64)
+  }
+}
+
+fn is_strictly_monotonic(v: &[f64]) -> bool {
+  v.windows(2).all(|w| w[0] < w[1]) || v.windows(2).all(|w| w[0] > w[1])
+}
+
+/// Solves `A x = b` by Gaussian elimination with partial pivoting, with `A` a `n x n` row-major
+/// matrix. The result is stored in `b`. Returns `false` if the matrix is singular.
+fn solve_linear(a: &mut [f64], b: &mut [f64], n: usize) -> bool {
+  for col in 0..n {
+    let pivot = (col..n)
+      .max_by(|i, j| a[i * n + col].abs().total_cmp(&a[j * n + col].abs()))
+      .unwrap();
+    if a[pivot * n + col] == 0.0 {
+      return false;
+    }
+    if pivot != col {
+      for j in 0..n {
+        a.swap(pivot * n + j, col * n + j);
+      }
+      b.swap(pivot, col);
+    }
+    for row in col + 1..n {
+      let f = a[row * n + col] / a[col * n + col];
+      for j in col..n {
+        a[row * n + j] -= f * a[col * n + j];
+      }
+      b[row] -= f * b[col];
+    }
+  }
+  for row in (0..n).rev() {
+    let mut s = b[row];
+    for j in row + 1..n {
+      s -= a[row * n + j] * b[j];
+    }
+    b[row] = s / a[row * n + row];
+  }
+  true
+}
+
+/// Standalone tabular axis (e.g. a spectral axis), with a 1-dimensional coordinate array:
+/// `world = Tab(CRVAL + CDELT * (pix - CRPIX))`.
+#[derive(Debug, Clone)]
+pub struct TabAxis {
+  /// Value of the `CRPIXi` keyword.
+  crpix: f64,
+  /// Value of the `CDELTi` keyword.
+  cdelt: f64,
+  /// Value of the `CRVALi` keyword.
+  crval: f64,
+  /// The 1-dimensional table.
+  tab: Tab,
+}
+
+impl TabAxis {
+
+  /// # Params
+  /// * `crpix`: value of the `CRPIXi` keyword
+  /// * `cdelt`: value of the `CDELTi` keyword (use 1 as default value)
+  /// * `crval`: value of the `CRVALi` keyword (use 0 as default value)
+  /// * `coords`: the coordinate vector
+  /// * `index`: the index vector, if any
+  /// # Return
+  /// * `None` if `cdelt` equals 0 or if the index vector is not valid
+  pub fn new(crpix: f64, cdelt: f64, crval: f64, coords: &[f64], index: Option<&[f64]>) -> Option<Self> {
+    if cdelt == 0.0 || !cdelt.is_finite() {
+      return None;
+    }
+    Tab::new(&[coords.len()], coords, &[index]).map(|tab| Self { crpix, cdelt, crval, tab })
+  }
+}
+
+impl WcsAxis for TabAxis {
+
+  fn pix2world(&self, pix: f64) -> Option<f64> {
+    self.tab.psi2world(&[self.crval + self.cdelt * (pix - self.crpix)]).map(|w| w[0])
+  }
+
+  fn world2pix(&self, world: f64) -> Option<f64> {
+    self.tab.world2psi(&[world]).map(|psi| self.crpix + (psi[0] - self.crval) / self.cdelt)
+  }
+}
+
+/// Celestial pair of tabular axes (e.g. `RA---TAB` and `DEC--TAB`).
+/// The first coordinate of the (2-dimensional) coordinate array is the longitude, the second one
+/// is the latitude, both in degrees.
+pub struct TabImg2Celestial<W: ImgXY2ProjXY> {
+  /// Linear transformation providing the intermediate world coordinates `x_m` (in radians).
+  img2proj: W,
+  /// Inverse of the linear transformation.
+  proj2img: W::T,
+  /// Value of the `CRVALi` keyword of the longitude axis, in degrees.
+  crval1: f64,
+  /// Value of the `CRVALi` keyword of the latitude axis, in degrees.
+  crval2: f64,
+  /// The 2-dimensional table.
+  tab: Tab,
+}
+
+impl<W: ImgXY2ProjXY> TabImg2Celestial<W> {
+
+  /// # Params
+  /// * `img2proj`: linear transformation (built from `CRPIXi`, `CDi_j` or `PCi_j` + `CDELTi`)
+  /// * `crval1`: value of the `CRVALi` keyword of the longitude axis, in degrees (usually 0)
+  /// * `crval2`: value of the `CRVALi` keyword of the latitude axis, in degrees (usually 0)
+  /// * `tab`: the 2-dimensional table, providing longitudes and latitudes in degrees
+  /// # Return
+  /// * `None` if the table is not 2-dimensional
+  pub fn new(img2proj: W, crval1: f64, crval2: f64, tab: Tab) -> Option<Self> {
+    if tab.n_coords() != 2 {
+      return None;
+    }
+    let proj2img = img2proj.inverse();
+    Some(Self { img2proj, proj2img, crval1, crval2, tab })
+  }
+}
+
+impl<W: ImgXY2ProjXY> ImgXY2Celestial for TabImg2Celestial<W> {
+
+  fn img2xyz(&self, img_pos: &ImgXY) -> Option<XYZ> {
+    self.img2lonlat(img_pos).map(|lonlat| lonlat.to_xyz())
+  }
+
+  fn xyz2img(&self, xyz: &XYZ) -> Option<ImgXY> {
+    self.lonlat2img(&xyz.to_lonlat())
+  }
+
+  fn img2lonlat(&self, img_pos: &ImgXY) -> Option<LonLat> {
+    let xy = self.img2proj.img2proj(img_pos);
+    let psi = [self.crval1 + xy.x().to_degrees(), self.crval2 + xy.y().to_degrees()];
+    self.tab.psi2world(&psi).map(|w| LonLat::new(w[0].to_radians().rem_euclid(std::f64::consts::TAU), w[1].to_radians()))
+  }
+
+  fn lonlat2img(&self, lonlat: &LonLat) -> Option<ImgXY> {
+    let lon = lonlat.lon().to_degrees();
+    let lat = lonlat.lat().to_degrees();
+    // The table longitudes may be in [-180, 180] or around 360
+    [lon, lon - 360.0, lon + 360.0].iter()
+      .find_map(|l| self.tab.world2psi(&[*l, lat]))
+      .and_then(|psi| self.proj2img.proj2img(&ProjXY::new(
+        (psi[0] - self.crval1).to_radians(),
+        (psi[1] - self.crval2).to_radians()
+      )))
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::img2proj::WcsImgXY2ProjXY;
+
+  #[test]
+  fn test_tab_axis() {
+    // Wavelength table with an index vector
+    let coords = [500.0, 510.0, 530.0, 560.0];
+    let index = [0.0, 2.0, 4.0, 6.0];
+    let axis = TabAxis::new(1.0, 1.0, 0.0, &coords, Some(&index)).unwrap();
+    assert_eq!(axis.pix2world(1.0), Some(500.0));
+    assert_eq!(axis.pix2world(2.0), Some(505.0));
+    assert_eq!(axis.pix2world(6.0), Some(545.0));
+    assert_eq!(axis.pix2world(7.0), Some(560.0));
+    assert_eq!(axis.pix2world(8.0), Some(575.0)); // half a cell of extrapolation
+    assert_eq!(axis.pix2world(8.5), None);
+    assert!((axis.world2pix(545.0).unwrap() - 6.0).abs() < 1e-12);
+    assert!((axis.world2pix(495.0).unwrap() - 0.0).abs() < 1e-12);
+    assert_eq!(axis.world2pix(600.0), None);
+  }
+
+  #[test]
+  fn test_tab_celestial() {
+    // 3x3 table of (lon, lat), slightly non-linear
+    let mut coords = Vec::new();
+    for j in 0..3 {
+      for i in 0..3 {
+        let (i, j) = (i as f64, j as f64);
+        coords.push(359.0 + i + 0.1 * j * j);
+        coords.push(-1.0 + j + 0.05 * i * i);
+      }
+    }
+    let tab = Tab::new(&[3, 3], &coords, &[None, None]).unwrap();
+    let img2proj = WcsImgXY2ProjXY::from_cd(0.0, 0.0, 1.0, 0.0, 0.0, 1.0);
+    let tab = TabImg2Celestial::new(img2proj, 1.0, 1.0, tab).unwrap();
+    for (x, y) in [(0.0, 0.0), (0.3, 1.7), (1.5, 0.5), (1.9, 1.2), (-0.4, 2.4)] {
+      let lonlat = tab.img2lonlat(&ImgXY::new(x, y)).unwrap();
+      let img = tab.lonlat2img(&lonlat).unwrap();
+      assert!((img.x() - x).abs() < 1e-8 && (img.y() - y).abs() < 1e-8, "{} {} {:?}", x, y, img.x());
+    }
+    let lonlat = tab.img2lonlat(&ImgXY::new(1.0, 0.0)).unwrap();
+    assert!(lonlat.lon().abs() < 1e-12 && (lonlat.lat().to_degrees() + 0.95).abs() < 1e-12);
+    assert!(tab.img2lonlat(&ImgXY::new(2.6, 0.0)).is_none());
+  }
+}
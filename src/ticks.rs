@@ -0,0 +1,259 @@
+//! Module containing the placement of the coordinate grid ticks and labels along the borders
+//! of an image (like e.g. WCSAxes does).
+//!
+//! The graticule lines (see the `graticule` module) are intersected with the image borders.
+//! The steps are chosen from the local pixel scale so that two consecutive lines are separated by
+//! at least a given number of pixels, and the labels are greedily selected along each border so
+//! that they do not overlap.
+
+use std::f64::consts::PI;
+
+use crate::{Cone, ImgXY};
+use crate::bounded::BoundedImg2Celestial;
+use crate::graticule::{format_angle, nice_step_ceil, Graticule, GraticuleLineKind};
+use crate::img2celestial::ImgXY2Celestial;
+
+/// Maximum distance, in pixels, between the graticule polylines and the true lines.
+const TICK_TOL: f64 = 0.01;
+
+/// Format of the tick labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelFormat {
+  /// Sexagesimal degrees, e.g. `+12°30'`.
+  Sexagesimal,
+  /// Decimal degrees, e.g. `12.5°`.
+  Decimal,
+}
+
+/// Border of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImgBorder {
+  /// `y` minimum.
+  Bottom,
+  /// `x` maximum.
+  Right,
+  /// `y` maximum.
+  Top,
+  /// `x` minimum.
+  Left,
+}
+
+/// Intersection of a graticule line with an image border.
+pub struct BorderTick {
+  kind: GraticuleLineKind,
+  value: f64,
+  label: String,
+  pos: ImgXY,
+  border: ImgBorder,
+  angle: f64,
+  labelled: bool,
+}
+
+impl BorderTick {
+
+  /// Get the kind of graticule line
+  pub fn kind(&self) -> GraticuleLineKind {
+    self.kind
+  }
+
+  /// Get the longitude (for a meridian) or the latitude (for a parallel) of the line, in radians
+  pub fn value(&self) -> f64 {
+    self.value
+  }
+
+  /// Get the formatted label
+  pub fn label(&self) -> &str {
+    &self.label
+  }
+
+  /// Get the position of the tick on the image border
+  pub fn pos(&self) -> &ImgXY {
+    &self.pos
+  }
+
+  /// Get the border the tick is on
+  pub fn border(&self) -> ImgBorder {
+    self.border
+  }
+
+  /// Get the direction of the graticule line at the tick, toward the inside of the image,
+  /// in radians counter-clockwise from the image x-axis
+  pub fn angle(&self) -> f64 {
+    self.angle
+  }
+
+  /// Returns `true` if the label of the tick has been selected to be displayed
+  /// (i.e. it does not overlap the label of another tick)
+  pub fn is_labelled(&self) -> bool {
+    self.labelled
+  }
+}
+
+/// Computes the ticks (and labels) along the borders of the given image.
+/// # Params
+/// * `img`: the image
+/// * `format`: the label format
+/// * `min_spacing`: the minimum distance, in pixels at the image center, between two consecutive
+///   lines of a same kind
+/// * `label_size`: the size, in pixels, of a label along a border (labels closer than this distance
+///   are not all selected)
+/// # Return
+/// * `None` if the image footprint or its pixel scale can't be computed
+/// * else the ticks, sorted by border and by position along each border
+pub fn border_ticks<T: ImgXY2Celestial>(
+  img: &BoundedImg2Celestial<T>,
+  format: LabelFormat,
+  min_spacing: f64,
+  label_size: f64
+) -> Option<Vec<BorderTick>> {
+  assert!(min_spacing > 0.0 && label_size >= 0.0);
+  let cone = img.bounding_cone()?;
+  let (sx, sy) = img.pixel_scale_at_center()?;
+  let step_ceil = match format {
+    LabelFormat::Sexagesimal => nice_step_ceil,
+    LabelFormat::Decimal => decimal_step_ceil,
+  };
+  let lat_step = step_ceil(min_spacing * sx.max(sy));
+  // The meridians are closer to each other than the parallels by a factor cos(lat)
+  let cos_lat = img.center()?.lat().cos().max(1e-3);
+  let lon_step = step_ceil((min_spacing * sx.max(sy) / cos_lat).min(PI / 2.0));
+  let area = Cone::new(cone.center().clone(), (cone.radius() * 1.01).min(PI));
+  let (xmin, xmax) = img.x_edges();
+  let (ymin, ymax) = img.y_edges();
+  let mut ticks = Vec::new();
+  for line in Graticule::new(lon_step, lat_step).img_lines(img.img2celestial(), Some(&area), TICK_TOL) {
+    let step = match line.kind() {
+      GraticuleLineKind::Meridian => lon_step,
+      GraticuleLineKind::Parallel => lat_step,
+    };
+    let label = match format {
+      LabelFormat::Sexagesimal => format_angle(line.value(), step, line.kind() == GraticuleLineKind::Parallel),
+      LabelFormat::Decimal => format_decimal(line.value(), step, line.kind() == GraticuleLineKind::Parallel),
+    };
+    for polyline in line.polylines() {
+      for w in polyline.windows(2) {
+        let (a, b) = (&w[0], &w[1]);
+        let (dx, dy) = (b.x() - a.x(), b.y() - a.y());
+        // (border, coordinate of the border line, `true` for a vertical border, inward direction sign)
+        for (border, c, vertical, inward) in [
+          (ImgBorder::Bottom, ymin, false, 1.0),
+          (ImgBorder::Right, xmax, true, -1.0),
+          (ImgBorder::Top, ymax, false, -1.0),
+          (ImgBorder::Left, xmin, true, 1.0),
+        ] {
+          let (ca, cb) = if vertical { (a.x(), b.x()) } else { (a.y(), b.y()) };
+          // Half-open test so that a vertex on the border is counted once
+          if (ca - c) * (cb - c) > 0.0 || ca == cb || cb == c {
+            continue;
+          }
+          let t = (c - ca) / (cb - ca);
+          let pos = ImgXY::new(a.x() + t * dx, a.y() + t * dy);
+          let along = if vertical { pos.y() } else { pos.x() };
+          let (lmin, lmax) = if vertical { (ymin, ymax) } else { (xmin, xmax) };
+          if !(lmin..=lmax).contains(&along) {
+            continue;
+          }
+          // Direction pointing inside the image
+          let d_normal = if vertical { dx } else { dy };
+          let angle = if d_normal * inward >= 0.0 { dy.atan2(dx) } else { (-dy).atan2(-dx) };
+          ticks.push(BorderTick {
+            kind: line.kind(), value: line.value(), label: label.clone(), pos, border, angle, labelled: false,
+          });
+        }
+      }
+    }
+  }
+  let along = |t: &BorderTick| match t.border {
+    ImgBorder::Bottom | ImgBorder::Top => t.pos.x(),
+    ImgBorder::Left | ImgBorder::Right => t.pos.y(),
+  };
+  ticks.sort_by(|a, b| (a.border as u8).cmp(&(b.border as u8)).then(along(a).total_cmp(&along(b))));
+  // Greedy label selection along each border
+  let mut last: Option<(ImgBorder, f64)> = None;
+  for tick in ticks.iter_mut() {
+    let pos = along(tick);
+    if last.map(|(border, p)| border != tick.border || pos - p >= label_size).unwrap_or(true) {
+      tick.labelled = true;
+      last = Some((tick.border, pos));
+    }
+  }
+  Some(ticks)
+}
+
+/// Returns the smallest step of the form `{1, 2, 5} x 10^k` degrees larger than or equal to the
+/// given value (the largest step being 90 deg).
+fn decimal_step_ceil(approx: f64) -> f64 {
+  let deg = approx.to_degrees();
+  if deg >= 50.0 {
+    return PI / 2.0;
+  }
+  let p = 10_f64.powf(deg.log10().floor());
+  [1.0, 2.0, 5.0, 10.0].iter()
+    .map(|m| m * p)
+    .find(|s| *s >= deg * (1.0 - 1e-12))
+    .unwrap_or(10.0 * p)
+    .to_radians()
+}
+
+/// Formats the given angle in decimal degrees, with the precision of the given step.
+fn format_decimal(angle: f64, step: f64, signed: bool) -> String {
+  let step_deg = step.to_degrees();
+  let n_dec = (0..10)
+    .find(|n| {
+      let r = step_deg * 10_f64.powi(*n);
+      (r - r.round()).abs() < 1e-6 * r.max(1.0)
+    })
+    .unwrap_or(10) as usize;
+  let deg = angle.to_degrees();
+  if signed {
+    format!("{:+.n$}°", deg, n = n_dec)
+  } else {
+    format!("{:.n$}°", deg, n = n_dec)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::LonLat;
+  use crate::bounded::tan_image;
+
+  #[test]
+  fn test_border_ticks() {
+    // 600 x 400 pixels of 1 arcsec, North up, centered on (lon=150, lat=2.2)
+    let center = LonLat::new(150_f64.to_radians(), 2.2_f64.to_radians());
+    let img = tan_image(&center, (300.5, 200.5), 0.0, 1.0 / 3600.0, (600, 400));
+    let ticks = border_ticks(&img, LabelFormat::Sexagesimal, 100.0, 60.0).unwrap();
+    // Steps of 2 arcmin (>= 100 arcsec): meridians cross the bottom and top borders, parallels the left and right ones
+    assert!(!ticks.is_empty());
+    for tick in ticks.iter() {
+      match tick.kind() {
+        GraticuleLineKind::Meridian => assert!(matches!(tick.border(), ImgBorder::Bottom | ImgBorder::Top)),
+        GraticuleLineKind::Parallel => assert!(matches!(tick.border(), ImgBorder::Left | ImgBorder::Right)),
+      }
+      // Position on the line
+      let lonlat = img.img2lonlat(tick.pos()).unwrap();
+      let value = match tick.kind() {
+        GraticuleLineKind::Meridian => lonlat.lon(),
+        GraticuleLineKind::Parallel => lonlat.lat(),
+      };
+      // (1e-7 rad ~ 0.02 pixel)
+      assert!((value - tick.value()).abs() < 1e-7);
+    }
+    // Bottom ticks: meridians are vertical (up = inward)
+    let bottom: Vec<_> = ticks.iter().filter(|t| t.border() == ImgBorder::Bottom).collect();
+    assert_eq!(bottom.len(), 5);
+    assert!(bottom.iter().all(|t| (t.angle() - PI / 2.0).abs() < 1e-3));
+    assert!(bottom.iter().any(|t| t.label() == "150°00'"));
+    assert!(ticks.iter().filter(|t| t.border() == ImgBorder::Left).any(|t| t.label() == "+2°12'"));
+    // Label selection (ticks separated by 120 pixels)
+    assert!(bottom.iter().all(|t| t.is_labelled()));
+    let ticks = border_ticks(&img, LabelFormat::Sexagesimal, 100.0, 250.0).unwrap();
+    assert_eq!(ticks.iter().filter(|t| t.border() == ImgBorder::Bottom && t.is_labelled()).count(), 2);
+    // Decimal: steps of 0.05 deg (>= 100 arcsec)
+    let ticks = border_ticks(&img, LabelFormat::Decimal, 100.0, 60.0).unwrap();
+    assert!(ticks.iter().any(|t| t.label() == "+2.20°"));
+    assert!(ticks.iter().any(|t| t.label() == "150.00°"));
+    assert_eq!(format_decimal(0.25_f64.to_radians(), 0.25_f64.to_radians(), false), "0.25°");
+  }
+}
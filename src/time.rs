@@ -0,0 +1,674 @@
+//! Module implementing the time axis of the FITS World Coordinate System, see
+//! "Representations of time coordinates in FITS" by A. Rots et al. (2015), a.k.a. WCS Paper VII.
+//!
+//! Supported time scales are `TT`, `TAI` and `UTC`. The conversions from/to `UTC` rely on
+//! a leap seconds table: a built-in table is provided, but it can be updated by the caller
+//! (e.g. from the IERS `Leap_Second.dat` file) when a new leap second is announced.
+
+use std::cmp::Ordering;
+
+use crate::ndim::WcsAxis;
+
+/// Number of SI seconds in a day (not containing a leap second).
+pub const SECONDS_PER_DAY: f64 = 86400.0;
+/// Value to be added to a Modified Julian Date to obtain a Julian Date.
+pub const MJD_TO_JD: f64 = 2400000.5;
+/// Constant offset `TT - TAI`, in seconds.
+pub const TT_MINUS_TAI: f64 = 32.184;
+
+/// Time scales supported by this library (`TIMESYS` keyword).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeScale {
+  /// Terrestrial Time.
+  TT,
+  /// International Atomic Time.
+  TAI,
+  /// Coordinated Universal Time, the default value (FITS WCS Paper VII).
+  #[default]
+  UTC,
+}
+
+impl TimeScale {
+
+  /// Returns the time scale from the value of the `TIMESYS` keyword
+  /// (`None` if not supported).
+  pub fn from_keyword(timesys: &str) -> Option<Self> {
+    match timesys.trim() {
+      "TT" | "TDT" | "ET" => Some(Self::TT),
+      "TAI" | "IAT" => Some(Self::TAI),
+      "UTC" => Some(Self::UTC),
+      _ => None,
+    }
+  }
+
+  /// Returns the value of the `TIMESYS` keyword associated to this time scale.
+  pub fn keyword(&self) -> &'static str {
+    match self {
+      Self::TT => "TT",
+      Self::TAI => "TAI",
+      Self::UTC => "UTC",
+    }
+  }
+}
+
+/// Time units (`TIMEUNIT` keyword).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+  /// SI second (`s`), the default value.
+  Second,
+  /// Minute (`min`), i.e. 60 s.
+  Minute,
+  /// Hour (`h`), i.e. 3600 s.
+  Hour,
+  /// Day (`d`), i.e. 86400 s.
+  Day,
+  /// Julian year (`a` or `yr`), i.e. 365.25 d.
+  JulianYear,
+  /// Julian century (`cy`), i.e. 36525 d.
+  JulianCentury,
+}
+
+impl TimeUnit {
+
+  /// Returns the time unit from the value of the `TIMEUNIT` keyword
+  /// (`None` if not supported).
+  pub fn from_keyword(timeunit: &str) -> Option<Self> {
+    match timeunit.trim() {
+      "s" => Some(Self::Second),
+      "min" => Some(Self::Minute),
+      "h" => Some(Self::Hour),
+      "d" => Some(Self::Day),
+      "a" | "yr" => Some(Self::JulianYear),
+      "cy" => Some(Self::JulianCentury),
+      _ => None,
+    }
+  }
+
+  /// Returns the value of the `TIMEUNIT` keyword associated to this unit.
+  pub fn keyword(&self) -> &'static str {
+    match self {
+      Self::Second => "s",
+      Self::Minute => "min",
+      Self::Hour => "h",
+      Self::Day => "d",
+      Self::JulianYear => "a",
+      Self::JulianCentury => "cy",
+    }
+  }
+
+  /// Returns the number of SI seconds in this unit.
+  pub fn seconds(&self) -> f64 {
+    match self {
+      Self::Second => 1.0,
+      Self::Minute => 60.0,
+      Self::Hour => 3600.0,
+      Self::Day => SECONDS_PER_DAY,
+      Self::JulianYear => 365.25 * SECONDS_PER_DAY,
+      Self::JulianCentury => 36525.0 * SECONDS_PER_DAY,
+    }
+  }
+}
+
+/// Reference position (`TREFPOS` keyword).
+/// # Remark
+/// * the reference position is only stored: converting times from one reference position
+///   to another requires an ephemeris and is out of the scope of this library.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TimeRefPos {
+  /// `TOPOCENTER`, the default value.
+  #[default]
+  Topocenter,
+  /// `GEOCENTER`
+  Geocenter,
+  /// `BARYCENTER`
+  Barycenter,
+  /// `HELIOCENTER`
+  Heliocenter,
+  /// `RELOCATABLE`
+  Relocatable,
+  /// Any other value.
+  Other(String),
+}
+
+impl TimeRefPos {
+
+  /// Returns the reference position from the value of the `TREFPOS` keyword.
+  pub fn from_keyword(trefpos: &str) -> Self {
+    match trefpos.trim() {
+      "TOPOCENTER" => Self::Topocenter,
+      "GEOCENTER" => Self::Geocenter,
+      "BARYCENTER" => Self::Barycenter,
+      "HELIOCENTER" => Self::Heliocenter,
+      "RELOCATABLE" => Self::Relocatable,
+      other => Self::Other(other.to_string()),
+    }
+  }
+
+  /// Returns the value of the `TREFPOS` keyword associated to this reference position.
+  pub fn keyword(&self) -> &str {
+    match self {
+      Self::Topocenter => "TOPOCENTER",
+      Self::Geocenter => "GEOCENTER",
+      Self::Barycenter => "BARYCENTER",
+      Self::Heliocenter => "HELIOCENTER",
+      Self::Relocatable => "RELOCATABLE",
+      Self::Other(s) => s,
+    }
+  }
+}
+
+/// Built-in leap seconds table: `(MJD of the UTC day from which the value applies, TAI - UTC)`.
+const BUILTIN_LEAP_SECONDS: [(i64, f64); 28] = [
+  (41317, 10.0), // 1972-01-01
+  (41499, 11.0), // 1972-07-01
+  (41683, 12.0), // 1973-01-01
+  (42048, 13.0), // 1974-01-01
+  (42413, 14.0), // 1975-01-01
+  (42778, 15.0), // 1976-01-01
+  (43144, 16.0), // 1977-01-01
+  (43509, 17.0), // 1978-01-01
+  (43874, 18.0), // 1979-01-01
+  (44239, 19.0), // 1980-01-01
+  (44786, 20.0), // 1981-07-01
+  (45151, 21.0), // 1982-07-01
+  (45516, 22.0), // 1983-07-01
+  (46247, 23.0), // 1985-07-01
+  (47161, 24.0), // 1988-01-01
+  (47892, 25.0), // 1990-01-01
+  (48257, 26.0), // 1991-01-01
+  (48804, 27.0), // 1992-07-01
+  (49169, 28.0), // 1993-07-01
+  (49534, 29.0), // 1994-07-01
+  (50083, 30.0), // 1996-01-01
+  (50630, 31.0), // 1997-07-01
+  (51179, 32.0), // 1999-01-01
+  (53736, 33.0), // 2006-01-01
+  (54832, 34.0), // 2009-01-01
+  (56109, 35.0), // 2012-07-01
+  (57204, 36.0), // 2015-07-01
+  (57754, 37.0), // 2017-01-01
+];
+
+/// Table of `TAI - UTC` values (leap seconds).
+/// # Remark
+/// * `UTC` is not defined (in this library) before 1972-01-01, i.e. before the first entry
+///   of the built-in table.
+#[derive(Debug, Clone)]
+pub struct LeapSecondTable {
+  /// Sorted list of `(MJD of the UTC day from which the value applies, TAI - UTC in seconds)`.
+  entries: Vec<(i64, f64)>,
+}
+
+impl Default for LeapSecondTable {
+  fn default() -> Self {
+    Self::builtin()
+  }
+}
+
+impl LeapSecondTable {
+
+  /// Returns the built-in table (last leap second: 2016-12-31).
+  pub fn builtin() -> Self {
+    Self::from_entries(BUILTIN_LEAP_SECONDS.to_vec())
+  }
+
+  /// Creates a table from the given list of `(MJD, TAI - UTC)` entries.
+  /// # Params
+  /// * `entries`: list of `(MJD of the UTC day from which the value applies, TAI - UTC in seconds)`
+  pub fn from_entries(mut entries: Vec<(i64, f64)>) -> Self {
+    entries.sort_by_key(|(mjd, _)| *mjd);
+    entries.dedup_by_key(|(mjd, _)| *mjd);
+    Self { entries }
+  }
+
+  /// Adds (or replace) an entry in the table, typically to add a newly announced leap second.
+  /// # Params
+  /// * `mjd`: MJD of the UTC day from which the value applies (the leap second being
+  ///   inserted at the end of the previous day)
+  /// * `tai_minus_utc`: the new `TAI - UTC` value, in seconds
+  pub fn insert(&mut self, mjd: i64, tai_minus_utc: f64) {
+    match self.entries.binary_search_by_key(&mjd, |(d, _)| *d) {
+      Ok(i) => self.entries[i].1 = tai_minus_utc,
+      Err(i) => self.entries.insert(i, (mjd, tai_minus_utc)),
+    }
+  }
+
+  /// Returns the list of `(MJD, TAI - UTC)` entries.
+  pub fn entries(&self) -> &[(i64, f64)] {
+    &self.entries
+  }
+
+  /// Returns the value of `TAI - UTC` (in seconds) for the given UTC day
+  /// (`None` if the day is before the first entry of the table).
+  /// # Params
+  /// * `mjd`: MJD of the UTC day
+  pub fn tai_minus_utc(&self, mjd: i64) -> Option<f64> {
+    match self.entries.binary_search_by_key(&mjd, |(d, _)| *d) {
+      Ok(i) => Some(self.entries[i].1),
+      Err(0) => None,
+      Err(i) => Some(self.entries[i - 1].1),
+    }
+  }
+
+  /// Returns the number of seconds in the given UTC day (usually 86400, or 86401 in case
+  /// of a positive leap second).
+  fn utc_day_length(&self, mjd: i64) -> Option<f64> {
+    let dat = self.tai_minus_utc(mjd)?;
+    let next_dat = self.tai_minus_utc(mjd + 1)?;
+    Some(SECONDS_PER_DAY + next_dat - dat)
+  }
+}
+
+/// A date (an instant) in a given time scale.
+/// The date is stored as a Modified Julian Day number plus a number of seconds in the day,
+/// to preserve the precision and to be able to represent leap seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Time {
+  /// Time scale in which the date is expressed.
+  scale: TimeScale,
+  /// Modified Julian Day number.
+  day: i64,
+  /// Number of seconds in the day, in `[0, 86400[`
+  /// (or in `[0, 86401[` for a UTC day containing a leap second).
+  sec: f64,
+}
+
+impl Time {
+
+  /// Creates a new date from a Modified Julian Date.
+  /// # Params
+  /// * `mjd`: Modified Julian Date
+  /// * `scale`: time scale in which the date is expressed
+  pub fn from_mjd(mjd: f64, scale: TimeScale) -> Self {
+    Self::from_mjd_parts(mjd, 0.0, scale)
+  }
+
+  /// Creates a new date from a Modified Julian Date provided in two parts
+  /// (like `MJDREFI` and `MJDREFF`) to preserve the precision.
+  /// # Params
+  /// * `mjd_int`: (usually) integer part of the Modified Julian Date
+  /// * `mjd_frac`: (usually) fractional part of the Modified Julian Date
+  /// * `scale`: time scale in which the date is expressed
+  pub fn from_mjd_parts(mjd_int: f64, mjd_frac: f64, scale: TimeScale) -> Self {
+    let day = mjd_int.floor();
+    let sec = ((mjd_int - day) + mjd_frac) * SECONDS_PER_DAY;
+    Self { scale, day: day as i64, sec }.normalized()
+  }
+
+  /// Creates a new date from a Julian Date.
+  /// # Params
+  /// * `jd`: Julian Date
+  /// * `scale`: time scale in which the date is expressed
+  pub fn from_jd(jd: f64, scale: TimeScale) -> Self {
+    Self::from_mjd_parts(jd - 2400000.0, -0.5, scale)
+  }
+
+  /// Creates a new date from a Gregorian calendar date and a time of the day.
+  /// # Params
+  /// * `year`, `month` (in `[1, 12]`), `day` (in `[1, 31]`): calendar date
+  /// * `hour`, `min`: hour and minute in the day
+  /// * `sec`: seconds in the minute, possibly in `[60, 61[` in case of a UTC leap second
+  /// * `scale`: time scale in which the date is expressed
+  #[allow(clippy::too_many_arguments)]
+  pub fn from_calendar(year: i64, month: u32, day: u32, hour: u32, min: u32, sec: f64, scale: TimeScale) -> Self {
+    Self {
+      scale,
+      day: mjd_from_calendar(year, month, day),
+      sec: (hour * 3600 + min * 60) as f64 + sec,
+    }
+  }
+
+  /// Returns the time scale in which this date is expressed.
+  pub fn scale(&self) -> TimeScale {
+    self.scale
+  }
+
+  /// Returns the Modified Julian Day number and the number of seconds in this day.
+  pub fn mjd_day_and_sec(&self) -> (i64, f64) {
+    (self.day, self.sec)
+  }
+
+  /// Returns the Modified Julian Date.
+  /// # Remark
+  /// * during a UTC leap second, the returned value is larger than the MJD of the next day
+  ///   midnight.
+  pub fn mjd(&self) -> f64 {
+    self.day as f64 + self.sec / SECONDS_PER_DAY
+  }
+
+  /// Returns the Julian Date.
+  pub fn jd(&self) -> f64 {
+    (self.day as f64 + MJD_TO_JD) + self.sec / SECONDS_PER_DAY
+  }
+
+  /// Returns the ISO-8601 representation of this date, e.g. `2016-12-31T23:59:60.500`.
+  /// # Params
+  /// * `n_decimals`: number of decimals of the seconds (max 9)
+  pub fn to_iso8601(&self, n_decimals: u8) -> String {
+    let n_decimals = n_decimals.min(9) as u32;
+    let s = 10_i64.pow(n_decimals);
+    let mut day = self.day;
+    let mut units = (self.sec * s as f64).round() as i64;
+    let is_leap_sec = self.sec >= SECONDS_PER_DAY;
+    let (hour, min, sec_units) = if is_leap_sec {
+      (23, 59, (units - 86340 * s).min(61 * s - 1))
+    } else {
+      if units >= 86400 * s {
+        day += 1;
+        units -= 86400 * s;
+      }
+      (units / (3600 * s), (units / (60 * s)) % 60, units % (60 * s))
+    };
+    let (year, month, mday) = calendar_from_mjd(day);
+    let mut iso = format!(
+      "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+      year, month, mday, hour, min, sec_units / s
+    );
+    if n_decimals > 0 {
+      iso.push_str(&format!(".{:0width$}", sec_units % s, width = n_decimals as usize));
+    }
+    iso
+  }
+
+  /// Converts this date into the given time scale.
+  /// Returns `None` if a conversion involving `UTC` is requested for a date
+  /// prior to the first entry of the leap seconds table.
+  /// # Params
+  /// * `scale`: time scale in which the result must be expressed
+  /// * `leap_seconds`: leap seconds table
+  pub fn to_scale(&self, scale: TimeScale, leap_seconds: &LeapSecondTable) -> Option<Time> {
+    if scale == self.scale {
+      return Some(self.clone());
+    }
+    let tai = match self.scale {
+      TimeScale::TAI => self.clone(),
+      TimeScale::TT => self.shifted(-TT_MINUS_TAI),
+      TimeScale::UTC => {
+        let dat = leap_seconds.tai_minus_utc(self.day)?;
+        Self { scale: TimeScale::TAI, day: self.day, sec: self.sec + dat }.normalized()
+      },
+    };
+    match scale {
+      TimeScale::TAI => Some(tai),
+      TimeScale::TT => Some(Self { scale, ..tai.shifted(TT_MINUS_TAI) }),
+      TimeScale::UTC => {
+        // UTC is behind TAI (by less than a day), so the UTC day is either the TAI day
+        // or the previous day.
+        (tai.day - 1..=tai.day).rev().find_map(|day| {
+          let dat = leap_seconds.tai_minus_utc(day)?;
+          let len = leap_seconds.utc_day_length(day)?;
+          let sec = (tai.day - day) as f64 * SECONDS_PER_DAY + tai.sec - dat;
+          if (0.0..len).contains(&sec) {
+            Some(Self { scale, day, sec })
+          } else {
+            None
+          }
+        })
+      },
+    }
+  }
+
+  /// Returns the number of SI seconds elapsed from the given date to this date.
+  /// Returns `None` if a conversion involving `UTC` is needed and failed.
+  /// # Params
+  /// * `from`: starting date (possibly in a different time scale)
+  /// * `leap_seconds`: leap seconds table
+  pub fn seconds_since(&self, from: &Time, leap_seconds: &LeapSecondTable) -> Option<f64> {
+    let (a, b) = if self.scale == from.scale && self.scale != TimeScale::UTC {
+      (self.clone(), from.clone())
+    } else {
+      (self.to_scale(TimeScale::TAI, leap_seconds)?, from.to_scale(TimeScale::TAI, leap_seconds)?)
+    };
+    Some((a.day - b.day) as f64 * SECONDS_PER_DAY + (a.sec - b.sec))
+  }
+
+  /// Returns a new date shifted by the given number of SI seconds.
+  /// For `UTC` dates, the computation is made in `TAI` to take leap seconds into account.
+  /// Returns `None` if a conversion involving `UTC` failed.
+  /// # Params
+  /// * `dt`: number of SI seconds (possibly negative)
+  /// * `leap_seconds`: leap seconds table
+  pub fn add_seconds(&self, dt: f64, leap_seconds: &LeapSecondTable) -> Option<Time> {
+    match self.scale {
+      TimeScale::UTC => self.to_scale(TimeScale::TAI, leap_seconds)?
+        .shifted(dt)
+        .to_scale(TimeScale::UTC, leap_seconds),
+      _ => Some(self.shifted(dt)),
+    }
+  }
+
+  /// Shift the date by the given number of seconds, assuming a uniform time scale.
+  fn shifted(&self, dt: f64) -> Self {
+    Self { scale: self.scale, day: self.day, sec: self.sec + dt }.normalized()
+  }
+
+  /// Ensures `sec` in `[0, 86400[` (assuming 86400 seconds per day).
+  fn normalized(mut self) -> Self {
+    let n_days = (self.sec / SECONDS_PER_DAY).floor();
+    if n_days != 0.0 {
+      self.day += n_days as i64;
+      self.sec -= n_days * SECONDS_PER_DAY;
+    }
+    if self.sec >= SECONDS_PER_DAY { // May happen due to rounding errors
+      self.day += 1;
+      self.sec = 0.0;
+    }
+    self
+  }
+}
+
+impl PartialOrd for Time {
+  /// Dates are comparable only if they are expressed in the same time scale.
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    if self.scale == other.scale {
+      match self.day.cmp(&other.day) {
+        Ordering::Equal => self.sec.partial_cmp(&other.sec),
+        ord => Some(ord),
+      }
+    } else {
+      None
+    }
+  }
+}
+
+/// Returns the Modified Julian Day number of the given Gregorian calendar date
+/// (Fliegel & Van Flandern, 1968).
+pub fn mjd_from_calendar(year: i64, month: u32, day: u32) -> i64 {
+  let (m, d) = (month as i64, day as i64);
+  let a = (14 - m) / 12;
+  let y = year + 4800 - a;
+  let m = m + 12 * a - 3;
+  let jdn = d + (153 * m + 2) / 5 + 365 * y + y / 4 - y / 100 + y / 400 - 32045;
+  jdn - 2400001
+}
+
+/// Returns the Gregorian calendar date `(year, month, day)` of the given Modified Julian Day number
+/// (Fliegel & Van Flandern, 1968).
+pub fn calendar_from_mjd(mjd: i64) -> (i64, u32, u32) {
+  let a = mjd + 2400001 + 32044;
+  let b = (4 * a + 3) / 146097;
+  let c = a - 146097 * b / 4;
+  let d = (4 * c + 3) / 1461;
+  let e = c - 1461 * d / 4;
+  let m = (5 * e + 2) / 153;
+  let day = e - (153 * m + 2) / 5 + 1;
+  let month = m + 3 - 12 * (m / 10);
+  let year = 100 * b + d - 4800 + m / 10;
+  (year, month as u32, day as u32)
+}
+
+/// Time axis of an image or a cube: `t = MJDREF + CRVAL + CDELT * (pix - CRPIX)`,
+/// with `CRVAL` and `CDELT` in `TIMEUNIT` and `MJDREF` in `TIMESYS`.
+#[derive(Debug, Clone)]
+pub struct TimeAxis {
+  /// Value of the `CRPIXi` keyword.
+  crpix: f64,
+  /// Value of the `CDELTi` keyword (in `TIMEUNIT`).
+  cdelt: f64,
+  /// Value of the `CRVALi` keyword (in `TIMEUNIT`), relative to `MJDREF`.
+  crval: f64,
+  /// Reference date (`MJDREF` or `MJDREFI + MJDREFF`), in `TIMESYS`.
+  mjdref: Time,
+  /// Unit of `CRVAL` and `CDELT`
+  timeunit: TimeUnit,
+  /// Reference position
+  trefpos: TimeRefPos,
+  /// Leap seconds table, used only if `TIMESYS = UTC`.
+  leap_seconds: LeapSecondTable,
+}
+
+impl TimeAxis {
+
+  /// # Params
+  /// * `crpix`: value of the `CRPIXi` keyword
+  /// * `cdelt`: value of the `CDELTi` keyword, in `timeunit`
+  /// * `crval`: value of the `CRVALi` keyword, in `timeunit` (use 0 as default value)
+  /// * `mjdrefi`: value of the `MJDREFI` keyword (or of the `MJDREF` keyword)
+  /// * `mjdreff`: value of the `MJDREFF` keyword (use 0 with `MJDREF`)
+  /// * `timesys`: time scale (`TIMESYS` keyword, default is `UTC`)
+  /// * `timeunit`: time unit (`TIMEUNIT` keyword, default is `s`)
+  /// # Panics
+  /// * if `cdelt` equals 0 or is not finite
+  pub fn new(
+    crpix: f64, cdelt: f64, crval: f64,
+    mjdrefi: f64, mjdreff: f64,
+    timesys: TimeScale, timeunit: TimeUnit,
+  ) -> Self {
+    assert!(cdelt != 0.0 && cdelt.is_finite());
+    Self {
+      crpix, cdelt, crval,
+      mjdref: Time::from_mjd_parts(mjdrefi, mjdreff, timesys),
+      timeunit,
+      trefpos: Default::default(),
+      leap_seconds: Default::default(),
+    }
+  }
+
+  /// Set the reference position (`TREFPOS` keyword).
+  pub fn set_trefpos(&mut self, trefpos: TimeRefPos) {
+    self.trefpos = trefpos;
+  }
+
+  /// Replace the leap seconds table (used only if `TIMESYS = UTC`).
+  pub fn set_leap_seconds(&mut self, leap_seconds: LeapSecondTable) {
+    self.leap_seconds = leap_seconds;
+  }
+
+  /// Returns the reference date (`MJDREF`).
+  pub fn mjdref(&self) -> &Time {
+    &self.mjdref
+  }
+
+  /// Returns the time scale (`TIMESYS`).
+  pub fn timesys(&self) -> TimeScale {
+    self.mjdref.scale()
+  }
+
+  /// Returns the time unit (`TIMEUNIT`).
+  pub fn timeunit(&self) -> TimeUnit {
+    self.timeunit
+  }
+
+  /// Returns the reference position (`TREFPOS`).
+  pub fn trefpos(&self) -> &TimeRefPos {
+    &self.trefpos
+  }
+
+  /// Returns the leap seconds table.
+  pub fn leap_seconds(&self) -> &LeapSecondTable {
+    &self.leap_seconds
+  }
+
+  /// Transforms a world coordinate (a time relative to `MJDREF`, in `TIMEUNIT`) into a date
+  /// expressed in `TIMESYS`.
+  pub fn world2time(&self, world: f64) -> Option<Time> {
+    self.mjdref.add_seconds(world * self.timeunit.seconds(), &self.leap_seconds)
+  }
+
+  /// Transforms a date (in any time scale) into a world coordinate (a time relative to `MJDREF`,
+  /// in `TIMEUNIT`).
+  pub fn time2world(&self, time: &Time) -> Option<f64> {
+    time.seconds_since(&self.mjdref, &self.leap_seconds)
+      .map(|dt| dt / self.timeunit.seconds())
+  }
+
+  /// Transforms a pixel coordinate into a date expressed in `TIMESYS`.
+  pub fn pix2time(&self, pix: f64) -> Option<Time> {
+    self.pix2world(pix).and_then(|world| self.world2time(world))
+  }
+
+  /// Transforms a date (in any time scale) into a pixel coordinate.
+  pub fn time2pix(&self, time: &Time) -> Option<f64> {
+    self.time2world(time).and_then(|world| self.world2pix(world))
+  }
+}
+
+impl WcsAxis for TimeAxis {
+
+  fn pix2world(&self, pix: f64) -> Option<f64> {
+    Some(self.crval + self.cdelt * (pix - self.crpix))
+  }
+
+  fn world2pix(&self, world: f64) -> Option<f64> {
+    Some(self.crpix + (world - self.crval) / self.cdelt)
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_builtin_table_dates() {
+    assert_eq!(mjd_from_calendar(1858, 11, 17), 0);
+    assert_eq!(calendar_from_mjd(0), (1858, 11, 17));
+    assert_eq!(mjd_from_calendar(1972, 1, 1), 41317);
+    assert_eq!(mjd_from_calendar(2017, 1, 1), 57754);
+    for (mjd, _) in BUILTIN_LEAP_SECONDS {
+      let (y, m, d) = calendar_from_mjd(mjd);
+      assert!(d == 1 && (m == 1 || m == 7), "{}: {}-{}-{}", mjd, y, m, d);
+      assert_eq!(mjd_from_calendar(y, m, d), mjd);
+    }
+  }
+
+  #[test]
+  fn test_leap_second() {
+    let table = LeapSecondTable::default();
+    let utc = Time::from_calendar(2016, 12, 31, 23, 59, 60.5, TimeScale::UTC);
+    assert_eq!(utc.to_iso8601(3), "2016-12-31T23:59:60.500");
+    let tai = utc.to_scale(TimeScale::TAI, &table).unwrap();
+    assert_eq!(tai.to_iso8601(3), "2017-01-01T00:00:36.500");
+    let tt = tai.to_scale(TimeScale::TT, &table).unwrap();
+    assert_eq!(tt.to_iso8601(3), "2017-01-01T00:01:08.684");
+    assert_eq!(tt.to_scale(TimeScale::UTC, &table), Some(utc.clone()));
+    // One second later in UTC is the first second of 2017
+    let next = utc.add_seconds(1.0, &table).unwrap();
+    assert_eq!(next.to_iso8601(3), "2017-01-01T00:00:00.500");
+    assert_eq!(next.seconds_since(&utc, &table), Some(1.0));
+    // UTC not defined before 1972
+    let old = Time::from_calendar(1960, 1, 1, 0, 0, 0.0, TimeScale::UTC);
+    assert_eq!(old.to_scale(TimeScale::TAI, &table), None);
+    // Update the table with a (fictive) new leap second
+    let mut table = table;
+    table.insert(61041, 38.0);
+    let utc = Time::from_calendar(2025, 12, 31, 23, 59, 60.0, TimeScale::UTC);
+    let tai = utc.to_scale(TimeScale::TAI, &table).unwrap();
+    assert_eq!(tai.to_scale(TimeScale::UTC, &table), Some(utc));
+  }
+
+  #[test]
+  fn test_time_axis() {
+    let axis = TimeAxis::new(1.0, 0.5, 10.0, 57753.0, 0.5, TimeScale::default(), TimeUnit::Second);
+    assert_eq!(axis.timesys(), TimeScale::UTC);
+    let t = axis.pix2time(1.0).unwrap();
+    assert_eq!(t.to_iso8601(1), "2016-12-31T12:00:10.0");
+    // Crossing the leap second
+    let t = axis.pix2time(1.0 + 2.0 * 43190.0).unwrap();
+    assert_eq!(t.to_iso8601(1), "2016-12-31T23:59:60.0");
+    assert!((axis.time2pix(&t).unwrap() - (1.0 + 2.0 * 43190.0)).abs() < 1e-6);
+    let tt = t.to_scale(TimeScale::TT, axis.leap_seconds()).unwrap();
+    assert!((axis.time2pix(&tt).unwrap() - (1.0 + 2.0 * 43190.0)).abs() < 1e-6);
+    assert!((Time::from_mjd(57753.5, TimeScale::TT).jd() - 2457754.0).abs() < 1e-9);
+  }
+}
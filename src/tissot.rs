@@ -0,0 +1,188 @@
+//! Module containing the Tissot indicatrix, i.e. the local distortion characteristics of a
+//! projection at a given position.
+//!
+//! The indicatrix is the image, in the projection plane, of an infinitesimal circle of the unit
+//! sphere. It is computed from the Jacobian of the projection (see `Projection::proj_jacobian`)
+//! in the local (East, North) frame, so it is available for every projection, centered or not.
+//! The scale factors are in projection plane units per radian.
+
+use std::f64::consts::{PI, TAU};
+
+use crate::{LonLat, ProjXY, Projection, XYZ};
+use crate::sphere::tangent_frame;
+
+/// Tissot indicatrix of a projection at a given position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TissotIndicatrix {
+  /// Projection of the position.
+  center: ProjXY,
+  /// Derivatives `(dX, dY)` of the projection along the East unit vector.
+  east: (f64, f64),
+  /// Derivatives `(dX, dY)` of the projection along the North unit vector.
+  north: (f64, f64),
+  /// Semi-major axis.
+  a: f64,
+  /// Semi-minor axis.
+  b: f64,
+  /// Angle between the x-axis and the semi-major axis.
+  orientation: f64,
+}
+
+impl TissotIndicatrix {
+
+  /// Computes the Tissot indicatrix of the given projection at the given position.
+  /// # Return
+  /// * `None` if the position can't be projected or if the projection is not differentiable at
+  ///   this position
+  /// # Remark
+  /// * at the poles, the East and North directions are the limit of the ones along the `lon = 0`
+  ///   meridian.
+  pub fn from_xyz<P: Projection + ?Sized>(proj: &P, xyz: &XYZ) -> Option<Self> {
+    let center = proj.proj_xyz(xyz)?;
+    let j = proj.proj_jacobian(xyz)?;
+    let (e, n) = tangent_frame(xyz);
+    let apply = |t: &XYZ| (
+      j[0][0] * t.x + j[0][1] * t.y + j[0][2] * t.z,
+      j[1][0] * t.x + j[1][1] * t.y + j[1][2] * t.z,
+    );
+    let (east, north) = (apply(&e), apply(&n));
+    // Singular values and left singular vectors of the Jacobian J = [east, north]:
+    // eigen decomposition of J J^T = [[m11, m12], [m12, m22]]
+    let m11 = east.0 * east.0 + north.0 * north.0;
+    let m22 = east.1 * east.1 + north.1 * north.1;
+    let m12 = east.0 * east.1 + north.0 * north.1;
+    let det = (east.0 * north.1 - north.0 * east.1).abs();
+    let s = m11 + m22;
+    let d = ((s - 2.0 * det) * (s + 2.0 * det)).max(0.0).sqrt();
+    let a = (0.5 * (s + d)).sqrt();
+    let b = if a > 0.0 { det / a } else { 0.0 };
+    let orientation = (0.5 * (2.0 * m12).atan2(m11 - m22) + 0.5 * PI).rem_euclid(PI) - 0.5 * PI;
+    Some(Self { center, east, north, a, b, orientation })
+  }
+
+  /// Same as `from_xyz` with a position given in longitude and latitude.
+  pub fn from_lonlat<P: Projection + ?Sized>(proj: &P, lonlat: &LonLat) -> Option<Self> {
+    Self::from_xyz(proj, &lonlat.to_xyz())
+  }
+
+  /// Get the projection of the position
+  pub fn center(&self) -> &ProjXY {
+    &self.center
+  }
+
+  /// Get the scale factor `h` along the meridian
+  pub fn h(&self) -> f64 {
+    self.north.0.hypot(self.north.1)
+  }
+
+  /// Get the scale factor `k` along the parallel
+  pub fn k(&self) -> f64 {
+    self.east.0.hypot(self.east.1)
+  }
+
+  /// Get the semi-major axis `a` of the indicatrix, i.e. the maximum scale factor
+  pub fn a(&self) -> f64 {
+    self.a
+  }
+
+  /// Get the semi-minor axis `b` of the indicatrix, i.e. the minimum scale factor
+  pub fn b(&self) -> f64 {
+    self.b
+  }
+
+  /// Get the areal scale `s = ab`
+  pub fn areal_scale(&self) -> f64 {
+    self.a * self.b
+  }
+
+  /// Get the maximum angular deformation `omega = 2 asin((a - b) / (a + b))`, in radians
+  pub fn max_angular_deformation(&self) -> f64 {
+    if self.a > 0.0 {
+      2.0 * ((self.a - self.b) / (self.a + self.b)).asin()
+    } else {
+      0.0
+    }
+  }
+
+  /// Get the angle, in radians in `[-pi/2, pi/2[`, between the projection plane x-axis and the
+  /// semi-major axis of the indicatrix (counter-clockwise)
+  pub fn orientation(&self) -> f64 {
+    self.orientation
+  }
+
+  /// Returns the closed polyline, in the projection plane, approximating the indicatrix scaled so
+  /// that it is the (first order) projection of the small circle of the given radius.
+  /// # Params
+  /// * `radius`: angular radius of the small circle, in radians
+  /// * `n`: number of segments of the polyline (at least 3)
+  pub fn ellipse(&self, radius: f64, n: usize) -> Vec<ProjXY> {
+    let n = n.max(3);
+    (0..=n)
+      .map(|i| {
+        let (sint, cost) = (TAU * (i % n) as f64 / n as f64).sin_cos();
+        ProjXY::new(
+          self.center.x + radius * (cost * self.east.0 + sint * self.north.0),
+          self.center.y + radius * (cost * self.east.1 + sint * self.north.1),
+        )
+      })
+      .collect()
+  }
+}
+
+/// Returns the indicatrices, ready to be drawn, of the given projection at the given positions.
+/// Positions at which the indicatrix can't be computed are skipped.
+/// # Params
+/// * `proj`: the projection
+/// * `positions`: the centers of the indicatrices (e.g. the nodes of a graticule)
+/// * `radius`: angular radius of the small circles, in radians
+/// * `n`: number of segments of each polyline (see `TissotIndicatrix::ellipse`)
+pub fn indicatrix_ellipses<P: Projection + ?Sized>(
+  proj: &P,
+  positions: &[LonLat],
+  radius: f64,
+  n: usize
+) -> Vec<Vec<ProjXY>> {
+  positions.iter()
+    .filter_map(|lonlat| TissotIndicatrix::from_lonlat(proj, lonlat))
+    .map(|indicatrix| indicatrix.ellipse(radius, n))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::CenteredProjection;
+  use crate::cylindrical::{car::Car, mer::Mer};
+  use crate::zenithal::{stg::Stg, zea::Zea};
+
+  #[test]
+  fn test_tissot() {
+    let lonlat = LonLat::new(0.3, 40_f64.to_radians());
+    let cosb = lonlat.lat().cos();
+    // CAR: h = 1, k = 1 / cos(lat)
+    let t = TissotIndicatrix::from_lonlat(&Car::new(), &lonlat).unwrap();
+    assert!((t.h() - 1.0).abs() < 1e-9 && (t.k() - 1.0 / cosb).abs() < 1e-9);
+    assert!((t.a() - 1.0 / cosb).abs() < 1e-9 && (t.b() - 1.0).abs() < 1e-9);
+    assert!(t.orientation().abs() < 1e-9);
+    // MER is conformal: h = k = 1 / cos(lat)
+    let t = TissotIndicatrix::from_lonlat(&Mer::new(), &lonlat).unwrap();
+    assert!((t.h() - 1.0 / cosb).abs() < 1e-9 && (t.k() - 1.0 / cosb).abs() < 1e-9);
+    assert!(t.max_angular_deformation() < 1e-6);
+    // ZEA is equal-area, also when centered and rotated
+    let mut zea = CenteredProjection::new(Zea::new());
+    zea.set_proj_center_from_lonlat_and_positional_angle(&LonLat::new(1.0, 1.2), 0.4);
+    let t = TissotIndicatrix::from_lonlat(&zea, &lonlat).unwrap();
+    assert!((t.areal_scale() - 1.0).abs() < 1e-9 && t.max_angular_deformation() > 0.01);
+    // STG at a distance theta from the center: h = k = 2 / (1 + cos(theta))
+    let t = TissotIndicatrix::from_lonlat(&Stg::new(), &LonLat::new(0.0, 0.5)).unwrap();
+    assert!((t.h() - 2.0 / (1.0 + 0.5_f64.cos())).abs() < 1e-9 && (t.k() - t.h()).abs() < 1e-9);
+    // MER can't project the poles
+    assert!(TissotIndicatrix::from_lonlat(&Mer::new(), &LonLat::new(0.0, 0.5 * PI)).is_none());
+    let ellipses = indicatrix_ellipses(&Car::new(), &[lonlat.clone(), LonLat::new(1.0, 0.0)], 0.1, 8);
+    assert_eq!(ellipses.len(), 2);
+    assert_eq!(ellipses[0].len(), 9);
+    assert!((ellipses[0][0].x() - (lonlat.lon() + 0.1 / cosb)).abs() < 1e-9);
+    assert!((ellipses[0][2].y() - (lonlat.lat() + 0.1)).abs() < 1e-9);
+    assert!((ellipses[0][8].x() - ellipses[0][0].x()).abs() < 1e-12);
+  }
+}
@@ -3,6 +3,7 @@
 use std::f64::consts::PI;
 
 use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::zenithal::zenithal_unproj_jacobian;
 
 static HALF_PI: f64 = 0.5 * PI;
 
@@ -32,7 +33,7 @@ impl Air {
   
   /// # Params
   /// * `rho_b`: WCS parameter `PVi_2a` (but converted in radians);
-  ///            angular distance from the proj center at which error is minimized
+  ///   angular distance from the proj center at which error is minimized
   /// # Panics
   /// * if `rho_b` no in `]0, pi[`
   pub fn from_param(rho_b: f64) -> Self {
@@ -107,6 +108,14 @@ impl CanonicalProjection for Air {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    None // Infinite radius at rho = PI
+  }
+
+  fn is_in_valid_proj_area(&self, _pos: &ProjXY) -> bool {
+    true
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     let x_p_1 = xyz.x + 1.0;
@@ -134,6 +143,25 @@ impl CanonicalProjection for Air {
       Some(XYZ::new(x, pos.x * w, pos.y * w))
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // R(psi) = sin(psi) g(x), with x = cos(psi) and g(x) = ln((1 + x) / 2) / (x - 1) - cte_b / (1 + x)
+    // => dR/dpsi = x g(x) - (1 - x^2) g'(x)
+    let big_r = (pos.x.pow2() + pos.y.pow2()).sqrt();
+    if big_r < 1e-6 {
+      // Limit for psi -> 0: g(1) = (1 - cte_b) / 2
+      let dpsi_dr = 2.0 / (1.0 - self.cte_b);
+      Some(zenithal_unproj_jacobian(pos, 1.0, dpsi_dr, dpsi_dr))
+    } else {
+      let x = self.newton_solve(big_r);
+      let (x_p_1, x_m_1) = (1.0 + x, x - 1.0);
+      let ln = (0.5 * x_p_1).ln();
+      let g = ln / x_m_1 - self.cte_b / x_p_1;
+      let dg = (x_m_1 / x_p_1 - ln) / x_m_1.pow2() + self.cte_b / x_p_1.pow2();
+      let sin2_psi = 1.0 - x.pow2();
+      Some(zenithal_unproj_jacobian(pos, x, sin2_psi.sqrt() / big_r, 1.0 / (x * g - sin2_psi * dg)))
+    }
+  }
 }
 
    
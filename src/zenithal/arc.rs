@@ -1,7 +1,8 @@
 //! Zenithal (or azimuthal) equidistant projection.
 
 use std::f64::consts::PI;
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{ellipse_polyline, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::zenithal::zenithal_unproj_jacobian;
 
 /// Zenithal (or azimuthal) equidistant projection.
 pub struct Arc;
@@ -30,6 +31,14 @@ impl CanonicalProjection for Arc {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    Some(ellipse_polyline(0.0, 0.0, PI, PI, n))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    (pos.x.pow2() + pos.y.pow2()).sqrt() <= PI
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     if xyz.x > -1.0 {
@@ -58,5 +67,15 @@ impl CanonicalProjection for Arc {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // r = psi
+    let r = (pos.x.pow2() + pos.y.pow2()).sqrt();
+    if r <= PI {
+      Some(zenithal_unproj_jacobian(pos, r.cos(), r.sinc(), 1.0))
+    } else {
+      None
+    }
+  }
 }
 
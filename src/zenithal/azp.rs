@@ -2,7 +2,8 @@
 
 use std::f64::consts::PI;
 
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{closed_ccw_polyline, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::sphere::small_circle_points;
 
 static HALF_PI: f64 = 0.5 * PI;
 
@@ -105,6 +106,31 @@ impl CanonicalProjection for Azp {
   fn bounds(&self) -> &ProjBounds {
     &self.proj_bounds
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    if self.mu * self.cg > 1.0 {
+      // Projection of the small circle made of the points on which the projection rays are tangent
+      // to the sphere (i.e. x = -1 / mu)
+      let center = XYZ::new(-1.0, 0.0, 0.0);
+      Some(closed_ccw_polyline(
+        small_circle_points(&center, (1.0 / self.mu).acos(), n.max(3)).iter()
+          .map(|xyz| {
+            let wx = self.mu + xyz.x - xyz.z * self.tg;
+            ProjXY::new((xyz.y * self.m_p_1) / wx, (xyz.z * self.m_p_1) / (self.cg * wx))
+          })
+          .collect()
+      ))
+    } else {
+      None
+    }
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    let big_r = (pos.x.pow2() + (pos.y * self.cg).pow2()).sqrt();
+    let w = self.m_p_1 + (pos.y * self.sg);
+    big_r == 0.0 || w == 0.0
+      || !((self.abs_mu > 1.0 && (big_r * self.sqrt_mu2_m_1) > w) || (self.abs_mu < 1.0 && big_r > 1.0e9))
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     let wx = self.mu + xyz.x;
@@ -122,7 +148,9 @@ impl CanonicalProjection for Azp {
     let y2d_cg = pos.y * self.cg;
     let big_r = (pos.x.pow2() + y2d_cg.pow2()).sqrt();
     let w = self.m_p_1 + (pos.y * self.sg);
-    if big_r == 0.0 {
+    if !self.is_in_valid_proj_area(pos) {
+      None
+    } else if big_r == 0.0 {
       // Center of the projection
       Some(XYZ::new(1.0, 0.0, 0.0))
     } else if w == 0.0 {
@@ -130,9 +158,6 @@ impl CanonicalProjection for Azp {
       debug_assert!(self.abs_mu <= 1.0);
       let w = (1.0 - self.mu.pow2()).sqrt() / big_r;
       Some(XYZ::new(-self.mu, pos.x * w, y2d_cg * w))
-    } else if (self.abs_mu > 1.0 && (big_r * self.sqrt_mu2_m_1) > w)
-     || (self.abs_mu < 1.0 && big_r > 1.0e9) {
-      None
     } else {
       let w = big_r / w;
       let w1 = (w.pow2() + 1.0).sqrt();
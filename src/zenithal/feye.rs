@@ -1,6 +1,7 @@
 //! Fisheye projection.
 
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{ellipse_polyline, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::zenithal::zenithal_unproj_jacobian;
 
 const D_MAX: f64 = 1.6580627893946132; // 95.0_f64.to_radians();
 
@@ -31,6 +32,14 @@ impl CanonicalProjection for Feye {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    Some(ellipse_polyline(0.0, 0.0, D_MAX, D_MAX, n))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    (pos.x.pow2() + pos.y.pow2()).sqrt() <= D_MAX
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     // Distance in the Euclidean plane (yz).
@@ -59,4 +68,14 @@ impl CanonicalProjection for Feye {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // r = psi
+    let r = (pos.x.pow2() + pos.y.pow2()).sqrt();
+    if r <= D_MAX {
+      Some(zenithal_unproj_jacobian(pos, r.cos(), r.sinc(), 1.0))
+    } else {
+      None
+    }
+  }
 }
@@ -12,3 +12,25 @@ pub mod szp;
 pub mod tan;
 pub mod zea;
 pub mod zpn;
+
+use crate::{CustomFloat, ProjXY};
+
+/// Returns the Jacobian `d(x, y, z)/d(X, Y)` of the deprojection of a radially symmetric zenithal
+/// projection, in which a position at the distance `r` from the origin of the projection plane
+/// is deprojected at the angular distance `psi(r)` from the projection center.
+/// # Params
+/// * `pos`: the position in the projection plane
+/// * `cos_psi`: `cos(psi)`
+/// * `sin_psi_over_r`: `sin(psi) / r` (i.e. `dpsi/dr` for `r = 0`)
+/// * `dpsi_dr`: the derivative of `psi` with respect to `r`
+pub(crate) fn zenithal_unproj_jacobian(pos: &ProjXY, cos_psi: f64, sin_psi_over_r: f64, dpsi_dr: f64) -> [[f64; 2]; 3] {
+  let r = (pos.x.pow2() + pos.y.pow2()).sqrt();
+  // Unit vector along the radial direction
+  let (u, v) = if r == 0.0 { (1.0, 0.0) } else { (pos.x / r, pos.y / r) };
+  let (s, cd) = (sin_psi_over_r, cos_psi * dpsi_dr);
+  [
+    [-s * dpsi_dr * pos.x, -s * dpsi_dr * pos.y],
+    [cd * u * u + s * v * v, (cd - s) * u * v],
+    [(cd - s) * u * v, cd * v * v + s * u * u],
+  ]
+}
@@ -1,5 +1,6 @@
 //! North Celestial Pole orthographic projection.
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{ellipse_polyline, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::zenithal::zenithal_unproj_jacobian;
 
 /// North Celestial Pole orthographic projection.
 pub struct Ncp;
@@ -28,6 +29,14 @@ impl CanonicalProjection for Ncp {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    Some(ellipse_polyline(0.0, 0.0, 1.0, 1.0, n))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    pos.x.pow2() + pos.y.pow2() <= 1.0
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     if xyz.x >= 0.0 { // Front hemisphere
@@ -45,4 +54,15 @@ impl CanonicalProjection for Ncp {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // r = sin(psi), not differentiable at r = 1
+    let r2 = pos.x.pow2() + pos.y.pow2();
+    if r2 < 1.0 {
+      let cos_psi = (1.0 - r2).sqrt();
+      Some(zenithal_unproj_jacobian(pos, cos_psi, 1.0, 1.0 / cos_psi))
+    } else {
+      None
+    }
+  }
 }
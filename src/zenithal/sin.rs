@@ -1,6 +1,9 @@
 //! Orthographic projections.
 
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{closed_ccw_polyline, ellipse_polyline, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::zenithal::zenithal_unproj_jacobian;
+use crate::math::HALF_PI;
+use crate::sphere::small_circle_points;
 
 /// Orthographic projection.
 pub struct Sin;
@@ -29,6 +32,14 @@ impl CanonicalProjection for Sin {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    Some(ellipse_polyline(0.0, 0.0, 1.0, 1.0, n))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    pos.x.pow2() + pos.y.pow2() <= 1.0
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     if xyz.x >= 0.0 {
@@ -47,6 +58,17 @@ impl CanonicalProjection for Sin {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // r = sin(psi), not differentiable at r = 1
+    let r2 = pos.x.pow2() + pos.y.pow2();
+    if r2 < 1.0 {
+      let cos_psi = (1.0 - r2).sqrt();
+      Some(zenithal_unproj_jacobian(pos, cos_psi, 1.0, 1.0 / cos_psi))
+    } else {
+      None
+    }
+  }
 }
 
 /// Slant Orthographic projection.
@@ -82,6 +104,16 @@ impl SinSlant {
       )
     }
   }
+
+  /// Returns the squared distance between the given point of the projection plane (`x = 1`) and
+  /// the projection axis (the line going through the sphere center, along the projection direction).
+  /// The point has a valid deprojection if the distance is lower than 1.
+  fn dist2_to_proj_axis(&self, pos: &ProjXY) -> f64 {
+    let s = self.xp + pos.x * self.yp + pos.y * self.zp;
+    (1.0 - self.xp * s).pow2()
+      + (pos.x - self.yp * s).pow2()
+      + (pos.y - self.zp * s).pow2()
+  }
 }
 
 impl CanonicalProjection for SinSlant {
@@ -92,6 +124,23 @@ impl CanonicalProjection for SinSlant {
   fn bounds(&self) -> &ProjBounds {
     &self.proj_bounds
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    // Projection of the great circle perpendicular to the projection direction
+    let p = XYZ::new(self.xp, self.yp, self.zp);
+    Some(closed_ccw_polyline(
+      small_circle_points(&p, HALF_PI, n.max(3)).iter()
+        .map(|xyz| {
+          let s = 1.0 - xyz.x;
+          ProjXY::new(xyz.y + self.xi * s, xyz.z + self.eta * s)
+        })
+        .collect()
+    ))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    self.dist2_to_proj_axis(pos) < 1.0
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     let s = xyz.x * self.xp + xyz.y * self.yp + xyz.z * self.zp;
@@ -107,16 +156,13 @@ impl CanonicalProjection for SinSlant {
     let x2d = pos.x;
     let y2d = pos.y;
     let r2 = x2d.pow2() + y2d.pow2();
-    let s = self.xp + x2d * self.yp + y2d * self.yp;
-    let v = (1.0 - self.xp * s).pow2()
-      + (x2d - self.yp * s).pow2()
-      + (y2d - self.zp * s).pow2();
-    if v < 1.0 {
+    if self.dist2_to_proj_axis(pos) < 1.0 {
       let rp = self.xi * x2d + self.eta * y2d; // R'
       let a = 1.0 + self.tg2;
       let b = 2.0 * (rp - self.tg2);
       let c = r2 - 2.0 * rp + self.tg2 - 1.0;
-      let x = (-b + (b.pow2() - 4.0 * a * c).sqrt()) / (2.0 * a);
+      // max(0) to avoid NaN due to numerical approximations close to the boundary
+      let x = (-b + (b.pow2() - 4.0 * a * c).max(0.0).sqrt()) / (2.0 * a);
       let tmp = 1.0 - x;
       Some(XYZ::new(x, x2d - self.xi * tmp, y2d - self.eta * tmp))
     } else {
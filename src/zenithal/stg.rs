@@ -1,6 +1,7 @@
 //! Stereographic projection.
 
 use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::zenithal::zenithal_unproj_jacobian;
 
 /// Stereographic projection.
 pub struct Stg;
@@ -29,6 +30,14 @@ impl CanonicalProjection for Stg {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    None
+  }
+
+  fn is_in_valid_proj_area(&self, _pos: &ProjXY) -> bool {
+    true
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     // All positions are valid, but diverges at lat = -PI/2
@@ -46,4 +55,11 @@ impl CanonicalProjection for Stg {
     let w = 1.0 + r;
     Some(XYZ::new((1.0 - r)  /w,  pos.x / w,  pos.y / w))
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // r = 2 tan(psi / 2)
+    let r = 0.25 * (pos.x.pow2() + pos.y.pow2());
+    let w = 1.0 + r;
+    Some(zenithal_unproj_jacobian(pos, (1.0 - r) / w, 1.0 / w, 1.0 / w))
+  }
 }
@@ -2,7 +2,8 @@
 
 use std::f64::consts::PI;
 
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{closed_ccw_polyline, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::sphere::small_circle_points;
 
 static HALF_PI: f64 = 0.5 * PI;
 static EPSILON: f64 = 1e-15;
@@ -127,6 +128,36 @@ impl CanonicalProjection for Szp {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    if self.xp.abs() > 1.0 {
+      // Projection of the small circle made of the points on which the projection rays are tangent
+      // to the sphere (i.e. x.xp + y.yp + z.zp = 1)
+      let center = XYZ::new(self.xp / self.abs_mu, self.yp / self.abs_mu, self.zp / self.abs_mu);
+      Some(closed_ccw_polyline(
+        small_circle_points(&center, (1.0 / self.abs_mu).acos(), n.max(3)).iter()
+          .map(|xyz| {
+            let o_m_x = 1.0 - xyz.x;
+            let d = xyz.x - self.xp;
+            ProjXY::new(
+              (self.o_m_xp * xyz.y - self.yp * o_m_x) / d,
+              (self.o_m_xp * xyz.z - self.zp * o_m_x) / d
+            )
+          })
+          .collect()
+      ))
+    } else {
+      None
+    }
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    let tx = pos.x - self.yp; // X - yp
+    let ty = pos.y - self.zp; // Y - zp
+    let t = self.xp * self.o_m_xp + self.yp * tx + self.zp * ty;
+    // The projection ray crosses the sphere
+    t.pow2() - (self.o_m_xp_2 + tx.pow2() + ty.pow2()) * self.mu2_m_1 > EPSILON
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     if self.is_in_proj_bounds(xyz) {
@@ -144,11 +175,8 @@ impl CanonicalProjection for Szp {
   fn unproj(&self, pos: &ProjXY) -> Option<XYZ> {
     let tx = pos.x - self.yp; // X - yp
     let ty = pos.y - self.zp; // Y - zp
-    let tx2 = tx.pow2();
-    let ty2 = ty.pow2();
-    let t = self.xp * self.o_m_xp + self.yp * tx + self.zp * ty;
     // Check if X and Y are in the bounds of the projection area
-    if t.pow2() - (self.o_m_xp_2 + tx2 + ty2) * self.mu2_m_1 <= EPSILON {
+    if !self.is_in_valid_proj_area(pos) {
       None
     } else {
       // Start the deprojection
@@ -156,17 +184,13 @@ impl CanonicalProjection for Szp {
       let ty = ty / self.o_m_xp;
       let txp = tx * self.xp - self.yp;
       let typ = ty * self.xp - self.zp;
-      let a = tx2 + ty2 + 1.0;
+      let a = tx.pow2() + ty.pow2() + 1.0;
       let b = -(tx * txp + ty * typ);
       let c = txp.pow2() + typ.pow2() - 1.0;
-      let x = (-b + (b.pow2() - a * c).sqrt()) / a;
-      // Double check (should be useless!)
-      if self.abs_mu > 1.0 && (!x.is_finite() || !(-1.0..=1.0).contains(&x)) {
-        None
-      } else {
-        let x_m_xp = x - self.xp;
-        Some(XYZ::new_renorming_if_necessary(x, tx * x_m_xp + self.yp, ty * x_m_xp + self.zp)) // new_renorming_if_necessary
-      }
+      // max(0) and clamp to avoid NaN due to numerical approximations close to the boundary
+      let x = ((-b + (b.pow2() - a * c).max(0.0).sqrt()) / a).clamp(-1.0, 1.0);
+      let x_m_xp = x - self.xp;
+      Some(XYZ::new_renorming_if_necessary(x, tx * x_m_xp + self.yp, ty * x_m_xp + self.zp)) // new_renorming_if_necessary
     }
   }
 }
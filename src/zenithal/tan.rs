@@ -1,6 +1,7 @@
 //! Gnomonic projection.
 
 use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::zenithal::zenithal_unproj_jacobian;
 
 /// Gnomonic projection.
 pub struct Tan;
@@ -29,6 +30,14 @@ impl CanonicalProjection for Tan {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, _n: usize) -> Option<Vec<ProjXY>> {
+    None
+  }
+
+  fn is_in_valid_proj_area(&self, _pos: &ProjXY) -> bool {
+    true
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     if xyz.x > 0.0 { // EPSILON ??
@@ -42,4 +51,10 @@ impl CanonicalProjection for Tan {
     let x = 1_f64 / (1_f64 + pos.x.pow2() + pos.y.pow2()).sqrt();
     Some(XYZ::new(x, pos.x * x, pos.y * x))
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // r = tan(psi)
+    let cos_psi = 1_f64 / (1_f64 + pos.x.pow2() + pos.y.pow2()).sqrt();
+    Some(zenithal_unproj_jacobian(pos, cos_psi, cos_psi, cos_psi.pow2()))
+  }
 }
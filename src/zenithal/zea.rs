@@ -1,6 +1,7 @@
 //! Lambert's zenithal (or azimuthal) equal area projection.
 
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{ellipse_polyline, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::zenithal::zenithal_unproj_jacobian;
 
 /// Lambert's zenithal (or azimuthal) equal area projection.
 pub struct Zea;
@@ -29,6 +30,14 @@ impl CanonicalProjection for Zea {
     );
     &PROJ_BOUNDS
   }
+
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    Some(ellipse_polyline(0.0, 0.0, 2.0, 2.0, n))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    0.25 * (pos.x.pow2() + pos.y.pow2()) <= 1.0
+  }
   
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     // Whole sphere, r <= 2 (equal area)
@@ -50,4 +59,15 @@ impl CanonicalProjection for Zea {
       None
     }
   }
+
+  fn unproj_jacobian(&self, pos: &ProjXY) -> Option<[[f64; 2]; 3]> {
+    // r = 2 sin(psi / 2), not differentiable at r = 2
+    let r = 0.25 * (pos.x.pow2() + pos.y.pow2());
+    if r < 1.0 {
+      let w = (1.0 - r).sqrt();
+      Some(zenithal_unproj_jacobian(pos, 1.0 - r.twice(), w, 1.0 / w))
+    } else {
+      None
+    }
+  }
 }
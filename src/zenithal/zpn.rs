@@ -5,7 +5,7 @@ use std::{
   f64::consts::PI,
 };
 
-use crate::{CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
+use crate::{ellipse_polyline, CanonicalProjection, CustomFloat, ProjBounds, ProjXY, XYZ};
 
 /// Zenithal polynomlial projection.
 #[derive(Debug, Clone)]
@@ -45,10 +45,10 @@ impl Zpn {
   /// # Params
   /// * `coeffs`: polynomial coefficients provided by keywords PVi_1a, PVi_2a, ..., PVi_na
   /// * `domain_step`: step used to determine the domain of validity of the function
-  ///                  default value: 1 arcmin converted in radians
+  ///   default value: 1 arcmin converted in radians
   /// * `domain_eps`: epsilon used in dichotomy to end the convergence process
-  ///                 default value: 1 mas converted into radians
-  ///                 (this value is also assigned to the `epsilon` used in deprojection.
+  ///   default value: 1 mas converted into radians
+  ///   (this value is also assigned to the `epsilon` used in deprojection.
   /// # Return
   /// * `None` if negative polynomial en `[0, pi]`
   pub fn from_params_custom(coeffs: Vec<f64>, domain_step: f64, domain_eps: f64) -> Option<Self> {
@@ -278,6 +278,16 @@ impl CanonicalProjection for Zpn {
     &self.proj_bounds
   }
 
+  fn boundary(&self, n: usize) -> Option<Vec<ProjXY>> {
+    let r = *self.euc_dist.end();
+    Some(ellipse_polyline(0.0, 0.0, r, r, n))
+  }
+
+  fn is_in_valid_proj_area(&self, pos: &ProjXY) -> bool {
+    let r = (pos.x.pow2() + pos.y.pow2()).sqrt();
+    r == 0.0 || self.euc_dist.contains(&r)
+  }
+
   fn proj(&self, xyz: &XYZ) -> Option<ProjXY> {
     let r = (xyz.y.pow2() + xyz.z.pow2()).sqrt();
     // Compute the angular distance from the projection center.
@@ -3,7 +3,7 @@ use std::f64::consts::PI;
 use mapproj::{
   self, CustomFloat,
   LonLat, ProjXY,
-  Projection,
+  Projection, CenteredProjection,
   conic::{
     cod::Cod,
     coe::Coe,
@@ -29,7 +29,7 @@ use mapproj::{
     azp::Azp,
     feye::Feye,
     ncp::Ncp,
-    sin::Sin,
+    sin::{Sin, SinSlant},
     stg::Stg,
     szp::Szp,
     tan::Tan,
@@ -38,12 +38,14 @@ use mapproj::{
   }
 };
 
+#[allow(dead_code)]
 struct Point {
   color: f64,
   coo: LonLat,
   xy: ProjXY,
 }
 
+#[allow(dead_code)]
 impl Point {
 
   fn new(lonlat: LonLat) -> Self {
@@ -102,6 +104,7 @@ fn gen_coo() -> Vec<LonLat> {
   coos
 }
 
+#[allow(dead_code)]
 fn gen_points() -> Vec<Point> {
   gen_coo()
     .drain(..)
@@ -144,7 +147,7 @@ fn test_canonical_back_and_forth<T: Projection>(proj: T, precision_rad: f64) {
 
 #[test]
 fn test_canonicals_back_and_forth() {
-  let mas_in_rad = (1.0 / 3600_000.0_f64).to_radians();
+  let mas_in_rad = (1.0 / 3_600_000.0_f64).to_radians();
   // Conic
   test_canonical_back_and_forth(Cod::new(), mas_in_rad / 1000.0);        //  1 uas
   test_canonical_back_and_forth(Coe::from_params((-20.0 - 70.0).half().to_radians(), (-20_f64 + 70.0).abs().half().to_radians()), 10.0 * mas_in_rad);        //  10 mas
@@ -175,4 +178,244 @@ fn test_canonicals_back_and_forth() {
   test_canonical_back_and_forth(Zea::new(), 2.0 * mas_in_rad);           //  2 mas
   test_canonical_back_and_forth(Zpn::from_params(vec![0.0, 1.0, 0.0, -50.0]).unwrap(), 10.0 * mas_in_rad); // 10 mas
   test_canonical_back_and_forth(Zpn::from_params(vec![0.050, 0.975, -0.807, 0.337, -0.065, 0.010, 0.003, -0.001]).unwrap(), 10.0 * mas_in_rad); // 10 mas
-}
\ No newline at end of file
+}
+
+/// Checks that the positions around the `(lon=0, lat=0)` direction are deprojected back to the
+/// original position.
+fn test_round_trip_around_origin<T: Projection>(proj: T, precision_rad: f64) {
+  let mut n = 0;
+  for lon in (-40..=40).step_by(10) {
+    for lat in (-40..=40).step_by(10) {
+      let coo = LonLat::new((lon as f64).to_radians().rem_euclid(PI.twice()), (lat as f64).to_radians());
+      if let Some(xy) = proj.proj_lonlat(&coo) {
+        let coo2 = proj.unproj_lonlat(&xy)
+          .unwrap_or_else(|| panic!("lon: {}; lat: {}; x: {}; y: {}", lon, lat, xy.x(), xy.y()));
+        assert!(coo.haversine_dist(&coo2) < precision_rad, "lon: {}; lat: {}", lon, lat);
+        n += 1;
+      }
+    }
+  }
+  assert!(n > 0);
+}
+
+#[test]
+fn test_sin_slant_unproj() {
+  let mas_in_rad = (1.0 / 3_600_000.0_f64).to_radians();
+  test_round_trip_around_origin(SinSlant::new(0.0, 0.5), mas_in_rad);
+  test_round_trip_around_origin(SinSlant::new(0.3, -0.4), mas_in_rad);
+}
+
+#[test]
+fn test_szp_unproj() {
+  let mas_in_rad = (1.0 / 3_600_000.0_f64).to_radians();
+  test_round_trip_around_origin(Szp::from_params(2.0, PI, 60_f64.to_radians()), mas_in_rad);
+  test_round_trip_around_origin(Szp::from_params(-0.5, 0.5 * PI, 30_f64.to_radians()), mas_in_rad);
+}
+
+/// Checks that the boundary is a closed counter-clockwise polyline, the points just inside
+/// (resp. outside) of each segment being (resp. not being) in the valid projection area.
+fn test_boundary<T: Projection>(proj: T) {
+  const EPS: f64 = 1.0e-3;
+  let boundary = proj.boundary(1000).unwrap();
+  assert_eq!(boundary.first(), boundary.last());
+  let twice_area: f64 = boundary.windows(2).map(|w| w[0].x() * w[1].y() - w[1].x() * w[0].y()).sum();
+  assert!(twice_area > 0.0, "{}", proj.short_name());
+  for w in boundary.windows(2) {
+    let (dx, dy) = (w[1].x() - w[0].x(), w[1].y() - w[0].y());
+    let d = dx.hypot(dy);
+    if d < 10.0 * EPS {
+      continue;
+    }
+    // Outward normal of a counter-clockwise polyline
+    let (nx, ny) = (dy / d, -dx / d);
+    let (mx, my) = ((w[0].x() + w[1].x()).half(), (w[0].y() + w[1].y()).half());
+    let inside = ProjXY::new(mx - EPS * nx, my - EPS * ny);
+    let outside = ProjXY::new(mx + EPS * nx, my + EPS * ny);
+    assert!(proj.unproj_xyz(&inside).is_some(), "{}: {:?}", proj.short_name(), inside);
+    assert!(proj.unproj_xyz(&outside).is_none(), "{}: {:?}", proj.short_name(), outside);
+  }
+}
+
+#[test]
+fn test_boundaries() {
+  // Conic
+  test_boundary(Cod::new());
+  test_boundary(Coe::from_params((-20.0 - 70.0).half().to_radians(), (-20_f64 + 70.0).abs().half().to_radians()));
+  assert!(Coo::new().boundary(100).is_none());
+  assert!(Cop::new().boundary(100).is_none());
+  // Cylindrical
+  test_boundary(Car::new());
+  test_boundary(Cea::from_param(0.5));
+  test_boundary(Cyp::new());
+  assert!(Mer::new().boundary(100).is_none());
+  // Hybrid
+  test_boundary(Hpx::new());
+  // Pseudo-Cylindrical
+  test_boundary(Ait::new());
+  test_boundary(Mol::new());
+  test_boundary(Par::new());
+  test_boundary(Sfl::new());
+  // Zenithal
+  assert!(Air::new().boundary(100).is_none());
+  test_boundary(Arc::new());
+  test_boundary(Azp::new());
+  test_boundary(Azp::from_params(2.0, 30_f64.to_radians()));
+  assert!(Azp::from_params(0.5, 0.0).boundary(100).is_none());
+  test_boundary(Feye::new());
+  test_boundary(Ncp::new());
+  test_boundary(Sin::new());
+  test_boundary(SinSlant::new(0.2, -0.1));
+  assert!(Stg::new().boundary(100).is_none());
+  test_boundary(Szp::from_params(2.0, 30_f64.to_radians(), 60_f64.to_radians()));
+  assert!(Szp::new().boundary(100).is_none());
+  assert!(Tan::new().boundary(100).is_none());
+  test_boundary(Zea::new());
+  test_boundary(Zpn::from_params(vec![0.0, 1.0, 0.0, -50.0]).unwrap());
+  test_boundary(Zpn::from_params(vec![0.050, 0.975, -0.807, 0.337, -0.065, 0.010, 0.003, -0.001]).unwrap());
+}
+
+/// Checks that `is_in_valid_proj_area` agrees with `unproj` on a dense grid covering the projection
+/// bounds, and on (and around) the points of the projection boundary.
+fn test_valid_area<T: Projection>(proj: T) {
+  const N: usize = 400;
+  const EPS: f64 = 1.0e-9;
+  let range = |bounds: &Option<std::ops::RangeInclusive<f64>>| match bounds {
+    Some(r) => (1.1 * *r.start(), 1.1 * *r.end()),
+    None => (-5.0, 5.0),
+  };
+  let (xmin, xmax) = range(proj.bounds().x_bounds());
+  let (ymin, ymax) = range(proj.bounds().y_bounds());
+  let mut points: Vec<ProjXY> = (0..=N)
+    .flat_map(|i| (0..=N).map(move |j| ProjXY::new(
+      xmin + (xmax - xmin) * i as f64 / N as f64,
+      ymin + (ymax - ymin) * j as f64 / N as f64
+    )))
+    .collect();
+  for p in proj.boundary(500).unwrap_or_default() {
+    for (dx, dy) in [(0.0, 0.0), (-EPS, 0.0), (EPS, 0.0), (0.0, -EPS), (0.0, EPS)] {
+      points.push(ProjXY::new(p.x() + dx, p.y() + dy));
+    }
+  }
+  for p in points {
+    assert_eq!(proj.is_in_valid_proj_area(&p), proj.unproj_xyz(&p).is_some(), "{}: {:?}", proj.short_name(), p);
+  }
+}
+
+#[test]
+fn test_valid_areas() {
+  // Conic
+  test_valid_area(Cod::new());
+  test_valid_area(Coe::from_params((-20.0 - 70.0).half().to_radians(), (-20_f64 + 70.0).abs().half().to_radians()));
+  test_valid_area(Coo::from_params((20.0 + 70.0).half().to_radians(), (20_f64 - 70.0).abs().half().to_radians()));
+  test_valid_area(Cop::from_params((20.0 + 70.0).half().to_radians(), (20_f64 - 70.0).abs().half().to_radians()));
+  // Cylindrical
+  test_valid_area(Car::new());
+  test_valid_area(Cea::from_param(0.5));
+  test_valid_area(Cyp::new());
+  test_valid_area(Mer::new());
+  // Hybrid
+  test_valid_area(Hpx::new());
+  // Pseudo-Cylindrical
+  test_valid_area(Ait::new());
+  test_valid_area(Mol::new());
+  test_valid_area(Par::new());
+  test_valid_area(Sfl::new());
+  // Zenithal
+  test_valid_area(Air::new());
+  test_valid_area(Arc::new());
+  test_valid_area(Azp::new());
+  test_valid_area(Azp::from_params(2.0, 30_f64.to_radians()));
+  test_valid_area(Azp::from_params(0.5, 0.0));
+  test_valid_area(Feye::new());
+  test_valid_area(Ncp::new());
+  test_valid_area(Sin::new());
+  test_valid_area(SinSlant::new(0.2, -0.1));
+  test_valid_area(Stg::new());
+  test_valid_area(Szp::new());
+  test_valid_area(Szp::from_params(2.0, 30_f64.to_radians(), 60_f64.to_radians()));
+  test_valid_area(Tan::new());
+  test_valid_area(Zea::new());
+  test_valid_area(Zpn::from_params(vec![0.0, 1.0, 0.0, -50.0]).unwrap());
+  test_valid_area(Zpn::from_params(vec![0.050, 0.975, -0.807, 0.337, -0.065, 0.010, 0.003, -0.001]).unwrap());
+}
+
+/// Checks the Jacobian of `unproj` against finite differences, and that the Jacobian of `proj`
+/// is its inverse, on a grid of positions.
+fn test_jacobian<T: Projection>(proj: T) {
+  const H: f64 = 1e-6;
+  for i in 0..35 {
+    for j in 0..16 {
+      let lonlat = LonLat::new((-172.0 + 10.0 * i as f64).to_radians(), (-77.0 + 10.0 * j as f64).to_radians());
+      let pos = match proj.proj_lonlat(&lonlat) {
+        Some(pos) => pos,
+        None => continue,
+      };
+      let fd = |dx: f64, dy: f64| -> Option<[f64; 3]> {
+        let plus = proj.unproj_xyz(&ProjXY::new(pos.x() + dx, pos.y() + dy))?;
+        let minus = proj.unproj_xyz(&ProjXY::new(pos.x() - dx, pos.y() - dy))?;
+        Some([(plus.x() - minus.x()) / (2.0 * H), (plus.y() - minus.y()) / (2.0 * H), (plus.z() - minus.z()) / (2.0 * H)])
+      };
+      let (dx, dy) = match (fd(H, 0.0), fd(0.0, H)) {
+        (Some(dx), Some(dy)) => (dx, dy),
+        _ => continue,
+      };
+      let ju = proj.unproj_jacobian(&pos)
+        .unwrap_or_else(|| panic!("{}: no Jacobian at {:?}", proj.short_name(), lonlat));
+      // Finite differences are not accurate enough close to the singularities
+      if ju.iter().flatten().any(|v| v.abs() > 10.0) {
+        continue;
+      }
+      for k in 0..3 {
+        let tol = 1e-5 * (1.0 + dx[k].abs() + dy[k].abs());
+        assert!((ju[k][0] - dx[k]).abs() < tol && (ju[k][1] - dy[k]).abs() < tol,
+          "{}: {:?}; analytic: {:?}; numeric: {:?} {:?}", proj.short_name(), lonlat, ju, dx, dy);
+      }
+      let jp = proj.proj_jacobian(&lonlat.to_xyz()).unwrap();
+      for (a, row) in jp.iter().enumerate() {
+        for b in 0..2 {
+          let v: f64 = row.iter().zip(ju.iter()).map(|(r, u)| r * u[b]).sum();
+          let expected = if a == b { 1.0 } else { 0.0 };
+          assert!((v - expected).abs() < 1e-8, "{}: {:?}; J_proj J_unproj: {}", proj.short_name(), lonlat, v);
+        }
+      }
+    }
+  }
+}
+
+#[test]
+fn test_jacobians() {
+  // Conic
+  test_jacobian(Cod::new());
+  test_jacobian(Coe::from_params((-20.0 - 70.0).half().to_radians(), (-20_f64 + 70.0).abs().half().to_radians()));
+  test_jacobian(Coo::from_params((20.0 + 70.0).half().to_radians(), (20_f64 - 70.0).abs().half().to_radians()));
+  test_jacobian(Cop::from_params((20.0 + 70.0).half().to_radians(), (20_f64 - 70.0).abs().half().to_radians()));
+  // Cylindrical
+  test_jacobian(Car::new());
+  test_jacobian(Cea::from_param(0.5));
+  test_jacobian(Cyp::new());
+  test_jacobian(Mer::new());
+  // Hybrid
+  test_jacobian(Hpx::new());
+  // Pseudo-Cylindrical
+  test_jacobian(Ait::new());
+  test_jacobian(Mol::new());
+  test_jacobian(Par::new());
+  test_jacobian(Sfl::new());
+  // Zenithal
+  test_jacobian(Air::new());
+  test_jacobian(Arc::new());
+  test_jacobian(Azp::from_params(2.0, 30_f64.to_radians()));
+  test_jacobian(Feye::new());
+  test_jacobian(Ncp::new());
+  test_jacobian(Sin::new());
+  test_jacobian(SinSlant::new(0.2, -0.1));
+  test_jacobian(Stg::new());
+  test_jacobian(Szp::from_params(2.0, 30_f64.to_radians(), 60_f64.to_radians()));
+  test_jacobian(Tan::new());
+  test_jacobian(Zea::new());
+  test_jacobian(Zpn::from_params(vec![0.050, 0.975, -0.807, 0.337, -0.065, 0.010, 0.003, -0.001]).unwrap());
+  // Centered and rotated
+  let mut proj = CenteredProjection::new(Ait::new());
+  proj.set_proj_center_from_lonlat_and_positional_angle(&LonLat::new(1.0, 0.5), 0.3);
+  test_jacobian(proj);
+}
@@ -33,7 +33,7 @@ fn test_sin() {
   let cd22 =  2.77777773495436e-4_f64;
   
   // Set the projection
-  let mut proj = CenteredProjection::new(Sin::default());
+  let mut proj = CenteredProjection::new(Sin::new());
   let proj_center = LonLat::new(crval1.to_radians(), crval2.to_radians());
   proj.set_proj_center_from_lonlat(&proj_center);
   let img2proj = WcsImgXY2ProjXY::from_cd(crpix1, crpix2, cd11, 0.0, 0.0, cd22);
@@ -50,5 +50,9 @@ fn test_sin() {
   let img_coo_input = img2lonlat.lonlat2img(&lonlat).unwrap();
   assert!((img_coo_input.x() - img_coo_input.x()).abs() < 1e-14);
   assert!((img_coo_input.y() - img_coo_input.y()).abs() < 1e-14);
-  
+  // Jacobian at the projection center: CD matrix, with the cos(dec) factor on the longitude
+  let j = img2lonlat.img2lonlat_jacobian(&ImgXY::new(crpix1, crpix2)).unwrap();
+  assert!((j[0][0] - cd11.to_radians() / crval2.to_radians().cos()).abs() < 1e-14);
+  assert!((j[1][1] - cd22.to_radians()).abs() < 1e-14);
+  assert!(j[0][1].abs() < 1e-14 && j[1][0].abs() < 1e-14);
 }
\ No newline at end of file